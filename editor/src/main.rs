@@ -2,6 +2,9 @@
 use fyrox::event_loop::EventLoop;
 use fyroxed_base::{Editor, StartupData};
 use mine64::GameConstructor;
+use voxels::VoxelPainter;
+
+mod voxels;
 
 fn main() {
     let event_loop = EventLoop::new().unwrap();
@@ -13,5 +16,6 @@ fn main() {
         }),
     );
     editor.add_game_plugin(GameConstructor);
+    editor.add_editor_plugin(VoxelPainter::new());
     editor.run(event_loop)
 }
@@ -0,0 +1,429 @@
+//! Voxel painting in the scene editor.
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use fyrox::core::algebra::Vector2;
+use fyrox::core::log::Log;
+use fyrox::core::pool::Handle;
+use fyrox::gui::button::{ButtonBuilder, ButtonMessage};
+use fyrox::gui::message::{MessageDirection, MouseButton, UiMessage};
+use fyrox::gui::stack_panel::StackPanelBuilder;
+use fyrox::gui::text::{TextBuilder, TextMessage};
+use fyrox::gui::widget::{WidgetBuilder, WidgetMessage};
+use fyrox::gui::window::{WindowBuilder, WindowMessage, WindowTitle};
+use fyrox::gui::{BuildContext, Orientation, Thickness, UiNode, UserInterface};
+use fyrox::scene::camera::Camera;
+use fyrox::scene::graph::Graph;
+use fyrox::scene::node::Node;
+use fyrox::scene::Scene;
+use fyroxed_base::message::Message;
+use fyroxed_base::scene::commands::{GameSceneCommandTrait, GameSceneContext};
+use fyroxed_base::scene::GameScene;
+use fyroxed_base::{Editor, EditorPlugin};
+use mine64::block::Block;
+use mine64::brush::{apply_edits, pick, BlockEdit, Brush, BrushTool, MAX_RADIUS};
+use mine64::scene_voxels::{build_voxel_mesh, empty_volume, voxels_path};
+use mine64::schematic::Schematic;
+
+/// Name of the mesh node that shows the voxels in the scene.
+const PREVIEW_NAME: &str = "__VoxelPreview";
+
+/// Farthest a click picks a block.
+const MAX_PICK_DISTANCE: f32 = 500.0;
+
+const BUTTON_HEIGHT: f32 = 24.0;
+
+/// Blocks painted in a scene, kept as a schematic next to the scene file that the game shows
+/// with the scene, and here with the mesher of the game.
+///
+/// The file is only written when the scene is saved, and the preview hangs under the editor
+/// objects of the scene so it is never saved with it.
+#[derive(Debug)]
+struct VoxelVolume {
+    schematic: Schematic,
+    /// File the voxels were read from or last saved to, `None` until a new scene is saved.
+    path: Option<PathBuf>,
+    scene: Handle<Scene>,
+    /// Editor objects of the scene, the preview is linked to it.
+    root: Handle<Node>,
+    preview: Handle<Node>,
+    /// Changed since it was read from or written to `path`.
+    dirty: bool,
+}
+
+/// Reads the voxels in the file, an empty volume when there is none.
+fn read_volume(path: &Path) -> Schematic {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == ErrorKind::NotFound => return empty_volume(),
+        Err(error) => {
+            Log::err(format!("Failed to read voxels from {path:?}: {error}"));
+            return empty_volume();
+        }
+    };
+    Schematic::decode(&bytes).unwrap_or_else(|error| {
+        Log::err(format!("Failed to read voxels from {path:?}: {error}"));
+        empty_volume()
+    })
+}
+
+impl VoxelVolume {
+    /// Reads the voxels of the scene, an empty volume when it has none yet.
+    fn load(
+        scene_path: Option<&Path>,
+        scene: Handle<Scene>,
+        root: Handle<Node>,
+        graph: &mut Graph,
+    ) -> Self {
+        let path = scene_path.map(voxels_path);
+        let schematic = path.as_deref().map_or_else(empty_volume, read_volume);
+        // Scenes saved by earlier versions of the painter kept the preview, it is shown anew.
+        while let Some((preview, _)) = graph.find_by_name_from_root(PREVIEW_NAME) {
+            graph.remove_node(preview);
+        }
+        let mut volume = Self {
+            schematic,
+            path,
+            scene,
+            root,
+            preview: Handle::NONE,
+            dirty: false,
+        };
+        volume.rebuild_preview(graph);
+        volume
+    }
+
+    fn rebuild_preview(&mut self, graph: &mut Graph) {
+        if graph.is_valid_handle(self.preview) {
+            graph.remove_node(self.preview);
+        }
+        self.preview = build_voxel_mesh(graph, &self.schematic, PREVIEW_NAME);
+        if self.preview.is_some() {
+            graph.link_nodes(self.preview, self.root);
+        }
+    }
+
+    fn apply(&mut self, graph: &mut Graph, edits: &[BlockEdit], undo: bool) {
+        apply_edits(&mut self.schematic, edits, undo);
+        self.dirty = true;
+        self.rebuild_preview(graph);
+    }
+
+    /// Writes the voxels next to the scene saved at `scene_path`, when they changed or the scene
+    /// was saved somewhere else.
+    fn save(&mut self, scene_path: &Path) {
+        let path = voxels_path(scene_path);
+        if !self.dirty && self.path.as_ref() == Some(&path) {
+            return;
+        }
+        if let Err(error) = fs::write(&path, self.schematic.encode()) {
+            Log::err(format!("Failed to save voxels to {path:?}: {error}"));
+            return;
+        }
+        self.path = Some(path);
+        self.dirty = false;
+    }
+}
+
+type SharedVolume = Arc<Mutex<VoxelVolume>>;
+
+fn lock(volume: &SharedVolume) -> MutexGuard<'_, VoxelVolume> {
+    volume.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// One click of the brush, on the command stack of the editor so it can be undone.
+#[derive(Debug)]
+struct PaintCommand {
+    volume: SharedVolume,
+    tool: BrushTool,
+    edits: Vec<BlockEdit>,
+}
+
+impl GameSceneCommandTrait for PaintCommand {
+    fn name(&mut self, _context: &GameSceneContext) -> String {
+        format!("Paint Voxels ({})", self.tool.name())
+    }
+
+    fn execute(&mut self, context: &mut GameSceneContext) {
+        lock(&self.volume).apply(&mut context.scene.graph, &self.edits, false);
+    }
+
+    fn revert(&mut self, context: &mut GameSceneContext) {
+        lock(&self.volume).apply(&mut context.scene.graph, &self.edits, true);
+    }
+}
+
+fn button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_height(BUTTON_HEIGHT)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn small_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(BUTTON_HEIGHT)
+            .with_height(BUTTON_HEIGHT)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn text(ctx: &mut BuildContext) -> Handle<UiNode> {
+    TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(4.0))).build(ctx)
+}
+
+/// Text between buttons to step a value down and up.
+fn stepper(ctx: &mut BuildContext) -> (Handle<UiNode>, [Handle<UiNode>; 3]) {
+    let less = small_button(ctx, "<");
+    let text = text(ctx);
+    let more = small_button(ctx, ">");
+    let row = StackPanelBuilder::new(WidgetBuilder::new().with_children([less, text, more]))
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+    (row, [less, text, more])
+}
+
+/// Window with the brush tools, and the brush that paints into the volume of the open scene
+/// when the scene preview is clicked.
+pub struct VoxelPainter {
+    window: Handle<UiNode>,
+    /// Buttons of the tools, and of turning the brush off so clicks select nodes again.
+    tools: Vec<(Handle<UiNode>, Option<BrushTool>)>,
+    block_previous: Handle<UiNode>,
+    block_text: Handle<UiNode>,
+    block_next: Handle<UiNode>,
+    radius_less: Handle<UiNode>,
+    radius_text: Handle<UiNode>,
+    radius_more: Handle<UiNode>,
+    status: Handle<UiNode>,
+    brush: Brush,
+    /// Whether clicks paint, the brush is off until a tool is picked.
+    painting: bool,
+    /// Volumes of the open scenes, they live as long as the commands that paint into them.
+    volumes: Vec<SharedVolume>,
+}
+
+impl VoxelPainter {
+    pub fn new() -> Self {
+        Self {
+            window: Handle::NONE,
+            tools: Vec::new(),
+            block_previous: Handle::NONE,
+            block_text: Handle::NONE,
+            block_next: Handle::NONE,
+            radius_less: Handle::NONE,
+            radius_text: Handle::NONE,
+            radius_more: Handle::NONE,
+            status: Handle::NONE,
+            brush: Brush::new(BrushTool::Place, Block::Stone),
+            painting: false,
+            volumes: Vec::new(),
+        }
+    }
+
+    fn show_brush(&self, ui: &UserInterface) {
+        let tool = if self.painting {
+            self.brush.tool.name()
+        } else {
+            "Off"
+        };
+        let status = match self.brush.corner() {
+            Some(corner) if self.painting => format!(
+                "{tool}: click the second corner, the first is at {} {} {}",
+                corner.x, corner.y, corner.z
+            ),
+            _ => format!("Tool: {tool}"),
+        };
+        for (handle, text) in [
+            (self.status, status),
+            (
+                self.block_text,
+                format!("Block: {}", self.brush.block.name()),
+            ),
+            (self.radius_text, format!("Radius: {}", self.brush.radius)),
+        ] {
+            ui.send_message(TextMessage::text(handle, MessageDirection::ToWidget, text));
+        }
+    }
+
+    /// Steps through the blocks other than air.
+    fn step_block(&mut self, steps: isize) {
+        let blocks: Vec<Block> = Block::ALL
+            .into_iter()
+            .filter(|block| !block.is_air())
+            .collect();
+        let current = blocks
+            .iter()
+            .position(|block| *block == self.brush.block)
+            .unwrap_or(0);
+        let next = (current as isize + steps).rem_euclid(blocks.len() as isize);
+        self.brush.block = blocks[next as usize];
+    }
+
+    /// Paints with the brush where the scene preview was clicked, `position` is in screen
+    /// coordinates.
+    fn paint(&mut self, editor: &mut Editor, position: Vector2<f32>) {
+        let Some(entry) = editor.scenes.current_scene_entry_mut() else {
+            return;
+        };
+        let Some(game_scene) = entry.controller.downcast_mut::<GameScene>() else {
+            return;
+        };
+        let Some(volume) = self.volume(game_scene.scene) else {
+            return;
+        };
+        let frame = editor
+            .engine
+            .user_interface
+            .node(editor.scene_viewer.frame());
+        let local = position - frame.screen_position();
+        let size = frame.actual_local_size();
+        let graph = &editor.engine.scenes[game_scene.scene].graph;
+        let Some(camera) = graph
+            .try_get(game_scene.camera_controller.camera)
+            .and_then(|node| node.cast::<Camera>())
+        else {
+            return;
+        };
+        let ray = camera.make_ray(local, size);
+        let edits = {
+            let volume = lock(&volume);
+            let Some(target) = pick(&volume.schematic, ray.origin, ray.dir, MAX_PICK_DISTANCE)
+            else {
+                return;
+            };
+            self.brush.click(&volume.schematic, &target)
+        };
+        if !edits.is_empty() {
+            editor.message_sender.do_scene_command(PaintCommand {
+                volume,
+                tool: self.brush.tool,
+                edits,
+            });
+        }
+    }
+
+    fn volume(&self, scene: Handle<Scene>) -> Option<SharedVolume> {
+        self.volumes
+            .iter()
+            .find(|volume| lock(volume).scene == scene)
+            .cloned()
+    }
+
+    /// Forgets the volumes of closed scenes without saving them, and reads the volume of the
+    /// scene that is open when it has none yet.
+    fn follow_scenes(&mut self, editor: &mut Editor) {
+        let scenes = &editor.engine.scenes;
+        self.volumes
+            .retain(|volume| scenes.try_get(lock(volume).scene).is_some());
+        let Some(entry) = editor.scenes.current_scene_entry_mut() else {
+            return;
+        };
+        let Some(game_scene) = entry.controller.downcast_ref::<GameScene>() else {
+            return;
+        };
+        if self.volume(game_scene.scene).is_some() {
+            return;
+        }
+        let graph = &mut editor.engine.scenes[game_scene.scene].graph;
+        self.volumes.push(Arc::new(Mutex::new(VoxelVolume::load(
+            entry.path.as_deref(),
+            game_scene.scene,
+            game_scene.editor_objects_root,
+            graph,
+        ))));
+    }
+}
+
+impl EditorPlugin for VoxelPainter {
+    fn on_start(&mut self, editor: &mut Editor) {
+        let ui = &mut editor.engine.user_interface;
+        let ctx = &mut ui.build_ctx();
+        let mut widgets = Vec::new();
+        for tool in BrushTool::ALL.map(Some).into_iter().chain([None]) {
+            let handle = button(ctx, tool.map_or("Off", |tool| tool.name()));
+            widgets.push(handle);
+            self.tools.push((handle, tool));
+        }
+        let (block_row, [block_previous, block_text, block_next]) = stepper(ctx);
+        let (radius_row, [radius_less, radius_text, radius_more]) = stepper(ctx);
+        let status = text(ctx);
+        widgets.extend([block_row, radius_row, status]);
+        self.block_previous = block_previous;
+        self.block_text = block_text;
+        self.block_next = block_next;
+        self.radius_less = radius_less;
+        self.radius_text = radius_text;
+        self.radius_more = radius_more;
+        self.status = status;
+        self.window = WindowBuilder::new(WidgetBuilder::new().with_width(260.0).with_height(320.0))
+            .with_title(WindowTitle::text("Voxels"))
+            .can_close(false)
+            .with_content(
+                StackPanelBuilder::new(WidgetBuilder::new().with_children(widgets)).build(ctx),
+            )
+            .build(ctx);
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            false,
+        ));
+        self.show_brush(ui);
+    }
+
+    fn on_update(&mut self, editor: &mut Editor) {
+        self.follow_scenes(editor);
+    }
+
+    fn on_message(&mut self, message: &Message, editor: &mut Editor) {
+        let Message::SaveScene { id, path } = message else {
+            return;
+        };
+        let scene = editor
+            .scenes
+            .entry_by_scene_id(*id)
+            .and_then(|entry| entry.controller.downcast_ref::<GameScene>())
+            .map(|game_scene| game_scene.scene);
+        if let Some(volume) = scene.and_then(|scene| self.volume(scene)) {
+            lock(&volume).save(path);
+        }
+    }
+
+    fn on_ui_message(&mut self, message: &mut UiMessage, editor: &mut Editor) {
+        if let Some(WidgetMessage::MouseDown {
+            pos,
+            button: MouseButton::Left,
+        }) = message.data()
+        {
+            if self.painting && message.destination() == editor.scene_viewer.frame() {
+                self.paint(editor, *pos);
+                self.show_brush(&editor.engine.user_interface);
+            }
+            return;
+        }
+        if !matches!(message.data(), Some(ButtonMessage::Click)) {
+            return;
+        }
+        let clicked = message.destination();
+        if let Some((_, tool)) = self.tools.iter().find(|(handle, _)| *handle == clicked) {
+            self.painting = tool.is_some();
+            self.brush.set_tool(tool.unwrap_or(self.brush.tool));
+        } else if clicked == self.block_previous || clicked == self.block_next {
+            self.step_block(if clicked == self.block_next { 1 } else { -1 });
+        } else if clicked == self.radius_less || clicked == self.radius_more {
+            let step = if clicked == self.radius_more { 1 } else { -1 };
+            self.brush.radius = (self.brush.radius + step).clamp(1, MAX_RADIUS);
+        } else {
+            return;
+        }
+        self.show_brush(&editor.engine.user_interface);
+    }
+}
@@ -3,6 +3,7 @@ use fyrox::{
     core::io, engine::executor::Executor, event_loop::EventLoopBuilder,
     platform::android::EventLoopBuilderExtAndroid,
 };
+use mine64::settings::load_settings;
 use mine64::GameConstructor;
 
 #[no_mangle]
@@ -11,7 +12,8 @@ fn android_main(app: fyrox::platform::android::activity::AndroidApp) {
         .set(app.clone())
         .expect("ANDROID_APP cannot be set twice.");
     let event_loop = EventLoopBuilder::new().with_android_app(app).build();
-    let mut executor = Executor::from_params(event_loop, Default::default());
+    let settings = load_settings();
+    let mut executor = Executor::from_params(event_loop, settings.graphics_context_params());
     executor.add_plugin_constructor(GameConstructor);
     executor.run()
 }
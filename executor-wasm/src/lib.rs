@@ -1,6 +1,9 @@
 //! Executor with your game connected to it as a plugin.
 use fyrox::core::wasm_bindgen::{self, prelude::*};
 use fyrox::engine::executor::Executor;
+use fyrox::event_loop::EventLoop;
+use mine64::settings::load_settings;
+use mine64::storage::preload;
 use mine64::GameConstructor;
 
 #[wasm_bindgen]
@@ -39,7 +42,14 @@ pub fn set_panic_hook() {
 #[wasm_bindgen]
 pub fn main() {
     set_panic_hook();
-    let mut executor = Executor::new();
-    executor.add_plugin_constructor(GameConstructor);
-    executor.run()
+    // Worlds are read from IndexedDB, which can't be done while the game runs.
+    preload(|| {
+        let settings = load_settings();
+        let mut executor = Executor::from_params(
+            EventLoop::new().unwrap(),
+            settings.graphics_context_params(),
+        );
+        executor.add_plugin_constructor(GameConstructor);
+        executor.run()
+    });
 }
@@ -1,9 +1,15 @@
 //! Executor with your game connected to it as a plugin.
 use fyrox::engine::executor::Executor;
+use fyrox::event_loop::EventLoop;
+use mine64::settings::load_settings;
 use mine64::GameConstructor;
 
 fn main() {
-    let mut executor = Executor::new();
+    let settings = load_settings();
+    let mut executor = Executor::from_params(
+        EventLoop::new().unwrap(),
+        settings.graphics_context_params(),
+    );
     executor.add_plugin_constructor(GameConstructor);
     executor.run()
 }
@@ -0,0 +1,197 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::block::ModelBox;
+use crate::chunk::BlockSource;
+use crate::direction::Direction;
+
+#[derive(Debug, PartialEq)]
+pub struct RayHit {
+    /// World position of the block that was hit.
+    pub block: Vector3<i32>,
+    /// Face of the block that was hit.
+    pub face: Direction,
+    pub position: Vector3<f32>,
+    pub distance: f32,
+}
+
+/// Slab test of a ray against a box of the block at `cell`, returns the distance and the face the
+/// ray enters through.
+fn intersect_box(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    cell: Vector3<i32>,
+    model_box: &ModelBox,
+) -> Option<(f32, Direction)> {
+    let mut near = f32::NEG_INFINITY;
+    let mut far = f32::INFINITY;
+    let mut face = Direction::Top;
+    for axis in 0..3 {
+        let min = cell[axis] as f32 + model_box.min[axis];
+        let max = cell[axis] as f32 + model_box.max[axis];
+        if direction[axis] == 0.0 {
+            if origin[axis] < min || origin[axis] > max {
+                return None;
+            }
+            continue;
+        }
+        let first = (min - origin[axis]) / direction[axis];
+        let second = (max - origin[axis]) / direction[axis];
+        let (enter, exit) = if first < second {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        if enter > near {
+            near = enter;
+            face = Direction::from_axis(axis, direction[axis] < 0.0)?;
+        }
+        far = far.min(exit);
+    }
+    if near > far || far < 0.0 {
+        None
+    } else {
+        Some((near.max(0.0), face))
+    }
+}
+
+/// Walks the voxel grid along the ray and returns the first hit against the actual boxes of block
+/// models, so slabs, stairs and fences are picked by their shape rather than by their cell.
+pub fn raycast(
+    source: &impl BlockSource,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+) -> Option<RayHit> {
+    let direction = direction.try_normalize(f32::EPSILON)?;
+    let mut cell = origin.map(|v| v.floor() as i32);
+    let step = direction.map(|v| v.signum() as i32);
+    let delta = direction.map(|v| {
+        if v == 0.0 {
+            f32::INFINITY
+        } else {
+            1.0 / v.abs()
+        }
+    });
+    let mut next = Vector3::from_fn(|axis, _| {
+        if direction[axis] > 0.0 {
+            (cell[axis] as f32 + 1.0 - origin[axis]) * delta[axis]
+        } else if direction[axis] < 0.0 {
+            (origin[axis] - cell[axis] as f32) * delta[axis]
+        } else {
+            f32::INFINITY
+        }
+    });
+
+    let mut travelled = 0.0;
+    while travelled <= max_distance {
+        let block = source.block(cell);
+        let hit = block
+            .boxes()
+            .iter()
+            .filter_map(|model_box| intersect_box(origin, direction, cell, model_box))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((distance, face)) = hit {
+            if distance <= max_distance {
+                return Some(RayHit {
+                    block: cell,
+                    face,
+                    position: origin + direction * distance,
+                    distance,
+                });
+            }
+        }
+
+        let axis = next.imin();
+        travelled = next[axis];
+        cell[axis] += step[axis];
+        next[axis] += delta[axis];
+    }
+    None
+}
+
+/// Hashes integer coordinates with a seed into `0.0..1.0`.
+pub fn hash(seed: u32, x: i32, y: i32, z: i32) -> f32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h >> 8) as f32 / (1 << 24) as f32
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Value noise in `0.0..1.0` with lattice points one unit apart.
+pub fn value_noise_2d(seed: u32, x: f32, z: f32) -> f32 {
+    let (x0, z0) = (x.floor(), z.floor());
+    let (tx, tz) = (smooth(x - x0), smooth(z - z0));
+    let (x0, z0) = (x0 as i32, z0 as i32);
+    let corner = |dx: i32, dz: i32| hash(seed, x0 + dx, 0, z0 + dz);
+    lerp(
+        lerp(corner(0, 0), corner(1, 0), tx),
+        lerp(corner(0, 1), corner(1, 1), tx),
+        tz,
+    )
+}
+
+/// Sums octaves of value noise, each with double the frequency and half the amplitude of the
+/// previous one. The result stays in `0.0..1.0`.
+pub fn fractal_noise_2d(seed: u32, x: f32, z: f32, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        sum += value_noise_2d(seed.wrapping_add(octave), x * frequency, z * frequency) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+/// Value noise in `0.0..1.0` with lattice points one unit apart.
+pub fn value_noise_3d(seed: u32, x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+    let (x0, y0, z0) = (x0 as i32, y0 as i32, z0 as i32);
+    let corner = |dx: i32, dy: i32, dz: i32| hash(seed, x0 + dx, y0 + dy, z0 + dz);
+    let layer = |dy: i32| {
+        lerp(
+            lerp(corner(0, dy, 0), corner(1, dy, 0), tx),
+            lerp(corner(0, dy, 1), corner(1, dy, 1), tx),
+            tz,
+        )
+    };
+    lerp(layer(0), layer(1), ty)
+}
+
+/// Three dimensional version of `fractal_noise_2d`, also in `0.0..1.0`.
+pub fn fractal_noise_3d(seed: u32, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        sum += value_noise_3d(
+            seed.wrapping_add(octave),
+            x * frequency,
+            y * frequency,
+            z * frequency,
+        ) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
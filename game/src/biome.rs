@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+/// Kind of terrain of a column, picked by its temperature and humidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Forest,
+    Mountains,
+    Ocean,
+    Snow,
+}
@@ -0,0 +1,255 @@
+use serde::Deserialize;
+
+use crate::direction::{Direction, DirectionSet};
+
+/// Axis aligned box in block local coordinates, where the whole block spans `0.0..1.0` on every axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl ModelBox {
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns `true` when the face of the box pointing to `direction` lies on the block boundary.
+    pub fn touches_side(&self, direction: &Direction) -> bool {
+        let axis = direction.axis();
+        if direction.sign() > 0 {
+            self.max[axis] >= 1.0
+        } else {
+            self.min[axis] <= 0.0
+        }
+    }
+}
+
+/// Shape of a block.
+///
+/// `full_faces` are the sides of the block that are completely covered, so the touching face of a neighbour can be culled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockModel {
+    Empty,
+    Boxes {
+        boxes: &'static [ModelBox],
+        collision: &'static [ModelBox],
+        full_faces: DirectionSet,
+    },
+    /// Two crossed quads, used for plants. Has no collision and never culls neighbours.
+    Cross,
+}
+
+const FULL_BOX: [ModelBox; 1] = [ModelBox::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0])];
+
+const SLAB_BOX: [ModelBox; 1] = [ModelBox::new([0.0, 0.0, 0.0], [1.0, 0.5, 1.0])];
+
+const STAIR_BOXES: [ModelBox; 2] = [
+    ModelBox::new([0.0, 0.0, 0.0], [1.0, 0.5, 1.0]),
+    ModelBox::new([0.0, 0.5, 0.0], [1.0, 1.0, 0.5]),
+];
+
+const FENCE_BOX: [ModelBox; 1] = [ModelBox::new([0.375, 0.0, 0.375], [0.625, 1.0, 0.625])];
+
+// Fences are higher for collision than they look, so they can't be jumped over.
+const FENCE_COLLISION_BOX: [ModelBox; 1] =
+    [ModelBox::new([0.375, 0.0, 0.375], [0.625, 1.5, 0.625])];
+
+pub const CUBE: BlockModel = BlockModel::Boxes {
+    boxes: &FULL_BOX,
+    collision: &FULL_BOX,
+    full_faces: DirectionSet::ALL,
+};
+
+/// Drawn like a cube but can be walked through and doesn't hide anything behind it.
+pub const LIQUID: BlockModel = BlockModel::Boxes {
+    boxes: &FULL_BOX,
+    collision: &[],
+    full_faces: DirectionSet::EMPTY,
+};
+
+pub const SLAB: BlockModel = BlockModel::Boxes {
+    boxes: &SLAB_BOX,
+    collision: &SLAB_BOX,
+    full_faces: DirectionSet::of(&[Direction::Bottom]),
+};
+
+pub const STAIR: BlockModel = BlockModel::Boxes {
+    boxes: &STAIR_BOXES,
+    collision: &STAIR_BOXES,
+    full_faces: DirectionSet::of(&[Direction::Back, Direction::Bottom]),
+};
+
+pub const FENCE: BlockModel = BlockModel::Boxes {
+    boxes: &FENCE_BOX,
+    collision: &FENCE_COLLISION_BOX,
+    full_faces: DirectionSet::EMPTY,
+};
+
+/// Discriminants are stored in saves, new blocks must be added at the end and to `Block::ALL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[repr(u8)]
+pub enum Block {
+    #[default]
+    Air,
+    Stone,
+    Dirt,
+    Grass,
+    Planks,
+    StoneSlab,
+    PlanksStairs,
+    Fence,
+    TallGrass,
+    Flower,
+    CoalOre,
+    IronOre,
+    GoldOre,
+    Sand,
+    Snow,
+    Water,
+    DeadBush,
+    Log,
+    Leaves,
+}
+
+impl Block {
+    /// Every block, indexed by its id.
+    pub const ALL: [Block; 19] = [
+        Block::Air,
+        Block::Stone,
+        Block::Dirt,
+        Block::Grass,
+        Block::Planks,
+        Block::StoneSlab,
+        Block::PlanksStairs,
+        Block::Fence,
+        Block::TallGrass,
+        Block::Flower,
+        Block::CoalOre,
+        Block::IronOre,
+        Block::GoldOre,
+        Block::Sand,
+        Block::Snow,
+        Block::Water,
+        Block::DeadBush,
+        Block::Log,
+        Block::Leaves,
+    ];
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Block> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    pub fn model(&self) -> BlockModel {
+        match self {
+            Block::Air => BlockModel::Empty,
+            Block::Stone
+            | Block::Dirt
+            | Block::Grass
+            | Block::Planks
+            | Block::CoalOre
+            | Block::IronOre
+            | Block::GoldOre
+            | Block::Sand
+            | Block::Snow
+            | Block::Log
+            | Block::Leaves => CUBE,
+            Block::Water => LIQUID,
+            Block::StoneSlab => SLAB,
+            Block::PlanksStairs => STAIR,
+            Block::Fence => FENCE,
+            Block::TallGrass | Block::Flower | Block::DeadBush => BlockModel::Cross,
+        }
+    }
+
+    /// Name shown to the player.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Block::Air => "Air",
+            Block::Stone => "Stone",
+            Block::Dirt => "Dirt",
+            Block::Grass => "Grass",
+            Block::Planks => "Planks",
+            Block::StoneSlab => "Stone slab",
+            Block::PlanksStairs => "Planks stairs",
+            Block::Fence => "Fence",
+            Block::TallGrass => "Tall grass",
+            Block::Flower => "Flower",
+            Block::CoalOre => "Coal ore",
+            Block::IronOre => "Iron ore",
+            Block::GoldOre => "Gold ore",
+            Block::Sand => "Sand",
+            Block::Snow => "Snow",
+            Block::Water => "Water",
+            Block::DeadBush => "Dead bush",
+            Block::Log => "Log",
+            Block::Leaves => "Leaves",
+        }
+    }
+
+    /// Color the block mostly has, as RGB. Used where a block is shown without its mesh, like
+    /// icons.
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            Block::Air => [0, 0, 0],
+            Block::Stone | Block::StoneSlab => [125, 125, 125],
+            Block::Dirt => [134, 96, 67],
+            Block::Grass => [95, 159, 53],
+            Block::Planks | Block::PlanksStairs | Block::Fence => [162, 130, 78],
+            Block::TallGrass => [110, 170, 60],
+            Block::Flower => [220, 60, 50],
+            Block::CoalOre => [70, 70, 70],
+            Block::IronOre => [175, 142, 119],
+            Block::GoldOre => [230, 200, 60],
+            Block::Sand => [219, 207, 163],
+            Block::Snow => [240, 250, 250],
+            Block::Water => [50, 90, 200],
+            Block::DeadBush => [148, 108, 50],
+            Block::Log => [102, 81, 50],
+            Block::Leaves => [60, 120, 40],
+        }
+    }
+
+    pub fn is_air(&self) -> bool {
+        *self == Block::Air
+    }
+
+    pub fn is_liquid(&self) -> bool {
+        *self == Block::Water
+    }
+
+    /// Whether every side of the block is full, like a plain cube.
+    pub fn is_full(&self) -> bool {
+        Direction::iterator().all(|direction| self.is_face_full(direction))
+    }
+
+    /// Whether the side of this block pointing to `direction` hides the touching face of a neighbour.
+    pub fn is_face_full(&self, direction: &Direction) -> bool {
+        match self.model() {
+            BlockModel::Boxes { full_faces, .. } => full_faces.contains(direction),
+            BlockModel::Empty | BlockModel::Cross => false,
+        }
+    }
+
+    /// Boxes used for drawing and picking.
+    pub fn boxes(&self) -> &'static [ModelBox] {
+        match self.model() {
+            BlockModel::Boxes { boxes, .. } => boxes,
+            BlockModel::Empty => &[],
+            // Plants are picked by their whole cell.
+            BlockModel::Cross => &FULL_BOX,
+        }
+    }
+
+    /// Boxes used for building colliders.
+    pub fn collision_boxes(&self) -> &'static [ModelBox] {
+        match self.model() {
+            BlockModel::Boxes { collision, .. } => collision,
+            BlockModel::Empty | BlockModel::Cross => &[],
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::algorithm::raycast;
+use crate::block::Block;
+use crate::chunk::BlockSource;
+use crate::direction::Direction;
+use crate::schematic::Schematic;
+
+/// Radius of a new sphere brush, in blocks.
+pub const DEFAULT_RADIUS: i32 = 3;
+
+pub const MAX_RADIUS: i32 = 16;
+
+/// How a click of the voxel brush of the editor changes the blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushTool {
+    /// Puts the block of the brush onto the face that was clicked.
+    Place,
+    Remove,
+    /// Swaps the clicked block for the block of the brush.
+    Replace,
+    /// Fills the box between the blocks in front of two clicked faces.
+    BoxFill,
+    /// Fills a ball around the block in front of the clicked face.
+    Sphere,
+}
+
+impl BrushTool {
+    pub const ALL: [BrushTool; 5] = [
+        BrushTool::Place,
+        BrushTool::Remove,
+        BrushTool::Replace,
+        BrushTool::BoxFill,
+        BrushTool::Sphere,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushTool::Place => "Place",
+            BrushTool::Remove => "Remove",
+            BrushTool::Replace => "Replace",
+            BrushTool::BoxFill => "Box fill",
+            BrushTool::Sphere => "Sphere",
+        }
+    }
+}
+
+/// Face of a block the brush points at. Below an empty volume it is the top of the floor, the
+/// layer just under the volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrushTarget {
+    pub block: Vector3<i32>,
+    pub face: Direction,
+}
+
+impl BrushTarget {
+    /// Block in front of the face, where placed blocks go.
+    pub fn front(&self) -> Vector3<i32> {
+        self.face.neighbour(self.block)
+    }
+}
+
+/// Block the brush changed, with what was there before so the change can be undone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockEdit {
+    pub position: Vector3<i32>,
+    pub old: Block,
+    pub new: Block,
+}
+
+/// Face the ray from `origin` points at, the blocks of the volume first and then its floor.
+pub fn pick(
+    volume: &Schematic,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+) -> Option<BrushTarget> {
+    if let Some(hit) = raycast(volume, origin, direction, max_distance) {
+        return Some(BrushTarget {
+            block: hit.block,
+            face: hit.face,
+        });
+    }
+    if direction.y >= 0.0 || origin.y < 0.0 {
+        return None;
+    }
+    let distance = origin.y / -direction.y * direction.norm();
+    if distance > max_distance {
+        return None;
+    }
+    let floor = origin + direction * (origin.y / -direction.y);
+    let size = volume.size();
+    let (x, z) = (floor.x.floor() as i32, floor.z.floor() as i32);
+    ((0..size.x).contains(&x) && (0..size.z).contains(&z)).then_some(BrushTarget {
+        block: Vector3::new(x, -1, z),
+        face: Direction::Top,
+    })
+}
+
+/// Tool, block and size of the voxel brush.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brush {
+    pub tool: BrushTool,
+    pub block: Block,
+    /// Radius of the sphere tool.
+    pub radius: i32,
+    /// First corner of a box fill, until the second click.
+    corner: Option<Vector3<i32>>,
+}
+
+impl Brush {
+    pub fn new(tool: BrushTool, block: Block) -> Self {
+        Self {
+            tool,
+            block,
+            radius: DEFAULT_RADIUS,
+            corner: None,
+        }
+    }
+
+    /// Switches the tool, forgetting the first corner of a box fill.
+    pub fn set_tool(&mut self, tool: BrushTool) {
+        self.tool = tool;
+        self.corner = None;
+    }
+
+    pub fn corner(&self) -> Option<Vector3<i32>> {
+        self.corner
+    }
+
+    /// Changes a click on the target makes to the volume, blocks outside of it and blocks that
+    /// would stay the same are left out. The first click of a box fill only keeps its corner.
+    pub fn click(&mut self, volume: &Schematic, target: &BrushTarget) -> Vec<BlockEdit> {
+        let mut positions = Vec::new();
+        let mut block = self.block;
+        match self.tool {
+            BrushTool::Place => {
+                if volume.block(target.front()).is_air() {
+                    positions.push(target.front());
+                }
+            }
+            BrushTool::Remove => {
+                positions.push(target.block);
+                block = Block::Air;
+            }
+            BrushTool::Replace => {
+                if !volume.block(target.block).is_air() {
+                    positions.push(target.block);
+                }
+            }
+            BrushTool::BoxFill => match self.corner.take() {
+                None => self.corner = Some(target.front()),
+                Some(corner) => {
+                    let (min, max) = (corner.inf(&target.front()), corner.sup(&target.front()));
+                    for y in min.y..=max.y {
+                        for z in min.z..=max.z {
+                            for x in min.x..=max.x {
+                                positions.push(Vector3::new(x, y, z));
+                            }
+                        }
+                    }
+                }
+            },
+            BrushTool::Sphere => {
+                let center = target.front();
+                let radius = self.radius;
+                // Taking in cells a bit beyond the radius keeps small spheres from looking like
+                // crosses.
+                let reach = radius * radius + radius;
+                for y in -radius..=radius {
+                    for z in -radius..=radius {
+                        for x in -radius..=radius {
+                            if x * x + y * y + z * z <= reach {
+                                positions.push(center + Vector3::new(x, y, z));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let size = volume.size();
+        positions
+            .into_iter()
+            .filter(|position| (0..3).all(|axis| (0..size[axis]).contains(&position[axis])))
+            .map(|position| BlockEdit {
+                position,
+                old: volume.block(position),
+                new: block,
+            })
+            .filter(|edit| edit.old != edit.new)
+            .collect()
+    }
+}
+
+/// Sets the new blocks of the edits, or the old ones to undo them.
+pub fn apply_edits(volume: &mut Schematic, edits: &[BlockEdit], undo: bool) {
+    for edit in edits {
+        let block = if undo { edit.old } else { edit.new };
+        volume.set(edit.position, block);
+    }
+}
@@ -0,0 +1,135 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::item::DroppedItem;
+
+pub const CHUNK_SIZE: i32 = 16;
+
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Anything blocks can be read from with world coordinates.
+pub trait BlockSource {
+    fn block(&self, position: Vector3<i32>) -> Block;
+}
+
+/// Cube of `CHUNK_SIZE` blocks on every side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    position: Vector3<i32>,
+    blocks: Vec<Block>,
+    /// Whether the chunk differs from what the generator makes, only such chunks are saved.
+    modified: bool,
+    /// Whether the chunk changed since it was last saved.
+    dirty: bool,
+    /// Dropped items lying in the chunk. They only stay here while the chunk is saved or loaded,
+    /// the simulation moves them around the rest of the time.
+    items: Vec<DroppedItem>,
+}
+
+impl Chunk {
+    pub fn new(position: Vector3<i32>) -> Self {
+        Self {
+            position,
+            blocks: vec![Block::Air; CHUNK_VOLUME],
+            modified: false,
+            dirty: false,
+            items: Vec::new(),
+        }
+    }
+
+    /// Chunk with blocks listed in the same order as `blocks()` returns them.
+    pub fn from_blocks(position: Vector3<i32>, blocks: Vec<Block>) -> Option<Self> {
+        (blocks.len() == CHUNK_VOLUME).then_some(Self {
+            position,
+            blocks,
+            modified: false,
+            dirty: false,
+            items: Vec::new(),
+        })
+    }
+
+    /// All blocks, x changes fastest, then z, then y.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: bool) {
+        self.modified = modified;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+
+    pub fn items(&self) -> &[DroppedItem] {
+        &self.items
+    }
+
+    pub fn add_item(&mut self, item: DroppedItem) {
+        self.items.push(item);
+    }
+
+    pub fn take_items(&mut self) -> Vec<DroppedItem> {
+        std::mem::take(&mut self.items)
+    }
+
+    /// Position of the chunk in chunk coordinates.
+    pub fn position(&self) -> Vector3<i32> {
+        self.position
+    }
+
+    /// World position of the first block of the chunk.
+    pub fn origin(&self) -> Vector3<i32> {
+        self.position * CHUNK_SIZE
+    }
+
+    fn index(local: Vector3<i32>) -> Option<usize> {
+        if (0..CHUNK_SIZE).contains(&local.x)
+            && (0..CHUNK_SIZE).contains(&local.y)
+            && (0..CHUNK_SIZE).contains(&local.z)
+        {
+            Some((local.x + local.z * CHUNK_SIZE + local.y * CHUNK_SIZE * CHUNK_SIZE) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Block at local coordinates, or air when outside of the chunk.
+    pub fn get(&self, local: Vector3<i32>) -> Block {
+        Self::index(local).map_or(Block::Air, |index| self.blocks[index])
+    }
+
+    pub fn set(&mut self, local: Vector3<i32>, block: Block) {
+        if let Some(index) = Self::index(local) {
+            self.blocks[index] = block;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(Block::is_air)
+    }
+}
+
+impl BlockSource for Chunk {
+    fn block(&self, position: Vector3<i32>) -> Block {
+        self.get(position - self.origin())
+    }
+}
+
+/// Chunk containing the given world block position.
+pub fn chunk_position(block: Vector3<i32>) -> Vector3<i32> {
+    block.map(|v| v.div_euclid(CHUNK_SIZE))
+}
+
+/// Position of a world block inside of its chunk.
+pub fn local_position(block: Vector3<i32>) -> Vector3<i32> {
+    block.map(|v| v.rem_euclid(CHUNK_SIZE))
+}
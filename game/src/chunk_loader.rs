@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use fyrox::core::algebra::Vector3;
+
+use crate::chunk::Chunk;
+use crate::lod::build_lod_meshes;
+use crate::logging::{self, Category};
+use crate::mesher::ChunkMesh;
+use crate::profiler::{self, Scope};
+use crate::save::WorldSave;
+use crate::visibility::Connectivity;
+use crate::world::World;
+use crate::world_generator::{WorldGenerator, WORLD_HEIGHT};
+
+/// Radius in chunks around the camera chunk that the game keeps loaded, unless the settings pick
+/// another one.
+pub const VIEW_DISTANCE: i32 = 32;
+
+/// How many chunks may be generating at the same time.
+const MAX_PENDING: usize = 64;
+
+/// What the renderer needs of a chunk, built on the worker next to the blocks.
+pub struct ChunkRenderData {
+    pub position: Vector3<i32>,
+    /// Downsampled meshes, see `lod::LOD_FACTORS`.
+    pub lods: Vec<ChunkMesh>,
+    pub connectivity: Connectivity,
+}
+
+pub struct LoadedChunk {
+    pub chunk: Chunk,
+    pub render_data: ChunkRenderData,
+}
+
+/// Reads the chunk from the save when it was modified, otherwise generates it.
+fn load(generator: &WorldGenerator, save: &WorldSave, position: Vector3<i32>) -> LoadedChunk {
+    let saved = match save.load_chunk(position) {
+        Ok(chunk) => chunk,
+        Err(error) => {
+            logging::error(
+                Category::World,
+                format!("Failed to load chunk {position:?}: {error}"),
+            );
+            None
+        }
+    };
+    let chunk = saved.unwrap_or_else(|| {
+        let _timer = profiler::time(Scope::Generation);
+        generator.generate(position)
+    });
+    let lods = {
+        let _timer = profiler::time(Scope::Meshing);
+        build_lod_meshes(&chunk)
+    };
+    let render_data = ChunkRenderData {
+        position,
+        lods,
+        connectivity: Connectivity::compute(&chunk),
+    };
+    LoadedChunk { chunk, render_data }
+}
+
+fn is_in_range(view_distance: i32, center: Vector3<i32>, position: Vector3<i32>) -> bool {
+    let (dx, dz) = (position.x - center.x, position.z - center.z);
+    dx * dx + dz * dz <= view_distance * view_distance && (0..WORLD_HEIGHT).contains(&position.y)
+}
+
+/// Decides which chunks should be in memory around the camera and generates them on worker threads.
+///
+/// Wasm has no threads, there chunks are generated on the main thread a few per frame.
+pub struct ChunkLoader {
+    generator: WorldGenerator,
+    save: Arc<WorldSave>,
+    /// Radius in chunks around the center that is kept loaded.
+    view_distance: i32,
+    center: Option<Vector3<i32>>,
+    /// Chunks in range that are not loaded yet, the closest one is last.
+    queue: Vec<Vector3<i32>>,
+    pending: HashSet<Vector3<i32>>,
+    requests: Sender<Vector3<i32>>,
+    results: Receiver<LoadedChunk>,
+    #[cfg(target_arch = "wasm32")]
+    jobs: Receiver<Vector3<i32>>,
+    #[cfg(target_arch = "wasm32")]
+    done: Sender<LoadedChunk>,
+    _workers: Vec<JoinHandle<()>>,
+}
+
+impl ChunkLoader {
+    pub fn new(generator: WorldGenerator, save: Arc<WorldSave>, view_distance: i32) -> Self {
+        let (requests, jobs) = channel::<Vector3<i32>>();
+        let (done, results) = channel();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let workers = {
+            let jobs = Arc::new(Mutex::new(jobs));
+            let count = std::thread::available_parallelism()
+                .map_or(2, |count| count.get().saturating_sub(1).max(1));
+            (0..count)
+                .map(|_| {
+                    let jobs = jobs.clone();
+                    let done = done.clone();
+                    let generator = generator.clone();
+                    let save = save.clone();
+                    std::thread::spawn(move || loop {
+                        let position = match jobs.lock().unwrap().recv() {
+                            Ok(position) => position,
+                            // Loader was dropped.
+                            Err(_) => break,
+                        };
+                        if done.send(load(&generator, &save, position)).is_err() {
+                            break;
+                        }
+                    })
+                })
+                .collect()
+        };
+
+        Self {
+            generator,
+            save,
+            view_distance,
+            center: None,
+            queue: Vec::new(),
+            pending: HashSet::new(),
+            requests,
+            results,
+            #[cfg(target_arch = "wasm32")]
+            jobs,
+            #[cfg(target_arch = "wasm32")]
+            done,
+            #[cfg(not(target_arch = "wasm32"))]
+            _workers: workers,
+            #[cfg(target_arch = "wasm32")]
+            _workers: Vec::new(),
+        }
+    }
+
+    pub fn generator(&self) -> &WorldGenerator {
+        &self.generator
+    }
+
+    /// Whether every chunk in range is loaded and nothing is generating.
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_empty() && self.pending.is_empty()
+    }
+
+    /// Forgets the center, so the next `recenter` queues every missing chunk in range again.
+    pub fn reset(&mut self) {
+        self.center = None;
+        self.queue.clear();
+    }
+
+    /// Radius in chunks of the loaded area.
+    pub fn view_distance(&self) -> i32 {
+        self.view_distance
+    }
+
+    /// Chunks in range waiting to be sent to the workers.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Chunks the workers are generating and meshing.
+    pub fn loading(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Moves the loaded area to `center` when the camera entered another chunk. Returns chunks that
+    /// went out of range and should be unloaded.
+    pub fn recenter(&mut self, center: Vector3<i32>, world: &World) -> Vec<Vector3<i32>> {
+        if self.center == Some(center) {
+            return Vec::new();
+        }
+        self.center = Some(center);
+
+        self.queue.clear();
+        let view_distance = self.view_distance;
+        for y in 0..WORLD_HEIGHT {
+            for z in -view_distance..=view_distance {
+                for x in -view_distance..=view_distance {
+                    let position = Vector3::new(center.x + x, y, center.z + z);
+                    if is_in_range(view_distance, center, position)
+                        && world.chunk(position).is_none()
+                        && !self.pending.contains(&position)
+                    {
+                        self.queue.push(position);
+                    }
+                }
+            }
+        }
+        let distance = |position: &Vector3<i32>| (position - center).cast::<f32>().norm_squared();
+        self.queue
+            .sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+        world
+            .chunks()
+            .map(|chunk| chunk.position())
+            .filter(|position| !is_in_range(view_distance, center, *position))
+            .collect()
+    }
+
+    /// Sends closest missing chunks to the workers and returns chunks that finished loading.
+    pub fn update(&mut self) -> Vec<LoadedChunk> {
+        while self.pending.len() < MAX_PENDING {
+            let Some(position) = self.queue.pop() else {
+                break;
+            };
+            if self.requests.send(position).is_ok() {
+                self.pending.insert(position);
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        for position in self.jobs.try_iter().take(4) {
+            let _ = self.done.send(load(&self.generator, &self.save, position));
+        }
+
+        let center = self.center;
+        let view_distance = self.view_distance;
+        let loaded: Vec<LoadedChunk> = self.results.try_iter().collect();
+        loaded
+            .into_iter()
+            .filter(|loaded| {
+                let position = loaded.chunk.position();
+                self.pending.remove(&position);
+                // The camera could move away while the chunk was generating.
+                center.is_some_and(|center| is_in_range(view_distance, center, position))
+            })
+            .collect()
+    }
+}
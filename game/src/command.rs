@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::chunk::chunk_position;
+use crate::item::ItemStack;
+use crate::player::GameMode;
+use crate::simulation::Simulation;
+
+/// Most blocks `fill` changes at once.
+pub const MAX_FILL_VOLUME: i64 = 32 * 32 * 32;
+
+/// Lines of scripts starting with this are comments.
+const COMMENT: char = '#';
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    Unknown(String),
+    /// Arguments don't fit the command, with its usage.
+    Usage(&'static str),
+    Failed(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Unknown(name) => write!(f, "unknown command {name}, try help"),
+            CommandError::Usage(usage) => write!(f, "usage: {usage}"),
+            CommandError::Failed(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// Something a command changed that whoever draws the world has to follow.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandEffect {
+    /// Blocks in the box between the corners changed.
+    BlocksChanged {
+        min: Vector3<i32>,
+        max: Vector3<i32>,
+    },
+    ChunksUnloaded(Vec<Vector3<i32>>),
+}
+
+/// What commands run on.
+pub struct CommandContext<'a> {
+    pub simulation: &'a mut Simulation,
+    pub effects: Vec<CommandEffect>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(simulation: &'a mut Simulation) -> Self {
+        Self {
+            simulation,
+            effects: Vec::new(),
+        }
+    }
+}
+
+/// Kind of an argument, used to complete it.
+#[derive(Debug, Clone, Copy)]
+pub enum Argument {
+    Any,
+    /// Name of a block, like `stone_slab`.
+    Block,
+    Choice(&'static [&'static str]),
+}
+
+/// Arguments a command was run with.
+pub struct Arguments<'a> {
+    values: Vec<&'a str>,
+    usage: &'static str,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Error with the usage of the command, for arguments that don't fit it.
+    pub fn usage_error(&self) -> CommandError {
+        CommandError::Usage(self.usage)
+    }
+
+    /// Fails with the usage of the command when the count is not in the range.
+    pub fn expect_count(
+        &self,
+        count: impl std::ops::RangeBounds<usize>,
+    ) -> Result<(), CommandError> {
+        if count.contains(&self.len()) {
+            Ok(())
+        } else {
+            Err(CommandError::Usage(self.usage))
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Result<&'a str, CommandError> {
+        self.values
+            .get(index)
+            .copied()
+            .ok_or(CommandError::Usage(self.usage))
+    }
+
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, CommandError> {
+        let value = self.get(index)?;
+        value
+            .parse()
+            .map_err(|_| CommandError::Failed(format!("invalid value {value}")))
+    }
+
+    pub fn block(&self, index: usize) -> Result<Block, CommandError> {
+        let name = self.get(index)?;
+        block_by_name(name).ok_or_else(|| CommandError::Failed(format!("unknown block {name}")))
+    }
+
+    /// Three coordinates from `index` on. A coordinate starting with `~` is relative to the one
+    /// of `origin`, like `~` or `~-2`.
+    pub fn position(
+        &self,
+        index: usize,
+        origin: Vector3<f32>,
+    ) -> Result<Vector3<f32>, CommandError> {
+        let mut position = origin;
+        for axis in 0..3 {
+            let value = self.get(index + axis)?;
+            let (relative, number) = match value.strip_prefix('~') {
+                Some(offset) => (true, offset),
+                None => (false, value),
+            };
+            let number = if relative && number.is_empty() {
+                0.0
+            } else {
+                number
+                    .parse::<f32>()
+                    .map_err(|_| CommandError::Failed(format!("invalid coordinate {value}")))?
+            };
+            position[axis] = if relative {
+                origin[axis] + number
+            } else {
+                number
+            };
+        }
+        Ok(position)
+    }
+
+    /// Like `position`, for the block the position is in.
+    pub fn block_position(
+        &self,
+        index: usize,
+        origin: Vector3<f32>,
+    ) -> Result<Vector3<i32>, CommandError> {
+        Ok(self.position(index, origin)?.map(|v| v.floor() as i32))
+    }
+}
+
+/// Name commands know the block by: its name in lower case with `_` for spaces.
+pub fn block_name(block: Block) -> String {
+    block.name().to_lowercase().replace(' ', "_")
+}
+
+pub fn block_by_name(name: &str) -> Option<Block> {
+    Block::ALL
+        .iter()
+        .copied()
+        .find(|block| block_name(*block) == name)
+}
+
+pub struct Command {
+    pub name: &'static str,
+    /// Name of the command with its arguments, like `tp <x> <y> <z>`.
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub arguments: &'static [Argument],
+    /// Runs the command, returns what to show to the player.
+    pub run: fn(&mut CommandContext, &Arguments) -> Result<String, CommandError>,
+}
+
+/// Commands by name. Every part of the game registers its own, the console, tests and scripts
+/// run lines of text with them.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl CommandRegistry {
+    /// Adds the command, replacing one with the same name.
+    pub fn register(&mut self, command: Command) {
+        self.commands.insert(command.name, command);
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.values()
+    }
+
+    /// Runs a line like `setblock ~ ~-1 ~ stone`. `help` lists the commands, or tells how to use
+    /// the one named after it.
+    pub fn run(&self, context: &mut CommandContext, line: &str) -> Result<String, CommandError> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(String::new());
+        };
+        let values: Vec<&str> = words.collect();
+        if name == "help" {
+            return self.help(values.first().copied());
+        }
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+        let arguments = Arguments {
+            values,
+            usage: command.usage,
+        };
+        (command.run)(context, &arguments)
+    }
+
+    fn help(&self, name: Option<&str>) -> Result<String, CommandError> {
+        match name {
+            Some(name) => {
+                let command = self
+                    .commands
+                    .get(name)
+                    .ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+                Ok(format!("{}: {}", command.usage, command.help))
+            }
+            None => Ok(self
+                .commands()
+                .map(|command| command.usage)
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+
+    /// Runs every line of the script that is not empty or a comment, the results are in order
+    /// of the lines. Lines after a failed one still run.
+    pub fn run_script(
+        &self,
+        context: &mut CommandContext,
+        script: &str,
+    ) -> Vec<Result<String, CommandError>> {
+        script
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(COMMENT))
+            .map(|line| self.run(context, line))
+            .collect()
+    }
+
+    /// Lines the last word of `line` can be completed to, sorted. The first word completes to
+    /// command names, the others by the kind of argument they are.
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let (head, word) = match line.rfind(char::is_whitespace) {
+            Some(index) => line.split_at(index + 1),
+            None => ("", line),
+        };
+        let mut previous = head.split_whitespace();
+        let candidates: Vec<String> = match previous.next() {
+            None => self
+                .commands
+                .keys()
+                .copied()
+                .chain(["help"])
+                .map(str::to_string)
+                .collect(),
+            Some("help") => self.commands.keys().map(|name| name.to_string()).collect(),
+            Some(name) => {
+                let index = previous.count();
+                match self
+                    .commands
+                    .get(name)
+                    .and_then(|command| command.arguments.get(index))
+                {
+                    Some(Argument::Block) => Block::ALL.iter().map(|b| block_name(*b)).collect(),
+                    Some(Argument::Choice(choices)) => {
+                        choices.iter().map(|choice| choice.to_string()).collect()
+                    }
+                    Some(Argument::Any) | None => Vec::new(),
+                }
+            }
+        };
+        let mut lines: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| format!("{head}{candidate}"))
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+/// Fails when a block in the box is in a chunk that is not loaded.
+pub fn check_loaded(
+    simulation: &Simulation,
+    min: Vector3<i32>,
+    max: Vector3<i32>,
+) -> Result<(), CommandError> {
+    let (first, last) = (chunk_position(min), chunk_position(max));
+    for y in first.y..=last.y {
+        for z in first.z..=last.z {
+            for x in first.x..=last.x {
+                if simulation.world().chunk(Vector3::new(x, y, z)).is_none() {
+                    return Err(CommandError::Failed(
+                        "blocks outside of the loaded world can't be changed".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tp(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(3..=3)?;
+    let player = context.simulation.player_mut();
+    let position = arguments.position(0, player.position())?;
+    player.set_position(position);
+    Ok(format!(
+        "Teleported to {:.2} {:.2} {:.2}",
+        position.x, position.y, position.z
+    ))
+}
+
+fn setblock(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(4..=4)?;
+    let position = arguments.block_position(0, context.simulation.player().position())?;
+    let block = arguments.block(3)?;
+    check_loaded(context.simulation, position, position)?;
+    context.simulation.set_block(position, block);
+    context.effects.push(CommandEffect::BlocksChanged {
+        min: position,
+        max: position,
+    });
+    Ok(format!(
+        "Set {} {} {} to {}",
+        position.x,
+        position.y,
+        position.z,
+        block_name(block)
+    ))
+}
+
+fn fill(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(7..=7)?;
+    let origin = context.simulation.player().position();
+    let a = arguments.block_position(0, origin)?;
+    let b = arguments.block_position(3, origin)?;
+    let block = arguments.block(6)?;
+    let (min, max) = (a.inf(&b), a.sup(&b));
+    let size = (max - min).add_scalar(1).cast::<i64>();
+    let volume = size.x * size.y * size.z;
+    if volume > MAX_FILL_VOLUME {
+        return Err(CommandError::Failed(format!(
+            "{volume} blocks are too many, at most {MAX_FILL_VOLUME} can be filled"
+        )));
+    }
+    check_loaded(context.simulation, min, max)?;
+    for y in min.y..=max.y {
+        for z in min.z..=max.z {
+            for x in min.x..=max.x {
+                context.simulation.set_block(Vector3::new(x, y, z), block);
+            }
+        }
+    }
+    context
+        .effects
+        .push(CommandEffect::BlocksChanged { min, max });
+    Ok(format!("Filled {volume} blocks with {}", block_name(block)))
+}
+
+fn time(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    if arguments.is_empty() {
+        let time = context.simulation.metadata().time;
+        return Ok(format!("The world was played for {time:.0} seconds"));
+    }
+    arguments.expect_count(2..=2)?;
+    if arguments.get(0)? != "set" {
+        return Err(CommandError::Usage(arguments.usage));
+    }
+    let time: f32 = arguments.parse(1)?;
+    if !(time >= 0.0 && time.is_finite()) {
+        return Err(CommandError::Failed(format!("invalid time {time}")));
+    }
+    context.simulation.set_time(time);
+    Ok(format!("Set the time to {time} seconds"))
+}
+
+fn seed(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(0..=0)?;
+    Ok(format!("Seed: {}", context.simulation.metadata().seed))
+}
+
+fn give(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(1..=2)?;
+    let block = arguments.block(0)?;
+    let count: u32 = if arguments.len() > 1 {
+        arguments.parse(1)?
+    } else {
+        1
+    };
+    let stack = ItemStack::new(block, count);
+    if count == 0 || stack.max_count() == 0 {
+        return Err(CommandError::Failed(format!(
+            "{count} {} can't be given",
+            block_name(block)
+        )));
+    }
+    let rest = context.simulation.player_mut().inventory_mut().add(stack);
+    let given = count - rest.map_or(0, |rest| rest.count);
+    Ok(format!("Gave {given} {}", block_name(block)))
+}
+
+fn gamemode(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(1..=1)?;
+    let game_mode = match arguments.get(0)? {
+        "survival" => GameMode::Survival,
+        "creative" => GameMode::Creative,
+        _ => return Err(CommandError::Usage(arguments.usage)),
+    };
+    context.simulation.player_mut().set_game_mode(game_mode);
+    Ok(format!("Game mode is {game_mode:?}"))
+}
+
+fn reload_chunks(
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    arguments.expect_count(0..=0)?;
+    let unloaded = context.simulation.reload_chunks();
+    let count = unloaded.len();
+    context
+        .effects
+        .push(CommandEffect::ChunksUnloaded(unloaded));
+    Ok(format!("Reloading {count} chunks"))
+}
+
+/// Commands that change the world and the player.
+pub fn register_world_commands(registry: &mut CommandRegistry) {
+    registry.register(Command {
+        name: "tp",
+        usage: "tp <x> <y> <z>",
+        help: "moves the player, ~ makes a coordinate relative",
+        arguments: &[Argument::Any, Argument::Any, Argument::Any],
+        run: tp,
+    });
+    registry.register(Command {
+        name: "setblock",
+        usage: "setblock <x> <y> <z> <block>",
+        help: "changes a block",
+        arguments: &[Argument::Any, Argument::Any, Argument::Any, Argument::Block],
+        run: setblock,
+    });
+    registry.register(Command {
+        name: "fill",
+        usage: "fill <x1> <y1> <z1> <x2> <y2> <z2> <block>",
+        help: "changes every block in a box",
+        arguments: &[
+            Argument::Any,
+            Argument::Any,
+            Argument::Any,
+            Argument::Any,
+            Argument::Any,
+            Argument::Any,
+            Argument::Block,
+        ],
+        run: fill,
+    });
+    registry.register(Command {
+        name: "time",
+        usage: "time [set <seconds>]",
+        help: "shows or sets how long the world was played for",
+        arguments: &[Argument::Choice(&["set"])],
+        run: time,
+    });
+    registry.register(Command {
+        name: "seed",
+        usage: "seed",
+        help: "shows the seed of the world",
+        arguments: &[],
+        run: seed,
+    });
+    registry.register(Command {
+        name: "give",
+        usage: "give <block> [count]",
+        help: "puts blocks into the inventory",
+        arguments: &[Argument::Block],
+        run: give,
+    });
+    registry.register(Command {
+        name: "gamemode",
+        usage: "gamemode survival|creative",
+        help: "changes the game mode, creative flies, can't be hurt and doesn't use up blocks",
+        arguments: &[Argument::Choice(&["creative", "survival"])],
+        run: gamemode,
+    });
+    registry.register(Command {
+        name: "reload_chunks",
+        usage: "reload_chunks",
+        help: "saves and unloads every chunk, they are loaded again",
+        arguments: &[],
+        run: reload_chunks,
+    });
+}
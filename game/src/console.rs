@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+
+use fyrox::core::algebra::Vector2;
+use fyrox::core::color::Color;
+use fyrox::core::pool::Handle;
+use fyrox::gui::border::BorderBuilder;
+use fyrox::gui::brush::Brush;
+use fyrox::gui::message::{MessageDirection, UiMessage};
+use fyrox::gui::stack_panel::StackPanelBuilder;
+use fyrox::gui::text::{TextBuilder, TextMessage};
+use fyrox::gui::text_box::TextBoxBuilder;
+use fyrox::gui::widget::{WidgetBuilder, WidgetMessage};
+use fyrox::gui::{HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment};
+use fyrox::keyboard::KeyCode;
+
+use crate::command::{CommandError, CommandRegistry};
+use crate::settings::strip_key_character;
+
+/// Lines of output kept, older ones scroll away.
+const MAX_LINES: usize = 16;
+
+/// Lines typed in that can be brought back with the arrow keys.
+const MAX_HISTORY: usize = 64;
+
+const INPUT_HEIGHT: f32 = 24.0;
+const BACKGROUND_COLOR: Color = Color::from_rgba(0, 0, 0, 160);
+
+/// Developer console at the top of the screen, opened with the console key of the settings and
+/// closed with Escape, or with the console key when it types no character. Lines typed into it
+/// are run as commands.
+pub struct Console {
+    root: Handle<UiNode>,
+    output: Handle<UiNode>,
+    input: Handle<UiNode>,
+    screen_size: Vector2<f32>,
+    visible: bool,
+    lines: VecDeque<String>,
+    /// Text in the input.
+    line: String,
+    history: Vec<String>,
+    /// Entry of the history shown in the input, `None` while a new line is typed.
+    history_index: Option<usize>,
+    /// Key that opens the console.
+    toggle_key: KeyCode,
+    /// Whether the character the key that opened the console types may still reach the input,
+    /// it is left out once.
+    opening: bool,
+}
+
+impl Console {
+    pub fn new(ui: &mut UserInterface, toggle_key: KeyCode) -> Self {
+        let screen_size = ui.screen_size();
+        let ctx = &mut ui.build_ctx();
+        let output = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(6.0))
+                .with_foreground(Brush::Solid(Color::WHITE)),
+        )
+        .build(ctx);
+        let input = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_height(INPUT_HEIGHT)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .build(ctx);
+        let panel =
+            StackPanelBuilder::new(WidgetBuilder::new().with_child(output).with_child(input))
+                .build(ctx);
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_width(screen_size.x)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_background(Brush::Solid(BACKGROUND_COLOR))
+                .with_child(panel),
+        )
+        .with_stroke_thickness(Thickness::zero())
+        .build(ctx);
+        Self {
+            root,
+            output,
+            input,
+            screen_size,
+            visible: false,
+            lines: VecDeque::new(),
+            line: String::new(),
+            history: Vec::new(),
+            history_index: None,
+            toggle_key,
+            opening: false,
+        }
+    }
+
+    /// Follows a new binding of the key that toggles the console.
+    pub fn set_toggle_key(&mut self, key: KeyCode) {
+        self.toggle_key = key;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows the console with an empty input that has the keyboard focus, or hides it.
+    pub fn set_visible(&mut self, ui: &UserInterface, visible: bool) {
+        self.visible = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+        self.set_line(ui, String::new());
+        self.history_index = None;
+        self.opening = visible;
+        if visible {
+            ui.send_message(WidgetMessage::focus(self.input, MessageDirection::ToWidget));
+        } else {
+            ui.send_message(WidgetMessage::unfocus(
+                self.input,
+                MessageDirection::ToWidget,
+            ));
+        }
+    }
+
+    /// Keeps the console as wide as the screen.
+    pub fn update(&mut self, ui: &UserInterface) {
+        let screen_size = ui.screen_size();
+        if screen_size != self.screen_size {
+            self.screen_size = screen_size;
+            ui.send_message(WidgetMessage::width(
+                self.root,
+                MessageDirection::ToWidget,
+                screen_size.x,
+            ));
+        }
+    }
+
+    /// Adds lines to the output.
+    pub fn print(&mut self, ui: &UserInterface, text: &str) {
+        for line in text.lines() {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line.to_string());
+        }
+        ui.send_message(TextMessage::text(
+            self.output,
+            MessageDirection::ToWidget,
+            self.lines.iter().cloned().collect::<Vec<_>>().join("\n"),
+        ));
+    }
+
+    /// Adds what a command returned to the output, errors are marked as such.
+    pub fn print_result(&mut self, ui: &UserInterface, result: &Result<String, CommandError>) {
+        match result {
+            Ok(text) => self.print(ui, text),
+            Err(error) => self.print(ui, &format!("Error: {error}")),
+        }
+    }
+
+    /// Follows typing in the input.
+    pub fn handle_message(&mut self, ui: &UserInterface, message: &UiMessage) {
+        if let Some(TextMessage::Text(text)) = message.data() {
+            if message.direction() == MessageDirection::FromWidget
+                && message.destination() == self.input
+            {
+                // Only the first character typed after opening can be the one of the key.
+                if self.opening && !text.is_empty() {
+                    self.opening = false;
+                    if let Some(line) = strip_key_character(text, self.toggle_key) {
+                        self.set_line(ui, line);
+                        return;
+                    }
+                }
+                self.line = text.clone();
+            }
+        }
+    }
+
+    fn set_line(&mut self, ui: &UserInterface, line: String) {
+        self.line = line.clone();
+        ui.send_message(TextMessage::text(
+            self.input,
+            MessageDirection::ToWidget,
+            line,
+        ));
+    }
+
+    /// Takes the typed line to run it, echoing it to the output and keeping it in the history.
+    /// `None` when nothing was typed.
+    pub fn submit(&mut self, ui: &UserInterface) -> Option<String> {
+        let line = self.line.trim().to_string();
+        self.set_line(ui, String::new());
+        self.history_index = None;
+        if line.is_empty() {
+            return None;
+        }
+        self.print(ui, &format!("> {line}"));
+        if self.history.last() != Some(&line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        Some(line)
+    }
+
+    /// Puts an older line of the history into the input when `older`, a newer one otherwise.
+    pub fn browse_history(&mut self, ui: &UserInterface, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        let line = self
+            .history_index
+            .map_or_else(String::new, |index| self.history[index].clone());
+        self.set_line(ui, line);
+    }
+
+    /// Completes the input when there is a single completion, lists them when there are more.
+    pub fn complete(&mut self, ui: &UserInterface, commands: &CommandRegistry) {
+        let completions = commands.complete(&self.line);
+        match completions.as_slice() {
+            [] => (),
+            [completion] => self.set_line(ui, format!("{completion} ")),
+            _ => {
+                let words: Vec<&str> = completions
+                    .iter()
+                    .filter_map(|completion| completion.split_whitespace().last())
+                    .collect();
+                self.print(ui, &words.join(" "));
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use fyrox::core::algebra::Vector3;
+use fyrox::core::color::Color;
+use fyrox::scene::debug::{Line, SceneDrawingContext};
+use fyrox::scene::Scene;
+
+use crate::algorithm::RayHit;
+use crate::chunk::CHUNK_SIZE;
+use crate::simulation::Simulation;
+use crate::world_generator::WORLD_HEIGHT;
+
+/// Chunks around the chunk of the player whose corners the chunk borders show.
+const BORDER_RADIUS: i32 = 2;
+
+/// Lines the load radius circle is made of.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Edge of the cube marking the hit point of the pick ray.
+const HIT_MARKER_SIZE: f32 = 0.1;
+
+const CHUNK_COLOR: Color = Color::opaque(255, 220, 0);
+const CHUNK_CORNER_COLOR: Color = Color::opaque(0, 160, 255);
+const RAY_COLOR: Color = Color::opaque(255, 255, 255);
+const HIT_COLOR: Color = Color::opaque(255, 0, 0);
+const NORMAL_COLOR: Color = Color::opaque(0, 255, 0);
+const PLAYER_COLOR: Color = Color::opaque(255, 255, 255);
+const LOAD_RADIUS_COLOR: Color = Color::opaque(255, 0, 255);
+
+/// Something debug drawing can show, each is toggled on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugLayer {
+    /// Wireframes of the physics colliders.
+    Colliders,
+    /// Edges of the chunks in the column of the player and corners of the chunks around it.
+    ChunkBorders,
+    /// Ray the targeted block is picked with, its hit point and the normal of the hit face.
+    PickRay,
+    PlayerBox,
+    /// Circle around the chunk of the player inside of which chunks are kept loaded.
+    LoadRadius,
+}
+
+fn line(context: &mut SceneDrawingContext, begin: Vector3<f32>, end: Vector3<f32>, color: Color) {
+    context.add_line(Line { begin, end, color });
+}
+
+/// Twelve edges of the box between the corners.
+fn wire_box(context: &mut SceneDrawingContext, min: Vector3<f32>, max: Vector3<f32>, color: Color) {
+    let corner = |x: bool, y: bool, z: bool| {
+        Vector3::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        )
+    };
+    for a in [false, true] {
+        for b in [false, true] {
+            line(context, corner(false, a, b), corner(true, a, b), color);
+            line(context, corner(a, false, b), corner(a, true, b), color);
+            line(context, corner(a, b, false), corner(a, b, true), color);
+        }
+    }
+}
+
+/// Debug lines drawn into the drawing context of the scene every frame, for the layers that are
+/// turned on.
+#[derive(Default)]
+pub struct DebugDrawing {
+    layers: HashSet<DebugLayer>,
+}
+
+impl DebugDrawing {
+    /// Turns the layer on or off, returns whether it is on now.
+    pub fn toggle(&mut self, layer: DebugLayer) -> bool {
+        if !self.layers.remove(&layer) {
+            self.layers.insert(layer);
+        }
+        self.layers.contains(&layer)
+    }
+
+    pub fn is_enabled(&self, layer: DebugLayer) -> bool {
+        self.layers.contains(&layer)
+    }
+
+    /// Replaces the lines of the last frame. `hit` is what the pick ray of `reach` blocks hit.
+    pub fn draw(
+        &self,
+        scene: &mut Scene,
+        simulation: &Simulation,
+        hit: Option<&RayHit>,
+        reach: f32,
+    ) {
+        let context = &mut scene.drawing_context;
+        context.clear_lines();
+        if self.is_enabled(DebugLayer::Colliders) {
+            scene.graph.physics.draw(context);
+        }
+        let player = simulation.player();
+        if self.is_enabled(DebugLayer::ChunkBorders) {
+            let center = simulation.center();
+            for y in 0..WORLD_HEIGHT {
+                let min = Vector3::new(center.x, y, center.z) * CHUNK_SIZE;
+                let max = min.add_scalar(CHUNK_SIZE);
+                wire_box(context, min.cast(), max.cast(), CHUNK_COLOR);
+            }
+            let height = (WORLD_HEIGHT * CHUNK_SIZE) as f32;
+            for z in -BORDER_RADIUS..=BORDER_RADIUS + 1 {
+                for x in -BORDER_RADIUS..=BORDER_RADIUS + 1 {
+                    let corner = Vector3::new(center.x + x, 0, center.z + z) * CHUNK_SIZE;
+                    let bottom = corner.cast::<f32>();
+                    line(
+                        context,
+                        bottom,
+                        bottom + Vector3::new(0.0, height, 0.0),
+                        CHUNK_CORNER_COLOR,
+                    );
+                }
+            }
+        }
+        if self.is_enabled(DebugLayer::PickRay) {
+            let eyes = player.position();
+            let end = hit.map_or(eyes + player.look_vector() * reach, |hit| hit.position);
+            line(context, eyes, end, RAY_COLOR);
+            if let Some(hit) = hit {
+                let half = HIT_MARKER_SIZE / 2.0;
+                wire_box(
+                    context,
+                    hit.position.add_scalar(-half),
+                    hit.position.add_scalar(half),
+                    HIT_COLOR,
+                );
+                let normal = hit.face.offset().cast::<f32>();
+                line(context, hit.position, hit.position + normal, NORMAL_COLOR);
+                let block = hit.block.cast::<f32>();
+                wire_box(context, block, block.add_scalar(1.0), HIT_COLOR);
+            }
+        }
+        if self.is_enabled(DebugLayer::PlayerBox) {
+            let (min, max) = player.bounding_box();
+            wire_box(context, min, max, PLAYER_COLOR);
+        }
+        if self.is_enabled(DebugLayer::LoadRadius) {
+            // Chunks whose centers are inside of the circle around the center of the chunk of
+            // the player are in range.
+            let center = simulation.center().cast::<f32>().add_scalar(0.5) * CHUNK_SIZE as f32;
+            let radius = (simulation.view_distance() * CHUNK_SIZE) as f32;
+            let y = player.position().y;
+            let point = |segment: usize| {
+                let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                Vector3::new(
+                    center.x + angle.cos() * radius,
+                    y,
+                    center.z + angle.sin() * radius,
+                )
+            };
+            for segment in 0..CIRCLE_SEGMENTS {
+                line(
+                    context,
+                    point(segment),
+                    point(segment + 1),
+                    LOAD_RADIUS_COLOR,
+                );
+            }
+        }
+    }
+}
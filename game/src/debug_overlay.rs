@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use fyrox::core::algebra::Vector3;
+use fyrox::core::color::Color;
+use fyrox::core::pool::Handle;
+use fyrox::gui::border::BorderBuilder;
+use fyrox::gui::brush::Brush;
+use fyrox::gui::message::MessageDirection;
+use fyrox::gui::stack_panel::StackPanelBuilder;
+use fyrox::gui::text::{TextBuilder, TextMessage};
+use fyrox::gui::widget::{WidgetBuilder, WidgetMessage};
+use fyrox::gui::{
+    HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface, VerticalAlignment,
+};
+
+use crate::biome::Biome;
+use crate::block::Block;
+use crate::chunk::{chunk_position, CHUNK_SIZE};
+use crate::direction::Direction;
+use crate::profiler::{FrameProfile, Scope, FRAME_HISTORY};
+use crate::renderer::MeshStats;
+use crate::visibility::CullingStats;
+
+/// Seconds between refreshes of the text, so the numbers can be read.
+const REFRESH_INTERVAL: f32 = 0.25;
+
+const BACKGROUND_COLOR: Color = Color::from_rgba(0, 0, 0, 120);
+
+const GRAPH_BAR_WIDTH: f32 = 2.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+/// Update time of a frame that fills the height of the graph, longer ones are cut off.
+const GRAPH_MAX_TIME: f32 = 1.0 / 30.0;
+/// Frames updating in less time than this are drawn in the first color, others in the second.
+const FRAME_BUDGET: f32 = 1.0 / 60.0;
+const GRAPH_COLOR: Color = Color::opaque(80, 220, 80);
+const GRAPH_OVER_BUDGET_COLOR: Color = Color::opaque(230, 60, 60);
+
+fn milliseconds(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+/// Block the player looks at.
+pub struct DebugTarget {
+    pub position: Vector3<i32>,
+    pub block: Block,
+    pub face: Direction,
+}
+
+/// Numbers of the last frame the engine rendered.
+pub struct FrameInfo {
+    pub fps: usize,
+    /// Seconds the frame took, without waiting for vertical sync.
+    pub frame_time: f32,
+    pub draw_calls: usize,
+    pub triangles: usize,
+}
+
+/// What the debug overlay shows, gathered from the engine, the world and the renderer.
+pub struct DebugInfo {
+    /// `None` before the renderer exists.
+    pub frame: Option<FrameInfo>,
+    pub position: Vector3<f32>,
+    /// Horizontal direction the camera looks to, `None` when it looks straight up or down.
+    pub facing: Option<Direction>,
+    pub target: Option<DebugTarget>,
+    pub biome: Biome,
+    pub loaded_chunks: usize,
+    pub loading_chunks: usize,
+    pub queued_chunks: usize,
+    pub culling: CullingStats,
+    pub meshes: MeshStats,
+    pub items: usize,
+    /// Last frames of the profiler, the oldest first.
+    pub frames: Vec<FrameProfile>,
+}
+
+impl Display for DebugInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(frame) = &self.frame {
+            writeln!(
+                f,
+                "{} fps ({:.2} ms), {} draw calls, {} triangles",
+                frame.fps,
+                frame.frame_time * 1000.0,
+                frame.draw_calls,
+                frame.triangles
+            )?;
+        }
+        if !self.frames.is_empty() {
+            let mut total = FrameProfile::default();
+            let mut longest = Duration::ZERO;
+            for frame in &self.frames {
+                total.update += frame.update;
+                for (sum, time) in total.scopes.iter_mut().zip(frame.scopes) {
+                    *sum += time;
+                }
+                longest = longest.max(frame.update);
+            }
+            let count = self.frames.len() as f32;
+            writeln!(
+                f,
+                "Update: {:.2} ms, at most {:.2} ms",
+                milliseconds(total.update) / count,
+                milliseconds(longest)
+            )?;
+            let scopes: Vec<String> = Scope::ALL
+                .iter()
+                .map(|scope| {
+                    let average = milliseconds(total.scope(*scope)) / count;
+                    format!("{} {average:.2}", scope.name())
+                })
+                .collect();
+            writeln!(f, "Per frame, in ms: {}", scopes.join(", "))?;
+        }
+        let p = self.position;
+        writeln!(f, "XYZ: {:.2} / {:.2} / {:.2}", p.x, p.y, p.z)?;
+        let block = p.map(|v| v.floor() as i32);
+        let chunk = chunk_position(block);
+        let local = block - chunk * CHUNK_SIZE;
+        writeln!(
+            f,
+            "Chunk: {} {} {} in {} {} {}",
+            local.x, local.y, local.z, chunk.x, chunk.y, chunk.z
+        )?;
+        match self.facing {
+            Some(facing) => writeln!(f, "Facing: {facing:?}")?,
+            None => writeln!(f, "Facing: -")?,
+        }
+        match &self.target {
+            Some(target) => writeln!(
+                f,
+                "Target: {:?} at {} {} {}, {:?} face",
+                target.block, target.position.x, target.position.y, target.position.z, target.face
+            )?,
+            None => writeln!(f, "Target: -")?,
+        }
+        writeln!(f, "Biome: {:?}", self.biome)?;
+        writeln!(
+            f,
+            "Chunks: {} loaded, {} loading, {} queued",
+            self.loaded_chunks, self.loading_chunks, self.queued_chunks
+        )?;
+        writeln!(
+            f,
+            "Culling: {} drawn, {} outside of the view, {} occluded",
+            self.culling.drawn, self.culling.frustum_culled, self.culling.occlusion_culled
+        )?;
+        writeln!(
+            f,
+            "Chunk meshes: {}, {} vertices, {} triangles, {:.1} MiB",
+            self.meshes.meshes,
+            self.meshes.vertices,
+            self.meshes.triangles,
+            self.meshes.memory as f32 / (1024.0 * 1024.0)
+        )?;
+        write!(f, "Dropped items: {}", self.items)
+    }
+}
+
+/// Statistics in the top left corner of the screen, toggled with F3, over a graph of the update
+/// times of the last frames.
+pub struct DebugOverlay {
+    root: Handle<UiNode>,
+    text: Handle<UiNode>,
+    /// Bar of every frame of the graph, the oldest first.
+    bars: Vec<Handle<UiNode>>,
+    visible: bool,
+    /// Seconds since the text was refreshed, only counted while it is shown.
+    elapsed: f32,
+}
+
+impl DebugOverlay {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let ctx = &mut ui.build_ctx();
+        let text = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(6.0))
+                .with_foreground(Brush::Solid(Color::WHITE)),
+        )
+        .build(ctx);
+        let bars: Vec<Handle<UiNode>> = (0..FRAME_HISTORY)
+            .map(|_| {
+                BorderBuilder::new(
+                    WidgetBuilder::new()
+                        .with_width(GRAPH_BAR_WIDTH)
+                        .with_height(0.0)
+                        .with_vertical_alignment(VerticalAlignment::Bottom)
+                        .with_background(Brush::Solid(GRAPH_COLOR)),
+                )
+                .with_stroke_thickness(Thickness::zero())
+                .build(ctx)
+            })
+            .collect();
+        let graph = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_height(GRAPH_HEIGHT)
+                .with_margin(Thickness::uniform(6.0))
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_children(bars.iter().copied()),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let panel = StackPanelBuilder::new(WidgetBuilder::new().with_child(text).with_child(graph))
+            .build(ctx);
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_background(Brush::Solid(BACKGROUND_COLOR))
+                .with_child(panel),
+        )
+        .with_stroke_thickness(Thickness::zero())
+        .build(ctx);
+        Self {
+            root,
+            text,
+            bars,
+            visible: false,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self, ui: &UserInterface) {
+        self.visible = !self.visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            self.visible,
+        ));
+    }
+
+    pub fn remove(&self, ui: &UserInterface) {
+        ui.send_message(WidgetMessage::remove(self.root, MessageDirection::ToWidget));
+    }
+
+    /// Lets `dt` seconds pass, returns whether the overlay is shown and it is time to refresh it.
+    pub fn is_due(&mut self, dt: f32) -> bool {
+        if !self.visible {
+            return false;
+        }
+        self.elapsed += dt;
+        if self.elapsed < REFRESH_INTERVAL {
+            return false;
+        }
+        self.elapsed = 0.0;
+        true
+    }
+
+    pub fn show(&self, ui: &UserInterface, info: &DebugInfo) {
+        ui.send_message(TextMessage::text(
+            self.text,
+            MessageDirection::ToWidget,
+            info.to_string(),
+        ));
+        // The newest frame is on the right, bars without a frame yet stay empty.
+        let empty = self.bars.len().saturating_sub(info.frames.len());
+        for (i, bar) in self.bars.iter().enumerate() {
+            let update = i
+                .checked_sub(empty)
+                .and_then(|index| info.frames.get(index))
+                .map_or(0.0, |frame| frame.update.as_secs_f32());
+            let height = (update / GRAPH_MAX_TIME).min(1.0) * GRAPH_HEIGHT;
+            let color = if update < FRAME_BUDGET {
+                GRAPH_COLOR
+            } else {
+                GRAPH_OVER_BUDGET_COLOR
+            };
+            ui.send_message(WidgetMessage::height(
+                *bar,
+                MessageDirection::ToWidget,
+                height,
+            ));
+            ui.send_message(WidgetMessage::background(
+                *bar,
+                MessageDirection::ToWidget,
+                Brush::Solid(color),
+            ));
+        }
+    }
+}
@@ -1,13 +1,14 @@
+use std::iter::FusedIterator;
 use std::slice::Iter;
 
 use fyrox::core::algebra::{Vector2, Vector3, Vector4};
 use fyrox::core::math::TriangleDefinition;
 use fyrox::scene::mesh::vertex::StaticVertex;
 
-const CLOCK_WISE_TRIANGLE: [TriangleDefinition; 2] =
+pub const CLOCK_WISE_TRIANGLE: [TriangleDefinition; 2] =
     [TriangleDefinition([2, 1, 0]), TriangleDefinition([3, 2, 0])];
 
-const COUNTER_CLOCK_WISE_TRIANGLE: [TriangleDefinition; 2] =
+pub const COUNTER_CLOCK_WISE_TRIANGLE: [TriangleDefinition; 2] =
     [TriangleDefinition([0, 1, 2]), TriangleDefinition([0, 2, 3])];
 
 const FRONT: [StaticVertex; 4] = [
@@ -174,7 +175,10 @@ const LEFT: [StaticVertex; 4] = [
     },
 ];
 
-#[derive(Debug,PartialEq)]
+/// Face of a block or a chunk, and the unit step towards the neighbour behind that face.
+///
+/// Front is +z, top is +y and right is +x.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Direction {
     Front,
     Back,
@@ -185,18 +189,116 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// Position of the direction in `Direction::iterator()`, handy for per-face tables.
+    pub const fn index(&self) -> usize {
+        match self {
+            Direction::Front => 0,
+            Direction::Back => 1,
+            Direction::Top => 2,
+            Direction::Bottom => 3,
+            Direction::Right => 4,
+            Direction::Left => 5,
+        }
+    }
 
-    pub fn vector(&self) -> (i32, i32, i32) {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Front => Direction::Back,
+            Direction::Back => Direction::Front,
+            Direction::Top => Direction::Bottom,
+            Direction::Bottom => Direction::Top,
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    /// Direction at `index`, the reverse of [`Direction::index`].
+    pub fn from_index(index: usize) -> Option<Direction> {
+        Self::iterator().nth(index).copied()
+    }
+
+    /// Face pointing along the positive or negative side of axis `0`, `1` or `2`, `None` for any
+    /// other axis.
+    pub fn from_axis(axis: usize, positive: bool) -> Option<Direction> {
+        match (axis, positive) {
+            (0, true) => Some(Direction::Right),
+            (0, false) => Some(Direction::Left),
+            (1, true) => Some(Direction::Top),
+            (1, false) => Some(Direction::Bottom),
+            (2, true) => Some(Direction::Front),
+            (2, false) => Some(Direction::Back),
+            _ => None,
+        }
+    }
+
+    /// Direction of a unit step between two neighbouring cells, `None` for any other offset.
+    pub fn from_offset(offset: Vector3<i32>) -> Option<Direction> {
+        Self::iterator()
+            .find(|direction| direction.offset() == offset)
+            .copied()
+    }
+
+    /// Face a normal points out of, picked by its longest component. `None` for a zero or
+    /// non-finite normal.
+    pub fn from_normal(normal: Vector3<f32>) -> Option<Direction> {
+        let axis = normal.iamax();
+        if normal[axis] == 0.0 || !normal[axis].is_finite() {
+            return None;
+        }
+        Self::from_axis(axis, normal[axis] > 0.0)
+    }
+
+    /// Axis the direction lies on, `0` for x, `1` for y and `2` for z.
+    pub fn axis(&self) -> usize {
         match self {
-            Direction::Front => (0, 0, 1),
-            Direction::Back => (0, 0, -1),
-            Direction::Top => (0, 1, 0),
-            Direction::Bottom => (0, -1, 0),
-            Direction::Right => (1, 0, 0),
-            Direction::Left => (-1, 0, 0),
+            Direction::Right | Direction::Left => 0,
+            Direction::Top | Direction::Bottom => 1,
+            Direction::Front | Direction::Back => 2,
         }
     }
 
+    /// `1` when the direction points along its axis, `-1` when against it.
+    pub fn sign(&self) -> i32 {
+        match self {
+            Direction::Front | Direction::Top | Direction::Right => 1,
+            Direction::Back | Direction::Bottom | Direction::Left => -1,
+        }
+    }
+
+    /// Unit step as a tuple of x, y and z.
+    #[deprecated(note = "use `Direction::offset`")]
+    pub fn vector(&self) -> (i32, i32, i32) {
+        let offset = self.offset();
+        (offset.x, offset.y, offset.z)
+    }
+
+    pub fn offset(&self) -> Vector3<i32> {
+        let mut offset = Vector3::zeros();
+        offset[self.axis()] = self.sign();
+        offset
+    }
+
+    /// Position of the neighbour on this side, of a block or of a chunk alike.
+    pub fn neighbour(&self, position: Vector3<i32>) -> Vector3<i32> {
+        position + self.offset()
+    }
+
+    /// Direction after quarter turns around the y axis, from x towards z like
+    /// [`Placement`](crate::schematic::Placement). Top and bottom stay as they are.
+    pub fn rotate_y(&self, quarter_turns: u8) -> Direction {
+        let mut direction = *self;
+        for _ in 0..quarter_turns % 4 {
+            direction = match direction {
+                Direction::Right => Direction::Front,
+                Direction::Front => Direction::Left,
+                Direction::Left => Direction::Back,
+                Direction::Back => Direction::Right,
+                vertical => vertical,
+            };
+        }
+        direction
+    }
+
     pub fn triangles(&self) -> [TriangleDefinition; 2] {
         match self {
             Direction::Front | Direction::Top | Direction::Left => CLOCK_WISE_TRIANGLE,
@@ -204,10 +306,8 @@ impl Direction {
         }
     }
 
-    pub fn verticles(&self) -> Vec<StaticVertex>
-    {
-        match self
-        {
+    pub fn verticles(&self) -> Vec<StaticVertex> {
+        match self {
             Direction::Front => FRONT.to_vec(),
             Direction::Back => BACK.to_vec(),
             Direction::Top => TOP.to_vec(),
@@ -229,3 +329,95 @@ impl Direction {
         DIRECTIONS.iter()
     }
 }
+
+/// Set of directions packed into the bits of a byte, bit [`Direction::index`] for each direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DirectionSet(u8);
+
+impl DirectionSet {
+    pub const EMPTY: DirectionSet = DirectionSet(0);
+    pub const ALL: DirectionSet = DirectionSet(0b11_1111);
+
+    /// Set of the listed directions, usable in constants.
+    pub const fn of(directions: &[Direction]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < directions.len() {
+            bits |= 1 << directions[i].index();
+            i += 1;
+        }
+        Self(bits)
+    }
+
+    pub fn contains(&self, direction: &Direction) -> bool {
+        self.0 & (1 << direction.index()) != 0
+    }
+
+    pub fn insert(&mut self, direction: &Direction) {
+        self.0 |= 1 << direction.index();
+    }
+
+    pub fn remove(&mut self, direction: &Direction) {
+        self.0 &= !(1 << direction.index());
+    }
+
+    pub fn union(&self, other: DirectionSet) -> DirectionSet {
+        Self(self.0 | other.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Directions in the set, in the order of [`Direction::iterator`].
+    pub fn iter(&self) -> DirectionSetIter {
+        DirectionSetIter(self.0)
+    }
+}
+
+impl FromIterator<Direction> for DirectionSet {
+    fn from_iter<T: IntoIterator<Item = Direction>>(iter: T) -> Self {
+        let mut set = Self::EMPTY;
+        for direction in iter {
+            set.insert(&direction);
+        }
+        set
+    }
+}
+
+impl IntoIterator for DirectionSet {
+    type Item = Direction;
+    type IntoIter = DirectionSetIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct DirectionSetIter(u8);
+
+impl Iterator for DirectionSetIter {
+    type Item = Direction;
+
+    fn next(&mut self) -> Option<Direction> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Direction::from_index(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for DirectionSetIter {}
+
+impl FusedIterator for DirectionSetIter {}
@@ -0,0 +1,253 @@
+use std::fmt::Write;
+
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::chunk::{BlockSource, CHUNK_SIZE};
+use crate::mesher::{build_mesh, ChunkMesh};
+
+/// Name of the single material every exported mesh uses.
+const MATERIAL: &str = "blocks";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `.obj` with a `.mtl` next to it.
+    Obj,
+    /// `.gltf` with its buffer in a `.bin` next to it.
+    Gltf,
+}
+
+/// File produced by an export, `name` is relative to the directory the main file is written to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Blocks of the box and air around it, so the box is exported as if it were alone in the world.
+struct Clipped<'a, S> {
+    source: &'a S,
+    min: Vector3<i32>,
+    max: Vector3<i32>,
+}
+
+impl<S: BlockSource> BlockSource for Clipped<'_, S> {
+    fn block(&self, position: Vector3<i32>) -> Block {
+        let inside = (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&position[axis]));
+        if inside {
+            self.source.block(position)
+        } else {
+            Block::Air
+        }
+    }
+}
+
+/// Builds the mesh of the box between two opposite corners, both included, with the same mesher
+/// the game draws chunks with. Positions are relative to the minimum corner.
+pub fn build_region_mesh(source: &impl BlockSource, a: Vector3<i32>, b: Vector3<i32>) -> ChunkMesh {
+    let clipped = Clipped {
+        source,
+        min: a.inf(&b),
+        max: a.sup(&b),
+    };
+    let first = clipped.min.map(|v| v.div_euclid(CHUNK_SIZE));
+    let last = clipped.max.map(|v| v.div_euclid(CHUNK_SIZE));
+    let mut mesh = ChunkMesh::default();
+    for y in first.y..=last.y {
+        for z in first.z..=last.z {
+            for x in first.x..=last.x {
+                let origin = Vector3::new(x, y, z) * CHUNK_SIZE;
+                let offset = (origin - clipped.min).cast::<f32>();
+                mesh.append(build_mesh(&clipped, origin, CHUNK_SIZE), offset);
+            }
+        }
+    }
+    mesh
+}
+
+/// Writes the mesh in the format, `name` is the file name without an extension. `texture` is the
+/// path of the block texture relative to the exported files, the material has none without it.
+pub fn export_mesh(
+    mesh: &ChunkMesh,
+    format: ExportFormat,
+    name: &str,
+    texture: Option<&str>,
+) -> Vec<ExportedFile> {
+    match format {
+        ExportFormat::Obj => write_obj(mesh, name, texture),
+        ExportFormat::Gltf => write_gltf(mesh, name, texture),
+    }
+}
+
+fn write_obj(mesh: &ChunkMesh, name: &str, texture: Option<&str>) -> Vec<ExportedFile> {
+    let mut obj = String::new();
+    writeln!(obj, "mtllib {name}.mtl").unwrap();
+    writeln!(obj, "o {name}").unwrap();
+    for vertex in &mesh.vertices {
+        let [x, y, z] = vertex.position.into();
+        writeln!(obj, "v {x} {y} {z}").unwrap();
+    }
+    for vertex in &mesh.vertices {
+        let [u, v] = vertex.tex_coord.into();
+        writeln!(obj, "vt {u} {v}").unwrap();
+    }
+    for vertex in &mesh.vertices {
+        let [x, y, z] = vertex.normal.into();
+        writeln!(obj, "vn {x} {y} {z}").unwrap();
+    }
+    writeln!(obj, "usemtl {MATERIAL}").unwrap();
+    for triangle in &mesh.triangles {
+        // Indices start at one and every vertex uses its own texture coordinate and normal.
+        let [a, b, c] = triangle.0.map(|index| index + 1);
+        writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}").unwrap();
+    }
+
+    let mut mtl = format!("newmtl {MATERIAL}\nKd 1 1 1\n");
+    if let Some(texture) = texture {
+        writeln!(mtl, "map_Kd {texture}").unwrap();
+    }
+
+    vec![
+        ExportedFile {
+            name: format!("{name}.obj"),
+            bytes: obj.into_bytes(),
+        },
+        ExportedFile {
+            name: format!("{name}.mtl"),
+            bytes: mtl.into_bytes(),
+        },
+    ]
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from('"');
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            character if character.is_control() => {
+                write!(escaped, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn floats<const N: usize>(values: impl Iterator<Item = [f32; N]>) -> Vec<u8> {
+    values.flatten().flat_map(f32::to_le_bytes).collect()
+}
+
+/// Positions, normals, texture coordinates and indices, one buffer view each, in that order.
+fn write_gltf(mesh: &ChunkMesh, name: &str, texture: Option<&str>) -> Vec<ExportedFile> {
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+    let mut buffer = Vec::new();
+    let mut views = Vec::new();
+    let mut push_view = |buffer: &mut Vec<u8>, values: Vec<u8>, target: u32| {
+        views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            buffer.len(),
+            values.len()
+        ));
+        buffer.extend(values);
+    };
+    let vertices = &mesh.vertices;
+    push_view(
+        &mut buffer,
+        floats(vertices.iter().map(|vertex| vertex.position.into())),
+        ARRAY_BUFFER,
+    );
+    push_view(
+        &mut buffer,
+        floats(vertices.iter().map(|vertex| vertex.normal.into())),
+        ARRAY_BUFFER,
+    );
+    push_view(
+        &mut buffer,
+        floats(vertices.iter().map(|vertex| vertex.tex_coord.into())),
+        ARRAY_BUFFER,
+    );
+    push_view(
+        &mut buffer,
+        mesh.triangles
+            .iter()
+            .flat_map(|triangle| triangle.0)
+            .flat_map(u32::to_le_bytes)
+            .collect(),
+        ELEMENT_ARRAY_BUFFER,
+    );
+
+    let (min, max) = vertices.iter().fold(
+        (Vector3::repeat(f32::MAX), Vector3::repeat(f32::MIN)),
+        |(min, max), vertex| (min.inf(&vertex.position), max.sup(&vertex.position)),
+    );
+    let count = vertices.len();
+    let accessors = [
+        format!(
+            r#"{{"bufferView":0,"componentType":{FLOAT},"count":{count},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            min.x, min.y, min.z, max.x, max.y, max.z
+        ),
+        format!(r#"{{"bufferView":1,"componentType":{FLOAT},"count":{count},"type":"VEC3"}}"#),
+        format!(r#"{{"bufferView":2,"componentType":{FLOAT},"count":{count},"type":"VEC2"}}"#),
+        format!(
+            r#"{{"bufferView":3,"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR"}}"#,
+            mesh.triangles.len() * 3
+        ),
+    ];
+
+    let name_json = json_string(name);
+    let binary_name = format!("{name}.bin");
+    let mut gltf = String::from(
+        r#"{"asset":{"version":"2.0","generator":"mine64"},"scene":0,"scenes":[{"nodes":[0]}],"#,
+    );
+    // Accessors can't be empty, a region without faces is a node without a mesh.
+    if mesh.is_empty() {
+        write!(gltf, r#""nodes":[{{"name":{name_json}}}]}}"#).unwrap();
+        return vec![ExportedFile {
+            name: format!("{name}.gltf"),
+            bytes: gltf.into_bytes(),
+        }];
+    }
+    write!(
+        gltf,
+        r#""nodes":[{{"name":{name_json},"mesh":0}}],"meshes":[{{"name":{name_json},"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1,"TEXCOORD_0":2}},"indices":3,"material":0}}]}}],"#
+    )
+    .unwrap();
+    match texture {
+        Some(texture) => write!(
+            gltf,
+            r#""materials":[{{"name":"{MATERIAL}","pbrMetallicRoughness":{{"baseColorTexture":{{"index":0}},"metallicFactor":0}}}}],"textures":[{{"source":0}}],"images":[{{"uri":{}}}],"#,
+            json_string(texture)
+        ),
+        None => write!(
+            gltf,
+            r#""materials":[{{"name":"{MATERIAL}","pbrMetallicRoughness":{{"metallicFactor":0}}}}],"#
+        ),
+    }
+    .unwrap();
+    write!(
+        gltf,
+        r#""buffers":[{{"uri":{},"byteLength":{}}}],"bufferViews":[{}],"accessors":[{}]}}"#,
+        json_string(&binary_name),
+        buffer.len(),
+        views.join(","),
+        accessors.join(",")
+    )
+    .unwrap();
+
+    vec![
+        ExportedFile {
+            name: format!("{name}.gltf"),
+            bytes: gltf.into_bytes(),
+        },
+        ExportedFile {
+            name: binary_name,
+            bytes: buffer,
+        },
+    ]
+}
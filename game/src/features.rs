@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use fyrox::core::algebra::Vector3;
+use serde::Deserialize;
+
+use crate::block::{Block, BlockModel};
+use crate::chunk::{chunk_position, local_position, Chunk};
+use crate::schematic::{Placement, Schematic};
+use crate::world_generator::WorldGenerator;
+
+/// Something bigger than a block placed on top of the terrain after it is generated, it may reach
+/// into neighbouring chunks. No feature reaches further than a chunk sideways from the column it
+/// stands on, so it never gets past the chunks right next to it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum Feature {
+    Tree,
+    Boulder,
+    Hut,
+    /// One of the built in structures, by name.
+    Structure(String),
+}
+
+impl Feature {
+    /// Blocks of the feature standing on the ground at `anchor`, the first block above the
+    /// ground. `variation` in `0.0..1.0` picks the size. Empty when the feature doesn't fit there.
+    pub fn blocks(
+        &self,
+        generator: &WorldGenerator,
+        anchor: Vector3<i32>,
+        variation: f32,
+    ) -> Vec<(Vector3<i32>, Block)> {
+        match self {
+            Feature::Tree => tree(anchor, variation),
+            Feature::Boulder => boulder(anchor, variation),
+            Feature::Hut => hut(generator, anchor),
+            Feature::Structure(name) => structure(name).map_or_else(Vec::new, |schematic| {
+                let placement = Placement::from_variation(variation);
+                let size = schematic.placed_size(placement);
+                let origin = anchor - Vector3::new(size.x / 2, 0, size.z / 2);
+                schematic.blocks(origin, placement).collect()
+            }),
+        }
+    }
+}
+
+/// Schematics the `Structure` feature places, built in so the browser build has them as well.
+/// They stand on their middle column, so they may be at most 32 blocks wide.
+const STRUCTURES: [(&str, &[u8]); 1] = [("well", include_bytes!("../../data/structures/well.vox"))];
+
+pub fn structure(name: &str) -> Option<&'static Schematic> {
+    static PARSED: OnceLock<HashMap<&str, Schematic>> = OnceLock::new();
+    PARSED
+        .get_or_init(|| {
+            STRUCTURES
+                .iter()
+                .map(|(name, bytes)| {
+                    let schematic = Schematic::decode(bytes)
+                        .unwrap_or_else(|error| panic!("structure {name} must be valid: {error}"));
+                    (*name, schematic)
+                })
+                .collect()
+        })
+        .get(name)
+}
+
+fn tree(anchor: Vector3<i32>, variation: f32) -> Vec<(Vector3<i32>, Block)> {
+    let height = 4 + (variation * 3.0) as i32;
+    let mut blocks = Vec::new();
+    for y in 0..height {
+        blocks.push((anchor + Vector3::new(0, y, 0), Block::Log));
+    }
+    for y in height - 2..=height {
+        let radius: i32 = if y < height { 2 } else { 1 };
+        for z in -radius..=radius {
+            for x in -radius..=radius {
+                let corner = x.abs() == radius && z.abs() == radius && radius > 1;
+                let trunk = x == 0 && z == 0 && y < height;
+                if !corner && !trunk {
+                    blocks.push((anchor + Vector3::new(x, y, z), Block::Leaves));
+                }
+            }
+        }
+    }
+    blocks
+}
+
+fn boulder(anchor: Vector3<i32>, variation: f32) -> Vec<(Vector3<i32>, Block)> {
+    let radius = if variation < 0.7 { 1 } else { 2 };
+    let center = anchor + Vector3::new(0, radius - 1, 0);
+    let mut blocks = Vec::new();
+    for y in -radius..=radius {
+        for z in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + y * y + z * z <= radius * radius + 1 {
+                    blocks.push((center + Vector3::new(x, y, z), Block::Stone));
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// Planks hut on a stone foundation, placed only where the ground under it is nearly flat.
+fn hut(generator: &WorldGenerator, anchor: Vector3<i32>) -> Vec<(Vector3<i32>, Block)> {
+    const HALF_SIZE: i32 = 2;
+    const WALL_HEIGHT: i32 = 3;
+    let mut grounds = Vec::new();
+    for z in -HALF_SIZE..=HALF_SIZE {
+        for x in -HALF_SIZE..=HALF_SIZE {
+            grounds.push((x, z, generator.height(anchor.x + x, anchor.z + z)));
+        }
+    }
+    if !grounds
+        .iter()
+        .all(|(_, _, ground)| (anchor.y - 3..anchor.y).contains(ground))
+    {
+        return Vec::new();
+    }
+    let mut blocks = Vec::new();
+    for (x, z, ground) in grounds {
+        let column = anchor + Vector3::new(x, 0, z);
+        for y in ground + 1..anchor.y {
+            blocks.push((Vector3::new(column.x, y, column.z), Block::Stone));
+        }
+        blocks.push((column, Block::Planks));
+        let wall = x.abs() == HALF_SIZE || z.abs() == HALF_SIZE;
+        for y in 1..=WALL_HEIGHT {
+            let door = x == 0 && z == -HALF_SIZE && y < WALL_HEIGHT;
+            let block = if wall && !door {
+                Block::Planks
+            } else {
+                Block::Air
+            };
+            blocks.push((column + Vector3::new(0, y, 0), block));
+        }
+        blocks.push((
+            column + Vector3::new(0, WALL_HEIGHT + 1, 0),
+            Block::StoneSlab,
+        ));
+    }
+    blocks
+}
+
+/// Whether a feature block may replace the block that is already there. Features never dig into
+/// the terrain, they only replace air, water, plants and leaves.
+fn can_replace(existing: Block, block: Block) -> bool {
+    match existing {
+        Block::Air | Block::Water => !block.is_air(),
+        Block::Leaves => block != Block::Leaves,
+        existing => existing.model() == BlockModel::Cross,
+    }
+}
+
+/// Blocks placed by features that wait for their chunks to be generated, grouped by chunk.
+#[derive(Debug, Default)]
+pub struct PendingEdits {
+    edits: HashMap<Vector3<i32>, Vec<(Vector3<i32>, Block)>>,
+}
+
+impl PendingEdits {
+    /// Queues a block at a world position.
+    pub fn push(&mut self, position: Vector3<i32>, block: Block) {
+        self.edits
+            .entry(chunk_position(position))
+            .or_default()
+            .push((position, block));
+    }
+
+    /// Writes the queued blocks that fall into the chunk, in the order they were queued.
+    pub fn apply(&self, chunk: &mut Chunk) {
+        for (position, block) in self.edits.get(&chunk.position()).into_iter().flatten() {
+            let local = local_position(*position);
+            if can_replace(chunk.get(local), *block) {
+                chunk.set(local, *block);
+            }
+        }
+    }
+}
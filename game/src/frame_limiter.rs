@@ -0,0 +1,37 @@
+//! Keeps frames from coming faster than the frame cap of the settings.
+use std::time::{Duration, Instant};
+
+/// Start of the next frame, frames that come earlier wait for it.
+#[derive(Debug, Default)]
+pub struct FrameLimiter {
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// How long a frame that comes at `now` waits, `max_fps` is 0 for no cap. A frame that comes
+    /// late moves the next ones back rather than letting them catch up.
+    pub fn delay(&mut self, now: Instant, max_fps: u32) -> Duration {
+        if max_fps == 0 {
+            self.next_frame = None;
+            return Duration::ZERO;
+        }
+        let start = match self.next_frame {
+            Some(next) if next > now => next,
+            _ => now,
+        };
+        self.next_frame = Some(start + Duration::from_secs_f64(1.0 / max_fps as f64));
+        start - now
+    }
+
+    /// Sleeps until the frame may start. Browsers cap frames themselves and can't sleep, there it
+    /// returns at once.
+    pub fn wait(&mut self, max_fps: u32) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        let delay = self.delay(Instant::now(), max_fps);
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use serde::Deserialize;
+
+use crate::biome::Biome;
+use crate::block::Block;
+use crate::features::{structure, Feature};
+
+const DEFAULT_SETTINGS: &str = include_str!("../../data/generation.ron");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TerrainSettings {
+    /// Frequency of the height noise, one over the size of hills in blocks.
+    pub horizontal_scale: f32,
+    /// How many blocks the density noise can move the ground up or down, which makes cliffs and
+    /// overhangs.
+    pub overhang_strength: f32,
+    pub overhang_scale: f32,
+    /// Blocks of the subsurface block of a biome under its surface, stone starts below them.
+    pub subsurface_depth: i32,
+    /// Empty space below this height is filled with water.
+    pub sea_level: i32,
+    /// Surface of the ground under water, whatever the biome.
+    pub underwater_surface: Block,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClimateSettings {
+    /// Frequency of the temperature and humidity noises.
+    pub scale: f32,
+    /// Stretches the noises away from their middle, fractal noise rarely gets close to its ends.
+    pub contrast: f32,
+    /// How fast the influence of a biome on the height drops with its distance from the climate
+    /// of a column, higher makes the transitions between biomes narrower.
+    pub blend_sharpness: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Decoration {
+    pub block: Block,
+    /// Chance of the block being placed on a surface block.
+    pub chance: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeatureSettings {
+    pub feature: Feature,
+    /// Chance of the feature standing on a surface block.
+    pub chance: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BiomeSettings {
+    pub biome: Biome,
+    /// Climate the biome is the most typical for in `0.0..1.0`, columns get the biome closest to
+    /// their own climate.
+    pub temperature: f32,
+    pub humidity: f32,
+    /// Height of the ground where the height noise is lowest.
+    pub base_height: f32,
+    pub height_variation: f32,
+    pub surface: Block,
+    pub subsurface: Block,
+    pub decorations: Vec<Decoration>,
+    pub features: Vec<FeatureSettings>,
+}
+
+/// Caves are only carved through stone.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaveSettings {
+    /// Large open caves are carved where their noise is above the threshold.
+    pub cheese_scale: f32,
+    pub cheese_threshold: f32,
+    /// Tunnels are carved where two noises are both within the radius of their middle.
+    pub worm_scale: f32,
+    pub worm_radius: f32,
+    /// Nothing is carved below this height, so the world keeps a floor.
+    pub min_height: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OreSettings {
+    pub block: Block,
+    /// Heights the ore appears at, both inclusive.
+    pub min_height: i32,
+    pub max_height: i32,
+    /// Frequency of the vein noise, higher makes more and smaller veins.
+    pub frequency: f32,
+    /// Stone becomes ore where the vein noise is above this, higher makes veins rarer.
+    pub threshold: f32,
+}
+
+/// Everything world generation can be tuned with, read from `data/generation.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GenerationSettings {
+    pub terrain: TerrainSettings,
+    pub climate: ClimateSettings,
+    /// At least one biome is required.
+    pub biomes: Vec<BiomeSettings>,
+    pub caves: CaveSettings,
+    /// When veins of several ores overlap the first one listed wins.
+    pub ores: Vec<OreSettings>,
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        let settings: Self =
+            ron::from_str(DEFAULT_SETTINGS).expect("data/generation.ron must be valid");
+        for feature in settings.biomes.iter().flat_map(|biome| &biome.features) {
+            if let Feature::Structure(name) = &feature.feature {
+                assert!(structure(name).is_some(), "unknown structure {name}");
+            }
+        }
+        settings
+    }
+}
@@ -0,0 +1,321 @@
+use fyrox::core::algebra::Vector2;
+use fyrox::core::color::Color;
+use fyrox::core::pool::Handle;
+use fyrox::gui::border::BorderBuilder;
+use fyrox::gui::brush::Brush;
+use fyrox::gui::grid::{Column, GridBuilder, Row};
+use fyrox::gui::message::MessageDirection;
+use fyrox::gui::stack_panel::StackPanelBuilder;
+use fyrox::gui::text::{TextBuilder, TextMessage};
+use fyrox::gui::widget::{WidgetBuilder, WidgetMessage};
+use fyrox::gui::{
+    BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+    VerticalAlignment,
+};
+
+use crate::inventory::{Inventory, HOTBAR_SIZE};
+use crate::player::{Player, MAX_AIR, MAX_HEALTH};
+
+/// Slots are this part of the shorter side of the screen, within `MIN_SLOT_SIZE..MAX_SLOT_SIZE`.
+const SLOT_SCREEN_FRACTION: f32 = 0.08;
+const MIN_SLOT_SIZE: f32 = 28.0;
+const MAX_SLOT_SIZE: f32 = 64.0;
+
+const CROSSHAIR_COLOR: Color = Color::opaque(230, 230, 230);
+const SLOT_COLOR: Color = Color::from_rgba(0, 0, 0, 140);
+const SLOT_STROKE_COLOR: Color = Color::opaque(90, 90, 90);
+const SELECTED_STROKE_COLOR: Color = Color::opaque(255, 255, 255);
+const BAR_BACKGROUND_COLOR: Color = Color::from_rgba(0, 0, 0, 140);
+const HEALTH_COLOR: Color = Color::opaque(200, 40, 40);
+const AIR_COLOR: Color = Color::opaque(60, 140, 230);
+
+struct Slot {
+    frame: Handle<UiNode>,
+    /// Square in the color of the block. Blocks have no textures, the world draws them in the
+    /// same colors.
+    icon: Handle<UiNode>,
+    count: Handle<UiNode>,
+}
+
+/// Frame with a fill that is as wide as the part of the value that is left.
+struct Bar {
+    frame: Handle<UiNode>,
+    fill: Handle<UiNode>,
+}
+
+/// What the HUD shows last, it only sends messages for what changed.
+#[derive(PartialEq)]
+struct Shown {
+    inventory: Inventory,
+    health: f32,
+    air: f32,
+}
+
+/// Crosshair in the middle of the screen, and at the bottom the hotbar, the name of the selected
+/// block and health and air bars. Sizes follow the screen, so it fits desktop windows as well as
+/// phones and browsers, the whole HUD is built again when the screen size changes.
+pub struct Hud {
+    root: Handle<UiNode>,
+    screen_size: Vector2<f32>,
+    slots: Vec<Slot>,
+    name: Handle<UiNode>,
+    health: Bar,
+    air: Bar,
+    bar_width: f32,
+    shown: Option<Shown>,
+}
+
+fn solid(color: Color) -> Brush {
+    Brush::Solid(color)
+}
+
+fn block_color(color: [u8; 3]) -> Color {
+    Color::opaque(color[0], color[1], color[2])
+}
+
+fn build_crosshair(ctx: &mut BuildContext, size: f32) -> Handle<UiNode> {
+    let thickness = (size / 8.0).max(2.0);
+    let line = |ctx: &mut BuildContext, width: f32, height: f32| {
+        BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(width)
+                .with_height(height)
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_background(solid(CROSSHAIR_COLOR)),
+        )
+        .with_stroke_thickness(Thickness::zero())
+        .build(ctx)
+    };
+    let horizontal = line(ctx, size, thickness);
+    let vertical = line(ctx, thickness, size);
+    GridBuilder::new(
+        WidgetBuilder::new()
+            .with_width(size)
+            .with_height(size)
+            .with_horizontal_alignment(HorizontalAlignment::Center)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_child(horizontal)
+            .with_child(vertical),
+    )
+    .add_row(Row::stretch())
+    .add_column(Column::stretch())
+    .build(ctx)
+}
+
+fn build_slot(ctx: &mut BuildContext, size: f32) -> Slot {
+    let icon = BorderBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(Thickness::uniform(size * 0.2))
+            .with_visibility(false),
+    )
+    .with_stroke_thickness(Thickness::zero())
+    .build(ctx);
+    let count = TextBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(Thickness::uniform(size * 0.06))
+            .with_horizontal_alignment(HorizontalAlignment::Right)
+            .with_vertical_alignment(VerticalAlignment::Bottom)
+            .with_foreground(solid(Color::WHITE)),
+    )
+    .build(ctx);
+    let content = GridBuilder::new(WidgetBuilder::new().with_child(icon).with_child(count))
+        .add_row(Row::stretch())
+        .add_column(Column::stretch())
+        .build(ctx);
+    let frame = BorderBuilder::new(
+        WidgetBuilder::new()
+            .with_width(size)
+            .with_height(size)
+            .with_margin(Thickness::uniform(size * 0.03))
+            .with_background(solid(SLOT_COLOR))
+            .with_foreground(solid(SLOT_STROKE_COLOR))
+            .with_child(content),
+    )
+    .with_stroke_thickness(Thickness::uniform((size * 0.05).max(1.0)))
+    .build(ctx);
+    Slot { frame, icon, count }
+}
+
+fn build_bar(ctx: &mut BuildContext, width: f32, height: f32, color: Color) -> Bar {
+    let fill = BorderBuilder::new(
+        WidgetBuilder::new()
+            .with_width(width)
+            .with_horizontal_alignment(HorizontalAlignment::Left)
+            .with_background(solid(color)),
+    )
+    .with_stroke_thickness(Thickness::zero())
+    .build(ctx);
+    let frame = BorderBuilder::new(
+        WidgetBuilder::new()
+            .with_width(width)
+            .with_height(height)
+            .with_margin(Thickness::uniform(height * 0.25))
+            .with_background(solid(BAR_BACKGROUND_COLOR))
+            .with_child(fill),
+    )
+    .with_stroke_thickness(Thickness::zero())
+    .build(ctx);
+    Bar { frame, fill }
+}
+
+impl Hud {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let screen_size = ui.screen_size();
+        let slot_size = (screen_size.x.min(screen_size.y) * SLOT_SCREEN_FRACTION)
+            .clamp(MIN_SLOT_SIZE, MAX_SLOT_SIZE);
+        // Each bar spans half of the hotbar.
+        let bar_width = slot_size * HOTBAR_SIZE as f32 / 2.0 - slot_size * 0.25;
+        let bar_height = (slot_size * 0.2).max(6.0);
+
+        let ctx = &mut ui.build_ctx();
+        let crosshair = build_crosshair(ctx, slot_size * 0.4);
+        let slots: Vec<Slot> = (0..HOTBAR_SIZE)
+            .map(|_| build_slot(ctx, slot_size))
+            .collect();
+        let hotbar = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_children(slots.iter().map(|slot| slot.frame)),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let health = build_bar(ctx, bar_width, bar_height, HEALTH_COLOR);
+        let air = build_bar(ctx, bar_width, bar_height, AIR_COLOR);
+        let bars = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_child(health.frame)
+                .with_child(air.frame),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let name = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(slot_size * 0.1))
+                .with_foreground(solid(Color::WHITE)),
+        )
+        .with_horizontal_text_alignment(HorizontalAlignment::Center)
+        .build(ctx);
+        let bottom = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::bottom(slot_size * 0.2))
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Bottom)
+                .with_child(name)
+                .with_child(bars)
+                .with_child(hotbar),
+        )
+        .build(ctx);
+        let root = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_width(screen_size.x)
+                .with_height(screen_size.y)
+                .with_child(crosshair)
+                .with_child(bottom),
+        )
+        .add_row(Row::stretch())
+        .add_column(Column::stretch())
+        .build(ctx);
+
+        Self {
+            root,
+            screen_size,
+            slots,
+            name,
+            health,
+            air,
+            bar_width,
+            shown: None,
+        }
+    }
+
+    pub fn set_visible(&self, ui: &UserInterface, visible: bool) {
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    pub fn remove(&self, ui: &UserInterface) {
+        ui.send_message(WidgetMessage::remove(self.root, MessageDirection::ToWidget));
+    }
+
+    /// Shows the state of the player, rebuilds the HUD first when the screen was resized.
+    pub fn update(&mut self, ui: &mut UserInterface, player: &Player) {
+        if ui.screen_size() != self.screen_size {
+            self.remove(ui);
+            *self = Self::new(ui);
+        }
+        let shown = Shown {
+            inventory: player.inventory().clone(),
+            health: player.health(),
+            air: player.air(),
+        };
+        if self.shown.as_ref() == Some(&shown) {
+            return;
+        }
+
+        let inventory = &shown.inventory;
+        for (index, slot) in self.slots.iter().enumerate() {
+            let stack = inventory.slot(index);
+            let stroke = if index == inventory.selected() {
+                SELECTED_STROKE_COLOR
+            } else {
+                SLOT_STROKE_COLOR
+            };
+            ui.send_message(WidgetMessage::foreground(
+                slot.frame,
+                MessageDirection::ToWidget,
+                solid(stroke),
+            ));
+            ui.send_message(WidgetMessage::visibility(
+                slot.icon,
+                MessageDirection::ToWidget,
+                stack.is_some(),
+            ));
+            if let Some(stack) = stack {
+                ui.send_message(WidgetMessage::background(
+                    slot.icon,
+                    MessageDirection::ToWidget,
+                    solid(block_color(stack.block.color())),
+                ));
+            }
+            let count = stack
+                .filter(|stack| stack.count > 1)
+                .map_or(String::new(), |stack| stack.count.to_string());
+            ui.send_message(TextMessage::text(
+                slot.count,
+                MessageDirection::ToWidget,
+                count,
+            ));
+        }
+        let name = inventory
+            .selected_stack()
+            .map_or("", |stack| stack.block.name());
+        ui.send_message(TextMessage::text(
+            self.name,
+            MessageDirection::ToWidget,
+            name.to_string(),
+        ));
+
+        self.set_bar(ui, &self.health, shown.health / MAX_HEALTH);
+        self.set_bar(ui, &self.air, shown.air / MAX_AIR);
+        // Air only matters under water.
+        ui.send_message(WidgetMessage::visibility(
+            self.air.frame,
+            MessageDirection::ToWidget,
+            shown.air < MAX_AIR,
+        ));
+        self.shown = Some(shown);
+    }
+
+    fn set_bar(&self, ui: &UserInterface, bar: &Bar, fraction: f32) {
+        ui.send_message(WidgetMessage::width(
+            bar.fill,
+            MessageDirection::ToWidget,
+            self.bar_width * fraction.clamp(0.0, 1.0),
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::block::Block;
+use crate::item::ItemStack;
+use crate::save::{ByteReader, SaveError};
+
+/// Slots of the hotbar, they come first in the inventory.
+pub const HOTBAR_SIZE: usize = 9;
+
+/// Slots of the main storage, after the hotbar.
+pub const STORAGE_SIZE: usize = 27;
+
+pub const INVENTORY_SIZE: usize = HOTBAR_SIZE + STORAGE_SIZE;
+
+/// Items the player carries. The first `HOTBAR_SIZE` slots are the hotbar, the selected one of
+/// them holds the blocks that get placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    slots: [Option<ItemStack>; INVENTORY_SIZE],
+    selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: [None; INVENTORY_SIZE],
+            selected: 0,
+        }
+    }
+}
+
+impl Inventory {
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.slots[..HOTBAR_SIZE]
+    }
+
+    /// Stack in the slot, `None` when the slot is empty or there is no such slot.
+    pub fn slot(&self, index: usize) -> Option<ItemStack> {
+        self.slots.get(index).copied().flatten()
+    }
+
+    /// Puts the stack into the slot, an empty stack empties it.
+    pub fn set_slot(&mut self, index: usize, stack: Option<ItemStack>) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            *slot = stack.filter(|stack| stack.count > 0);
+        }
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a < INVENTORY_SIZE && b < INVENTORY_SIZE {
+            self.slots.swap(a, b);
+        }
+    }
+
+    /// Index of the selected hotbar slot.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects a hotbar slot, other indices are ignored.
+    pub fn select(&mut self, index: usize) {
+        if index < HOTBAR_SIZE {
+            self.selected = index;
+        }
+    }
+
+    /// Moves the selection by `steps` slots, wrapping around the ends of the hotbar.
+    pub fn scroll(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slot(self.selected)
+    }
+
+    /// Takes one item of the selected stack, returns its block.
+    pub fn take_selected(&mut self) -> Option<Block> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        stack.count -= 1;
+        let block = stack.block;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(block)
+    }
+
+    /// Items of the block in all slots together.
+    pub fn count(&self, block: Block) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.block == block)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Adds as much of the stack as fits, first onto stacks of the same block, then into empty
+    /// slots, the hotbar before the storage. Returns what did not fit.
+    pub fn add(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let mut left = stack.count;
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.block == stack.block {
+                let moved = left.min(slot.max_count().saturating_sub(slot.count));
+                slot.count += moved;
+                left -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if left == 0 {
+                break;
+            }
+            let moved = left.min(stack.max_count());
+            if moved == 0 {
+                break;
+            }
+            *slot = Some(ItemStack::new(stack.block, moved));
+            left -= moved;
+        }
+        (left > 0).then_some(ItemStack::new(stack.block, left))
+    }
+
+    /// Number of slots, then the block id and count of every slot, and the selected slot.
+    /// Empty slots are air.
+    pub(crate) fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.push(INVENTORY_SIZE as u8);
+        for slot in &self.slots {
+            let (block, count) = slot.map_or((Block::Air, 0), |stack| (stack.block, stack.count));
+            bytes.push(block.id());
+            bytes.extend_from_slice(&(count as u16).to_le_bytes());
+        }
+        bytes.push(self.selected as u8);
+    }
+
+    pub(crate) fn decode(reader: &mut ByteReader) -> Result<Self, SaveError> {
+        let mut inventory = Self::default();
+        let slots = reader.u8()? as usize;
+        if slots > INVENTORY_SIZE {
+            return Err(SaveError::Corrupted(format!("inventory has {slots} slots")));
+        }
+        for index in 0..slots {
+            let id = reader.u8()?;
+            let block = Block::from_id(id)
+                .ok_or_else(|| SaveError::Corrupted(format!("unknown item block id {id}")))?;
+            let stack = ItemStack::new(block, reader.u16()? as u32);
+            if stack.count > stack.max_count() {
+                return Err(SaveError::Corrupted(format!(
+                    "inventory slot {index} holds {stack:?}"
+                )));
+            }
+            inventory.set_slot(index, Some(stack));
+        }
+        inventory.select(reader.u8()? as usize);
+        Ok(inventory)
+    }
+}
@@ -0,0 +1,152 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::chunk::{chunk_position, CHUNK_SIZE};
+use crate::world::World;
+
+/// Edge of the little cube a dropped item is drawn and collides as.
+pub const ITEM_SIZE: f32 = 0.25;
+
+/// Most items of one block a stack can hold.
+const MAX_STACK: u32 = 64;
+
+/// Blocks per second squared.
+const GRAVITY: f32 = 20.0;
+
+/// Items never fall faster than this many blocks per second.
+const MAX_FALL_SPEED: f32 = 30.0;
+
+/// Part of the speed kept when an item hits a block.
+const BOUNCE: f32 = 0.4;
+
+/// Bounces slower than this many blocks per second stop the item instead.
+const MIN_BOUNCE_SPEED: f32 = 1.0;
+
+/// Part of the horizontal speed an item lying on the ground loses every second.
+const GROUND_FRICTION: f32 = 4.0;
+
+/// Items lying on the ground slower than this many blocks per second stop sliding.
+const MIN_SLIDE_SPEED: f32 = 0.05;
+
+/// Seconds after dropping before an item can be collected, so a broken block is seen falling.
+const PICKUP_DELAY: f32 = 0.5;
+
+/// Seconds an item stays in the world before it disappears.
+pub const DESPAWN_TIME: f32 = 300.0;
+
+/// Items of the same block closer than this many blocks become one stack.
+pub const MERGE_DISTANCE: f32 = 0.75;
+
+/// Some blocks of a kind, what the player collects and carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemStack {
+    pub block: Block,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(block: Block, count: u32) -> Self {
+        Self { block, count }
+    }
+
+    /// Most items a stack of the block can hold, air is never an item.
+    pub fn max_count(&self) -> u32 {
+        if self.block.is_air() {
+            0
+        } else {
+            MAX_STACK
+        }
+    }
+}
+
+/// Item lying or falling in the world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    /// Center of the item.
+    pub position: Vector3<f32>,
+    /// Blocks per second.
+    pub velocity: Vector3<f32>,
+    /// Seconds since the item was dropped.
+    pub age: f32,
+}
+
+impl DroppedItem {
+    pub fn new(stack: ItemStack, position: Vector3<f32>, velocity: Vector3<f32>) -> Self {
+        Self {
+            stack,
+            position,
+            velocity,
+            age: 0.0,
+        }
+    }
+
+    /// Chunk the item lies in, it is saved with that chunk.
+    pub fn chunk(&self) -> Vector3<i32> {
+        chunk_position(self.position.map(|v| v.floor() as i32))
+    }
+
+    pub fn can_be_collected(&self) -> bool {
+        self.age >= PICKUP_DELAY
+    }
+
+    /// Whether the item should disappear, because it is old or fell out of the bottom of the world.
+    pub fn is_expired(&self) -> bool {
+        self.age >= DESPAWN_TIME || self.position.y < -CHUNK_SIZE as f32
+    }
+
+    /// Whether the other item can be added to this one.
+    pub fn can_merge(&self, other: &DroppedItem) -> bool {
+        self.stack.block == other.stack.block
+            && self.stack.count + other.stack.count <= self.stack.max_count()
+            && (self.position - other.position).norm() < MERGE_DISTANCE
+    }
+
+    /// Falls and slides for `dt` seconds, bouncing off the collision boxes of blocks. Chunks that
+    /// are not loaded are solid, so items never leave the loaded part of the world.
+    pub fn step(&mut self, world: &World, dt: f32) {
+        self.age += dt;
+        self.velocity.y = (self.velocity.y - GRAVITY * dt).max(-MAX_FALL_SPEED);
+        // Sub steps are short enough that an item can't pass through a block in one of them.
+        let distance = self.velocity.norm() * dt;
+        let steps = (distance / (ITEM_SIZE / 2.0)).ceil().clamp(1.0, 16.0) as usize;
+        let dt = dt / steps as f32;
+        let mut on_ground = false;
+        for _ in 0..steps {
+            for axis in 0..3 {
+                let speed = self.velocity[axis];
+                if speed == 0.0 {
+                    continue;
+                }
+                let half = ITEM_SIZE / 2.0;
+                let (min, max) = (
+                    self.position.add_scalar(-half),
+                    self.position.add_scalar(half),
+                );
+                let distance = speed * dt;
+                let moved = world.sweep(min, max, axis, distance);
+                self.position[axis] += moved;
+                if moved == distance {
+                    continue;
+                }
+                if axis == 1 && speed < 0.0 {
+                    on_ground = true;
+                }
+                self.velocity[axis] = if speed.abs() * BOUNCE < MIN_BOUNCE_SPEED {
+                    0.0
+                } else {
+                    -speed * BOUNCE
+                };
+            }
+        }
+        if on_ground {
+            let keep = (1.0 - GROUND_FRICTION * dt * steps as f32).max(0.0);
+            self.velocity.x *= keep;
+            self.velocity.z *= keep;
+            if self.velocity.xz().norm() < MIN_SLIDE_SPEED {
+                self.velocity.x = 0.0;
+                self.velocity.z = 0.0;
+            }
+        }
+    }
+}
@@ -1,37 +1,24 @@
 //! Game project.
 use std::path::Path;
-use std::sync::{Arc, Mutex};
-use std::thread::JoinHandle;
 
-use direction::Direction;
 use fyrox::core::algebra::Matrix4;
-use fyrox::core::log::Log;
-use fyrox::core::math::TriangleDefinition;
 use fyrox::engine::GraphicsContext;
 use fyrox::event::DeviceEvent::MouseMotion;
 use fyrox::event::DeviceEvent::Button;
-use fyrox::event::MouseButton;
-use fyrox::event::WindowEvent::KeyboardInput;
-use fyrox::gui::draw::Vertex;
-use fyrox::gui::message::MessageData;
-use fyrox::material::shader::{SamplerFallback, Shader, self, ShaderResource};
-use fyrox::material::{Material, PropertyValue, SharedMaterial};
-use fyrox::resource::texture::Texture;
+use fyrox::event::{MouseButton, MouseScrollDelta};
+use fyrox::event::WindowEvent::{KeyboardInput, MouseInput, MouseWheel};
 use fyrox::scene::base::BaseBuilder;
-use fyrox::scene::collider::{ColliderBuilder, ColliderShape, TrimeshShape, GeometrySource};
-use fyrox::scene::graph::physics::RayCastOptions;
-use fyrox::scene::light::directional::DirectionalLight;
-use fyrox::scene::mesh::buffer::{VertexBuffer, TriangleBuffer};
-use fyrox::scene::mesh::vertex::StaticVertex;
-use fyrox::scene::mesh::{MeshBuilder, Mesh, surface};
-use fyrox::scene::mesh::surface::{SurfaceData, SurfaceSharedData, SurfaceBuilder, Surface};
-use fyrox::scene::node::NodeTrait;
-use fyrox::scene::rigidbody::RigidBodyBuilder;
+use fyrox::scene::camera::Projection;
+use fyrox::scene::graph::Graph;
+use fyrox::scene::mesh::MeshBuilder;
+use fyrox::scene::mesh::surface::{SurfaceData, SurfaceSharedData, SurfaceBuilder};
 use fyrox::scene::transform::TransformBuilder;
-use fyrox::window::CursorGrabMode;
+use fyrox::scene::SceneContainer;
+use fyrox::window::{CursorGrabMode, Fullscreen};
 use fyrox::{
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::Vector3,
+        io::{load_file, FileLoadError},
         pool::Handle,
     },
     event::{ElementState, Event},
@@ -41,18 +28,108 @@ use fyrox::{
     scene::{node::Node, Scene},
 };
 
-// use chunk::SectorBuilder;
-
-// use crate::world::World;
-
-// mod block;
-
-// mod chunk;
-
-// mod algorithm;
-mod direction;
-// mod world;
-// mod world_generator;
+use chunk::{chunk_position, BlockSource};
+use command::{
+    register_world_commands, CommandContext, CommandEffect, CommandError, CommandRegistry,
+};
+use console::Console;
+use debug_draw::{DebugDrawing, DebugLayer};
+use debug_overlay::{DebugInfo, DebugOverlay, DebugTarget, FrameInfo};
+use direction::Direction;
+use export::{build_region_mesh, export_mesh, ExportFormat};
+use frame_limiter::FrameLimiter;
+use generation_settings::GenerationSettings;
+use hud::Hud;
+use logging::Category;
+use menu::{Menu, MenuAction};
+use player::PlayerInput;
+use renderer::{ChunkRenderer, ItemRenderer};
+use scene_voxels::{build_voxel_mesh, voxels_path};
+use schematic::Schematic;
+use settings::{key_character, save_settings, Action, GameSettings};
+use simulation::Simulation;
+use storage::{MemoryStorage, Storage};
+use visibility::{ChunkCulling, Connectivity, CullingStats};
+use world::World;
+
+pub mod block;
+pub mod brush;
+
+pub mod chunk;
+mod chunk_loader;
+pub mod command;
+mod console;
+mod debug_draw;
+mod debug_overlay;
+
+mod algorithm;
+mod biome;
+pub mod direction;
+pub mod export;
+mod features;
+pub mod frame_limiter;
+pub mod generation_settings;
+mod hud;
+pub mod inventory;
+pub mod item;
+mod lod;
+pub mod logging;
+mod menu;
+pub mod mesher;
+pub mod player;
+pub mod profiler;
+pub mod region;
+mod renderer;
+pub mod save;
+pub mod scene_voxels;
+pub mod schematic;
+pub mod screenshot;
+pub mod settings;
+pub mod simulation;
+pub mod storage;
+mod visibility;
+pub mod world;
+mod world_generator;
+pub mod worlds;
+
+const WORLD_SEED: u32 = 64;
+
+/// Scene worlds are played in, unless the editor runs the game with another one.
+const SCENE_PATH: &str = "data/scene.rgs";
+
+/// Directory with a directory for every world on desktop, key prefix in the browser storage on
+/// wasm.
+const SAVES_DIRECTORY: &str = "saves";
+
+/// Where F9 writes meshes of the blocks around the player.
+const EXPORT_DIRECTORY: &str = "exports";
+
+/// Blocks around the player that F9 exports in every direction.
+const EXPORT_RADIUS: i32 = 32;
+
+/// Where the log file is written on desktop.
+#[cfg(not(target_arch = "wasm32"))]
+const LOGS_DIRECTORY: &str = "logs";
+
+/// Bytes a log file grows to before a new one is started.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Log files of earlier runs kept next to the current one.
+#[cfg(not(target_arch = "wasm32"))]
+const KEPT_LOGS: usize = 3;
+
+/// Name of the mesh node of the blocks painted in the scene.
+const SCENE_VOXELS_NAME: &str = "SceneVoxels";
+
+/// Directory of scripts with commands, on desktop.
+const SCRIPTS_DIRECTORY: &str = "scripts";
+
+/// Script in the scripts directory that runs in every world once it has loaded.
+const STARTUP_SCRIPT: &str = "startup.txt";
+
+/// Blocks farther away than this can't be broken or built on.
+const REACH: f32 = 10.0;
 
 pub struct GameConstructor;
 
@@ -67,264 +144,761 @@ impl PluginConstructor for GameConstructor {
 }
 
 struct InputController {
-    move_forward: bool,
-    move_backward: bool,
-    move_left: bool,
-    move_right: bool,
-    move_up: bool,
-    move_down: bool,
-    pitch: f32,
-    yaw: f32,
+    player: PlayerInput,
 
     mouse_right_button_pressed: bool,
+    /// Set when the button is pressed, the targeted block is broken on the next update.
+    break_block: bool,
+    /// Set on right click, a block of the selected stack is placed on the next update.
+    place_block: bool,
+}
+
+/// Debug drawing layer toggled by a function key.
+fn debug_layer(code: KeyCode) -> Option<DebugLayer> {
+    match code {
+        KeyCode::F4 => Some(DebugLayer::Colliders),
+        KeyCode::F5 => Some(DebugLayer::ChunkBorders),
+        KeyCode::F6 => Some(DebugLayer::PickRay),
+        KeyCode::F7 => Some(DebugLayer::PlayerBox),
+        KeyCode::F8 => Some(DebugLayer::LoadRadius),
+        _ => None,
+    }
+}
+
+/// Hotbar slot selected by a number key.
+fn hotbar_slot(code: KeyCode) -> Option<usize> {
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    keys.iter().position(|key| *key == code)
+}
+
+/// Rebuilds the chunks of the blocks between the corners, and the chunks next to them whose faces
+/// the blocks may hide.
+fn rebuild_region(
+    renderer: &mut ChunkRenderer,
+    culling: &mut ChunkCulling,
+    graph: &mut Graph,
+    world: &World,
+    min: Vector3<i32>,
+    max: Vector3<i32>,
+) {
+    let first = chunk_position(min.add_scalar(-1));
+    let last = chunk_position(max.add_scalar(1));
+    for y in first.y..=last.y {
+        for z in first.z..=last.z {
+            for x in first.x..=last.x {
+                let position = Vector3::new(x, y, z);
+                if let Some(chunk) = world.chunk(position) {
+                    renderer.rebuild_chunk(graph, chunk);
+                    culling.insert(position, Connectivity::compute(chunk));
+                }
+            }
+        }
+    }
+}
+
+/// Rebuilds the chunk of a changed block, and the chunks next to it whose faces it may hide.
+fn rebuild_around_block(
+    renderer: &mut ChunkRenderer,
+    culling: &mut ChunkCulling,
+    graph: &mut Graph,
+    world: &World,
+    block: Vector3<i32>,
+) {
+    let mut chunks = vec![chunk_position(block)];
+    for direction in Direction::iterator() {
+        let chunk = chunk_position(direction.neighbour(block));
+        if !chunks.contains(&chunk) {
+            chunks.push(chunk);
+        }
+    }
+    for position in chunks {
+        if let Some(chunk) = world.chunk(position) {
+            renderer.rebuild_chunk(graph, chunk);
+            culling.insert(position, Connectivity::compute(chunk));
+        }
+    }
+}
+
+/// Commands to run in every world once it has loaded, `None` when there are none.
+fn read_startup_script() -> Option<String> {
+    match storage::open(SCRIPTS_DIRECTORY).and_then(|scripts| scripts.read(STARTUP_SCRIPT)) {
+        Ok(bytes) => bytes.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        Err(error) => {
+            logging::error(
+                Category::World,
+                format!("Failed to read the startup script: {error}"),
+            );
+            None
+        }
+    }
+}
+
+/// Switches fullscreen, ambient occlusion and shadows of the window and the renderer to the
+/// settings.
+fn apply_graphics_settings(context: &mut PluginContext, settings: &GameSettings) {
+    let GraphicsContext::Initialized(ref mut graphics_context) = context.graphics_context else {
+        return;
+    };
+    if graphics_context.window.fullscreen().is_some() != settings.fullscreen {
+        let fullscreen = settings.fullscreen.then_some(Fullscreen::Borderless(None));
+        graphics_context.window.set_fullscreen(fullscreen);
+    }
+    let mut quality = graphics_context.renderer.get_quality_settings();
+    quality.use_ssao = settings.ambient_occlusion;
+    quality.point_shadows_enabled = settings.shadows;
+    quality.spot_shadows_enabled = settings.shadows;
+    quality.csm_settings.enabled = settings.shadows;
+    if let Err(error) = graphics_context.renderer.set_quality_settings(&quality) {
+        logging::error(
+            Category::World,
+            format!("Failed to change the graphics settings: {error:?}"),
+        );
+    }
+}
+
+/// Sets the vertical field of view of the camera, in degrees.
+fn set_fov(graph: &mut Graph, camera: Handle<Node>, fov: f32) {
+    if let Some(camera) = graph.try_get_mut(camera) {
+        if let Projection::Perspective(perspective) = camera.as_camera_mut().projection_mut() {
+            perspective.fov = fov.to_radians();
+        }
+    }
+}
 
+/// Confines the cursor to the window while playing, frees it for the menus.
+fn grab_cursor(context: &PluginContext, grab: bool) {
+    if let GraphicsContext::Initialized(ref graphics_context) = context.graphics_context {
+        let mode = if grab {
+            CursorGrabMode::Confined
+        } else {
+            CursorGrabMode::None
+        };
+        if let Err(error) = graphics_context.window.set_cursor_grab(mode) {
+            logging::warn(
+                Category::Input,
+                format!("Failed to change the cursor grab: {error}"),
+            );
+        }
+    }
 }
 
 pub struct Game {
     input_controller: InputController,
     scene: Handle<Scene>,
+    scene_path: String,
 
     indicator: Handle<Node>,
     camera: Handle<Node>,
-    test: Option<Node>,
+    /// World that is played, `None` in the main menu.
+    simulation: Option<Simulation>,
+    /// Storage with a directory for every saved world.
+    saves: Box<dyn Storage>,
+    menu: Menu,
+    console: Console,
+    commands: CommandRegistry,
+    /// Startup script of the opened world, run once its chunks are loaded.
+    startup_script: Option<String>,
+    debug_overlay: DebugOverlay,
+    debug_drawing: DebugDrawing,
+    /// Settings of the player, worlds take the view distance when they are opened.
+    settings: GameSettings,
+    renderer: ChunkRenderer,
+    frame_limiter: FrameLimiter,
+    items: ItemRenderer,
+    culling: ChunkCulling,
+    culling_stats: CullingStats,
+    /// Built once the scene is loaded.
+    hud: Option<Hud>,
 }
 
 impl Game {
     pub fn new(scene_path: Option<&str>, context: PluginContext) -> Self {
-  
-        context
-            .async_scene_loader
-            .request(scene_path.unwrap_or("data/scene.rgs"));
-
-        
-        let mut surfaces: Vec<Surface> = vec![];
-
-        for direction in Direction::iterator()
-        {
-            let mut triangles:Vec<TriangleDefinition> = vec![];
-            let mut verticles:Vec<StaticVertex> = vec![];
-            for triangle in direction.triangles()
-            {
-                triangles.push(TriangleDefinition([
-                    verticles.len() as u32 + triangle[0],
-                    verticles.len() as u32 + triangle[1],
-                    verticles.len() as u32 + triangle[2],
-                ]));
-            }
-            let mut surface_data = SurfaceData::new(
-                VertexBuffer::new(4, direction.verticles()).unwrap(),
-                TriangleBuffer::new(triangles),
-                false,
-            );
-            surface_data.calculate_tangents().unwrap();
-            surface_data.transform_geometry(&Matrix4::new_nonuniform_scaling(&Vector3::new(
-                2.0, 2.0, 2.0,
-            )))
-            .unwrap();
-
-            surfaces.push(
-                SurfaceBuilder::new(SurfaceSharedData::new(surface_data))
-                    // .with_material(sector_builder.grass_shader.clone())
-                    .build(),
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(error) = logging::open_file(LOGS_DIRECTORY, MAX_LOG_SIZE, KEPT_LOGS) {
+            logging::error(
+                Category::World,
+                format!("Failed to open the log file: {error}"),
             );
         }
-
-       let test = MeshBuilder::new(
-        BaseBuilder::new().with_local_transform(
-                TransformBuilder::new()
-                    .with_local_position(Vector3::new(
-                        0 as f32 * (64_u32 / 4) as f32,
-                        0 as f32 * (64_u32 / 4) as f32,
-                        0 as f32 * (64_u32 / 4) as f32,
-                    ))
-                    .build(),
-            ),
-        )
-        .with_surfaces(surfaces)
-        .build_node() ;
+        let settings = settings::load_settings();
+        let saves = storage::open(SAVES_DIRECTORY).unwrap_or_else(|error| {
+            logging::error(
+                Category::World,
+                format!("Saved worlds can't be listed: {error}"),
+            );
+            Box::new(MemoryStorage::default())
+        });
+        let mut commands = CommandRegistry::default();
+        register_world_commands(&mut commands);
+        screenshot::register_commands(&mut commands);
+        profiler::register_commands(&mut commands);
+        logging::register_commands(&mut commands);
+        schematic::register_commands(&mut commands);
 
         Self {
             scene: Handle::NONE,
+            scene_path: scene_path.unwrap_or(SCENE_PATH).to_string(),
             input_controller: InputController {
-                move_forward: false,
-                move_backward: false,
-                move_left: false,
-                move_right: false,
-                move_up: false,
-                move_down: false,
-                pitch: 0.0,
-                yaw: 0.0,
-
-                mouse_right_button_pressed: false,               
-                
+                player: PlayerInput::default(),
+
+                mouse_right_button_pressed: false,
+                break_block: false,
+                place_block: false,
             },
             camera: Handle::NONE,
             indicator: Handle::NONE,
-            test: Some(test),
+            simulation: None,
+            saves,
+            menu: Menu::new(context.user_interface, &settings),
+            console: Console::new(context.user_interface, settings.key_bindings.console),
+            commands,
+            startup_script: None,
+            debug_overlay: DebugOverlay::new(context.user_interface),
+            debug_drawing: DebugDrawing::default(),
+            renderer: ChunkRenderer::new(settings.view_distance),
+            settings,
+            frame_limiter: FrameLimiter::default(),
+            items: ItemRenderer::default(),
+            culling: ChunkCulling::default(),
+            culling_stats: CullingStats::default(),
+            hud: None,
         }
     }
-}
 
-impl Plugin for Game {
-    fn on_deinit(&mut self, _context: PluginContext) {
-        // Do a cleanup here.
+    /// Whether a world is played and not paused, only then input goes to the player.
+    fn is_playing(&self) -> bool {
+        self.simulation.is_some() && !self.menu.is_visible() && !self.console.is_visible()
     }
 
-    fn update(&mut self, _context: &mut PluginContext) {
-        // self.frame_counter += 1;
+    /// Forgets held keys and clicks, so nothing carries over between the menus and the world.
+    fn reset_input(&mut self) {
+        self.input_controller.player = PlayerInput::default();
+        self.input_controller.mouse_right_button_pressed = false;
+        self.input_controller.break_block = false;
+        self.input_controller.place_block = false;
+    }
 
-        let scene = &mut _context.scenes[self.scene];
+    /// Opens the saved world with the name, or creates it from the seed when there is none, and
+    /// loads the scene to play it in.
+    fn start_world(&mut self, context: &mut PluginContext, name: &str, seed: u32) {
+        let directory = format!("{SAVES_DIRECTORY}/{name}");
+        let storage = storage::open(&directory).unwrap_or_else(|error| {
+            logging::error(
+                Category::World,
+                format!("World is kept in memory and will not be saved: {error}"),
+            );
+            Box::new(MemoryStorage::default())
+        });
+        logging::info(Category::World, format!("Opening world {name}"));
+        self.simulation = Some(Simulation::new(
+            storage,
+            seed,
+            GenerationSettings::default(),
+            self.settings.view_distance,
+        ));
+        self.renderer = ChunkRenderer::new(self.settings.view_distance);
+        self.startup_script = read_startup_script();
+        self.reset_input();
+        self.menu.hide(context.user_interface);
+        context.async_scene_loader.request(&self.scene_path);
+    }
 
-        match self.test.take()
-        {
-            Some(data) => 
-            {
-                let handle = scene.graph.add_node(data);
+    /// Shows the blocks painted in the scene with the editor, a scene without any has no file.
+    fn show_scene_voxels(
+        &mut self,
+        scene: Handle<Scene>,
+        bytes: Result<Vec<u8>, FileLoadError>,
+        context: &mut PluginContext,
+    ) {
+        let Ok(bytes) = bytes else {
+            return;
+        };
+        if scene != self.scene {
+            return;
+        }
+        let Some(scene) = context.scenes.try_get_mut(scene) else {
+            return;
+        };
+        match Schematic::decode(&bytes) {
+            Ok(schematic) => {
+                build_voxel_mesh(&mut scene.graph, &schematic, SCENE_VOXELS_NAME);
+            }
+            Err(error) => logging::error(
+                Category::World,
+                format!("Failed to read the voxels of {}: {error}", self.scene_path),
+            ),
+        }
+    }
 
-                let collider = ColliderBuilder::new(BaseBuilder::new())
-                            .with_shape(ColliderShape::Trimesh(TrimeshShape {
-                                sources: vec![GeometrySource(handle)],
-                            }))
-                            .build(&mut scene.graph);
+    fn pause(&mut self, context: &mut PluginContext) {
+        self.reset_input();
+        self.console.set_visible(context.user_interface, false);
+        self.menu.show_pause(context.user_interface);
+        if let Some(hud) = &self.hud {
+            hud.set_visible(context.user_interface, false);
+        }
+        grab_cursor(context, false);
+    }
 
-                RigidBodyBuilder::new(BaseBuilder::new().with_children(&[collider]))
-                                .with_body_type(fyrox::scene::rigidbody::RigidBodyType::Static)
-                                .build(&mut scene.graph);
-            },
-            None => {}
+    fn resume(&mut self, context: &mut PluginContext) {
+        self.reset_input();
+        self.menu.hide(context.user_interface);
+        if let Some(hud) = &self.hud {
+            hud.set_visible(context.user_interface, true);
         }
+        grab_cursor(context, true);
+    }
 
-        let graph = &mut scene.graph;
+    /// Saves and closes the world, and goes back to the main menu.
+    fn exit_to_title(&mut self, context: &mut PluginContext) {
+        if let Some(mut simulation) = self.simulation.take() {
+            simulation.save();
+        }
+        if self.scene.is_some() {
+            context.scenes.remove(self.scene);
+            self.scene = Handle::NONE;
+        }
+        self.renderer = ChunkRenderer::new(self.settings.view_distance);
+        self.items = ItemRenderer::default();
+        self.culling = ChunkCulling::default();
+        if let Some(hud) = self.hud.take() {
+            hud.remove(context.user_interface);
+        }
+        self.console.set_visible(context.user_interface, false);
+        self.startup_script = None;
+        if self.debug_overlay.is_visible() {
+            self.debug_overlay.toggle(context.user_interface);
+        }
+        self.menu.show_main(context.user_interface);
+    }
+
+    fn apply_menu_action(&mut self, context: &mut PluginContext, action: MenuAction) {
+        match action {
+            MenuAction::NewWorld { name, seed } => self.start_world(context, &name, seed),
+            // The seed is only used when the world has no metadata.
+            MenuAction::LoadWorld(name) => self.start_world(context, &name, WORLD_SEED),
+            MenuAction::Resume => self.resume(context),
+            MenuAction::ExitToTitle => self.exit_to_title(context),
+            MenuAction::Quit => {
+                if let Some(simulation) = &mut self.simulation {
+                    simulation.save();
+                }
+                if let Some(window_target) = context.window_target {
+                    window_target.exit();
+                }
+            }
+            MenuAction::Settings(settings) => self.apply_settings(context, settings),
+        }
+    }
 
-        let camera_position;
-        let look_direction;
+    /// Applies what can change while the game runs and saves the settings.
+    fn apply_settings(&mut self, context: &mut PluginContext, settings: GameSettings) {
+        if (settings.fullscreen, settings.ambient_occlusion, settings.shadows)
+            != (
+                self.settings.fullscreen,
+                self.settings.ambient_occlusion,
+                self.settings.shadows,
+            )
         {
-            let camera = &mut graph[Handle::<Node>::new(1, 1)];
+            apply_graphics_settings(context, &settings);
+        }
+        if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+            set_fov(&mut scene.graph, self.camera, settings.fov);
+        }
+        self.console.set_toggle_key(settings.key_bindings.console);
+        save_settings(&settings);
+        self.settings = settings;
+    }
 
-            let mut offset: Vector3<f32> = Vector3::default();
-            if self.input_controller.move_forward {
-                offset += camera.look_vector();
-            }
-            if self.input_controller.move_backward {
-                offset -= camera.look_vector();
-            }
-            if self.input_controller.move_left {
-                offset += camera.side_vector();
-            }
-            if self.input_controller.move_right {
-                offset -= camera.side_vector();
+    /// Runs the commands of a script, or of a line typed into the console, on the world that is
+    /// played, and updates the scene for what they changed. Returns the result of every command.
+    fn run_script(
+        &mut self,
+        scenes: &mut SceneContainer,
+        script: &str,
+    ) -> Vec<Result<String, CommandError>> {
+        let Some(simulation) = &mut self.simulation else {
+            return vec![Err(CommandError::Failed("no world is open".to_string()))];
+        };
+        let mut context = CommandContext::new(simulation);
+        let results = self.commands.run_script(&mut context, script);
+        let effects = context.effects;
+        let Some(scene) = scenes.try_get_mut(self.scene) else {
+            return results;
+        };
+        let graph = &mut scene.graph;
+        for effect in effects {
+            match effect {
+                CommandEffect::BlocksChanged { min, max } => rebuild_region(
+                    &mut self.renderer,
+                    &mut self.culling,
+                    graph,
+                    simulation.world(),
+                    min,
+                    max,
+                ),
+                CommandEffect::ChunksUnloaded(positions) => {
+                    for position in positions {
+                        self.renderer.remove_chunk(graph, position);
+                        self.culling.remove(position);
+                    }
+                }
             }
-            if self.input_controller.move_up {
-                offset.y += 1.0
+        }
+        results
+    }
+
+    fn toggle_console(&mut self, context: &mut PluginContext) {
+        let visible = !self.console.is_visible();
+        self.reset_input();
+        self.console.set_visible(context.user_interface, visible);
+        grab_cursor(context, !visible);
+    }
+
+    /// Writes the loaded blocks around the player as OBJ and glTF, for renders outside of the game.
+    fn export_area(&self) {
+        let Some(simulation) = &self.simulation else {
+            return;
+        };
+        let center = simulation.player().position().map(|v| v.floor() as i32);
+        let radius = Vector3::repeat(EXPORT_RADIUS);
+        let mesh = build_region_mesh(simulation.world(), center - radius, center + radius);
+        let name = format!("area_{}_{}_{}", center.x, center.y, center.z);
+        let result = storage::open(EXPORT_DIRECTORY).and_then(|storage| {
+            [ExportFormat::Obj, ExportFormat::Gltf]
+                .into_iter()
+                .flat_map(|format| export_mesh(&mesh, format, &name, None))
+                .try_for_each(|file| storage.write(&file.name, &file.bytes))
+        });
+        match result {
+            Ok(()) => logging::info(
+                Category::Mesh,
+                format!("Exported {name} to {EXPORT_DIRECTORY}"),
+            ),
+            Err(error) => {
+                logging::error(Category::Mesh, format!("Failed to export {name}: {error}"))
             }
-            if self.input_controller.move_down {
-                offset.y -= 1.0
+        }
+    }
+}
+
+impl Plugin for Game {
+    fn on_deinit(&mut self, _context: PluginContext) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.save();
+        }
+    }
+
+    fn update(&mut self, _context: &mut PluginContext) {
+        let _frame = profiler::frame();
+        logging::flush();
+
+        self.menu.update(_context.user_interface);
+        self.console.update(_context.user_interface);
+        // The world stands still while a menu is open.
+        if self.menu.is_visible() {
+            return;
+        }
+        if self.scene.is_some() && self.simulation.as_ref().is_some_and(Simulation::is_loaded) {
+            if let Some(script) = self.startup_script.take() {
+                for result in self.run_script(_context.scenes, &script) {
+                    if let Err(error) = &result {
+                        logging::error(Category::World, format!("Startup script: {error}"));
+                    }
+                    self.console.print_result(_context.user_interface, &result);
+                }
             }
+        }
+        let Some(simulation) = &mut self.simulation else {
+            return;
+        };
+        let Some(scene) = _context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
 
-            if let Some(offset) = offset.try_normalize(f32::EPSILON) {
-                camera.local_transform_mut().offset(offset.scale(0.1));
+        let graph = &mut scene.graph;
+
+        let changes = simulation.step(_context.dt, &self.input_controller.player);
+
+        {
+            let player = simulation.player();
+            let camera = &mut graph[Handle::<Node>::new(1, 1)];
+            camera.local_transform_mut().set_position(player.position());
+            camera.local_transform_mut().set_rotation(player.rotation());
+        }
+
+        for position in changes.unloaded {
+            self.renderer.remove_chunk(graph, position);
+            self.culling.remove(position);
+        }
+        for loaded in changes.loaded {
+            if let Some(chunk) = simulation.world().chunk(loaded.position) {
+                self.renderer.add_chunk(graph, chunk, loaded.lods);
             }
+            self.culling.insert(loaded.position, loaded.connectivity);
+        }
+        for stack in changes.collected {
+            logging::info(
+                Category::World,
+                format!("Collected {} {:?}", stack.count, stack.block),
+            );
+        }
 
-            camera
-                .local_transform_mut()
-                .set_rotation(UnitQuaternion::from_euler_angles(
-                    self.input_controller.pitch,
-                    self.input_controller.yaw,
-                    0.0,
-                ));
-
-            camera_position = camera.global_position().clone();
-            look_direction = camera.look_vector().clone();
-        }
-        
-
-        let mut intersections = Vec::new();
-        
-        graph.physics.cast_ray(
-                RayCastOptions {
-                    ray_origin: camera_position.into(),
-                    ray_direction: look_direction,
-                    max_len: 10.0,
-                    groups: Default::default(),
-                    // Sort results of the ray casting so the closest intersection will be in the
-                    // beginning of the list.
-                    sort_results: true,
-                },
-                &mut intersections,
+        let hit = simulation.target(REACH);
+        let mut changed = Vec::new();
+        if std::mem::take(&mut self.input_controller.break_block) {
+            if let Some(hit) = &hit {
+                if simulation.break_block(hit.block).is_some() {
+                    changed.push(hit.block);
+                }
+            }
+        }
+        if std::mem::take(&mut self.input_controller.place_block) {
+            changed.extend(hit.as_ref().and_then(|hit| simulation.place_block(hit)));
+        }
+        for block in changed {
+            rebuild_around_block(
+                &mut self.renderer,
+                &mut self.culling,
+                graph,
+                simulation.world(),
+                block,
             );
+        }
+        self.items.update(graph, simulation.items());
+        if let Some(hud) = &mut self.hud {
+            hud.update(_context.user_interface, simulation.player());
+        }
+
+        let center = simulation.center();
+        self.renderer.update_detail(graph, simulation.world(), center);
+        self.culling_stats = self.renderer.cull(graph, &self.culling, self.camera, center);
+
+        if self.debug_overlay.is_due(_context.dt) {
+            let frame = match &_context.graphics_context {
+                GraphicsContext::Initialized(graphics_context) => {
+                    let statistics = graphics_context.renderer.get_statistics();
+                    Some(FrameInfo {
+                        fps: statistics.frames_per_second,
+                        frame_time: statistics.pure_frame_time,
+                        draw_calls: statistics.pipeline.draw_calls,
+                        triangles: statistics.pipeline.triangles_rendered,
+                    })
+                }
+                GraphicsContext::Uninitialized(_) => None,
+            };
+            let player = simulation.player();
+            let look = player.look_vector();
+            let column = player.position().map(|v| v.floor() as i32);
+            let info = DebugInfo {
+                frame,
+                position: player.position(),
+                facing: Direction::from_normal(Vector3::new(look.x, 0.0, look.z)),
+                target: hit.as_ref().map(|hit| DebugTarget {
+                    position: hit.block,
+                    block: simulation.world().block(hit.block),
+                    face: hit.face,
+                }),
+                biome: simulation.generator().biome_at(column.x, column.z),
+                loaded_chunks: simulation.world().chunks().count(),
+                loading_chunks: simulation.loading_chunks(),
+                queued_chunks: simulation.queued_chunks(),
+                culling: self.culling_stats,
+                meshes: self.renderer.mesh_stats(),
+                items: simulation.items().len(),
+                frames: profiler::profiler().frames().copied().collect(),
+            };
+            self.debug_overlay.show(_context.user_interface, &info);
+        }
+
         if self.input_controller.mouse_right_button_pressed
         {
-            Log::info("======================================");
-            for intersection in intersections
+            if let Some(hit) = &hit
             {
-                let (x,y,z) = (0.0, 0.12162548, 0.05452454);
-                let position = Vector3::new(
-                    intersection.position.x + x, 
-                    intersection.position.y + y,
-                    intersection.position.z + z,);
-                graph[self.indicator].as_mesh_mut().local_transform_mut().set_position(position );
-                Log::info( format!( "{} , {}",intersection.position.coords , position ));
-                break;
-
+                graph[self.indicator].local_transform_mut().set_position(hit.position);
             }
         }
-            
-        
+        self.debug_drawing.draw(scene, simulation, hit.as_ref(), REACH);
     }
 
-    fn on_os_event(&mut self, _event: &Event<()>, _context: PluginContext) {
+    fn on_graphics_context_initialized(&mut self, mut context: PluginContext) {
+        apply_graphics_settings(&mut context, &self.settings);
+    }
+
+    fn on_os_event(&mut self, _event: &Event<()>, mut _context: PluginContext) {
+        // Every turn of the event loop of the executors updates and draws once, waiting before
+        // it caps both.
+        if let Event::AboutToWait = _event {
+            self.frame_limiter.wait(self.settings.max_fps);
+        }
+        if let Event::WindowEvent {
+            event: KeyboardInput { event: input, .. },
+            ..
+        } = _event
+        {
+            let pressed = input.state == ElementState::Pressed;
+            // A binding of the settings screen takes the next key, Escape included.
+            if self.menu.is_rebinding() {
+                if let (true, PhysicalKey::Code(code)) = (pressed, input.physical_key) {
+                    if let Some(action) = self.menu.bind_key(_context.user_interface, code) {
+                        self.apply_menu_action(&mut _context, action);
+                    }
+                }
+                return;
+            }
+            // A console key that types a character is typed into the open console.
+            let console_key = self.settings.key_bindings.console;
+            if pressed
+                && input.physical_key == PhysicalKey::Code(console_key)
+                && self.simulation.is_some()
+                && !self.menu.is_visible()
+                && (!self.console.is_visible() || key_character(console_key).is_none())
+            {
+                self.toggle_console(&mut _context);
+                return;
+            }
+            if self.console.is_visible() {
+                if let (true, PhysicalKey::Code(code)) = (pressed, input.physical_key) {
+                    match code {
+                        KeyCode::Escape => self.toggle_console(&mut _context),
+                        KeyCode::Enter | KeyCode::NumpadEnter => {
+                            if let Some(line) = self.console.submit(_context.user_interface) {
+                                for result in self.run_script(_context.scenes, &line) {
+                                    self.console.print_result(_context.user_interface, &result);
+                                }
+                            }
+                        }
+                        KeyCode::ArrowUp => {
+                            self.console.browse_history(_context.user_interface, true)
+                        }
+                        KeyCode::ArrowDown => {
+                            self.console.browse_history(_context.user_interface, false)
+                        }
+                        KeyCode::Tab => self
+                            .console
+                            .complete(_context.user_interface, &self.commands),
+                        _ => (),
+                    }
+                }
+                return;
+            }
+            if input.physical_key == PhysicalKey::Code(KeyCode::Escape) && pressed {
+                if self.menu.is_visible() {
+                    if let Some(action) = self.menu.back(_context.user_interface) {
+                        self.apply_menu_action(&mut _context, action);
+                    }
+                } else if self.simulation.is_some() {
+                    self.pause(&mut _context);
+                }
+                return;
+            }
+        }
+        if !self.is_playing() {
+            return;
+        }
+        let Some(simulation) = &mut self.simulation else {
+            return;
+        };
+
         match _event {
             Event::WindowEvent {
                 event: KeyboardInput { event: input, .. },
                 ..
             } => {
                 if let PhysicalKey::Code(code) = input.physical_key {
-                    match code {
-                        KeyCode::KeyW => {
-                            self.input_controller.move_forward =
-                                input.state == ElementState::Pressed
+                    let pressed = input.state == ElementState::Pressed;
+                    let player = &mut self.input_controller.player;
+                    match self.settings.key_bindings.action(code) {
+                        Some(Action::Forward) => player.forward = pressed,
+                        Some(Action::Backward) => player.backward = pressed,
+                        Some(Action::Left) => player.left = pressed,
+                        Some(Action::Right) => player.right = pressed,
+                        Some(Action::Up) => player.up = pressed,
+                        Some(Action::Down) => player.down = pressed,
+                        Some(Action::DebugOverlay) if pressed => {
+                            self.debug_overlay.toggle(_context.user_interface);
                         }
-                        KeyCode::KeyS => {
-                            self.input_controller.move_backward =
-                                input.state == ElementState::Pressed
+                        Some(Action::Export) if pressed => {
+                            self.export_area();
                         }
-                        KeyCode::KeyA => {
-                            self.input_controller.move_left = input.state == ElementState::Pressed
-                        }
-                        KeyCode::KeyD => {
-                            self.input_controller.move_right = input.state == ElementState::Pressed
-                        }
-                        KeyCode::KeyP => {
-                            let mut scene = & mut _context.scenes[self.scene];
-                            scene.graph.physics.draw(&mut scene.drawing_context);
-                        }
-                        KeyCode::ShiftLeft => {
-                            self.input_controller.move_up = input.state == ElementState::Pressed
-                        }
-                        KeyCode::ControlLeft => {
-                            self.input_controller.move_down = input.state == ElementState::Pressed
+                        None if pressed => {
+                            if let Some(slot) = hotbar_slot(code) {
+                                simulation.player_mut().inventory_mut().select(slot);
+                            }
+                            if let Some(layer) = debug_layer(code) {
+                                let enabled = self.debug_drawing.toggle(layer);
+                                logging::info(
+                                    Category::Input,
+                                    format!("Debug drawing of {layer:?}: {enabled}"),
+                                );
+                            }
                         }
 
                         _ => (),
                     }
                 }
             }
+            Event::WindowEvent {
+                event: MouseWheel { delta, .. },
+                ..
+            } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+                // Scrolling up goes to the previous slot.
+                if lines != 0.0 {
+                    let steps = if lines > 0.0 { -1 } else { 1 };
+                    simulation.player_mut().inventory_mut().scroll(steps);
+                }
+            }
+            Event::WindowEvent {
+                event:
+                    MouseInput {
+                        button: MouseButton::Right,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                self.input_controller.place_block = true;
+            }
             Event::DeviceEvent {
                 event: MouseMotion { delta, .. },
                 ..
             } => {
-                let mouse_sens: f32 = 0.2 * _context.dt;
-                self.input_controller.yaw -= (delta.0 as f32) * mouse_sens;
-                self.input_controller.pitch = (self.input_controller.pitch
-                    + (delta.1 as f32) * mouse_sens)
-                    .clamp(-90.0f32.to_radians(), 90.0f32.to_radians())
+                let mouse_sens: f32 = 0.2 * _context.dt * self.settings.mouse_sensitivity;
+                let pitch_sens = if self.settings.invert_mouse {
+                    -mouse_sens
+                } else {
+                    mouse_sens
+                };
+                simulation
+                    .player_mut()
+                    .turn(-(delta.0 as f32) * mouse_sens, (delta.1 as f32) * pitch_sens)
             }
-            Event::DeviceEvent { event: Button { button, state }, .. } => 
+            Event::DeviceEvent { event: Button { button, state }, .. } =>
             {
                 if *button == 0_u32 // Left mouse button
                 {
-                    // Log::info( format!("button = {}", button) );
+                    logging::debug(
+                        Category::Input,
+                        format!("Mouse button {button}: {state:?}"),
+                    );
                     self.input_controller.mouse_right_button_pressed = ElementState::Pressed == *state;
+                    if ElementState::Pressed == *state {
+                        self.input_controller.break_block = true;
+                    }
                 }
             }
             _ => (),
@@ -332,7 +906,13 @@ impl Plugin for Game {
     }
 
     fn on_ui_message(&mut self, _context: &mut PluginContext, _message: &UiMessage) {
-        // Handle UI events here.
+        self.console.handle_message(_context.user_interface, _message);
+        if let Some(action) =
+            self.menu
+                .handle_message(_context.user_interface, _message, self.saves.as_ref())
+        {
+            self.apply_menu_action(_context, action);
+        }
     }
 
     fn on_scene_begin_loading(&mut self, _path: &Path, ctx: &mut PluginContext) {
@@ -349,15 +929,13 @@ impl Plugin for Game {
         _context: &mut PluginContext,
     ) {
 
-        if let GraphicsContext::Initialized(ref graphics_context) = _context.graphics_context {
-            graphics_context.window.set_cursor_grab(
-                CursorGrabMode::Confined,
-            );
-        }
-
+        grab_cursor(_context, true);
 
         self.scene = scene;
         self.camera = Handle::<Node>::new(1, 1);
+        if self.hud.is_none() {
+            self.hud = Some(Hud::new(_context.user_interface));
+        }
 
         self.indicator = MeshBuilder::new(
             BaseBuilder::new().with_local_transform(
@@ -370,10 +948,24 @@ impl Plugin for Game {
                 0.25, 0.25, 0.25,
             ))),
         ))
-            // .with_material(SharedMaterial::new(material))
             .build()])
-        .build(&mut _context.scenes[scene].graph);  
+        .build(&mut _context.scenes[scene].graph);
 
-        // self.world.lock().unwrap().start_generating_thread();
+        let camera = &mut _context.scenes[scene].graph[self.camera];
+        if let Some(simulation) = &self.simulation {
+            camera
+                .local_transform_mut()
+                .set_position(simulation.player().position());
+        }
+        camera
+            .as_camera_mut()
+            .projection_mut()
+            .set_z_far(self.renderer.z_far());
+        set_fov(&mut _context.scenes[scene].graph, self.camera, self.settings.fov);
+
+        _context.task_pool.spawn_plugin_task(
+            load_file(voxels_path(Path::new(&self.scene_path))),
+            move |bytes, game: &mut Game, context| game.show_scene_voxels(scene, bytes, context),
+        );
     }
 }
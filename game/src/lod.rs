@@ -0,0 +1,122 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::block::{Block, ModelBox};
+use crate::chunk::{BlockSource, Chunk, CHUNK_SIZE};
+use crate::direction::Direction;
+use crate::mesher::{box_face, build_mesh, ChunkMesh};
+
+/// Downsampling factors of the levels that follow the full detail mesh, from near to far.
+pub const LOD_FACTORS: [i32; 3] = [2, 4, 8];
+
+/// Side face of a surface cell hanging one cell below it, hides cracks against neighbours of a
+/// different level.
+const SKIRT: ModelBox = ModelBox::new([0.0, -1.0, 0.0], [1.0, 1.0, 1.0]);
+
+/// Blocks of a chunk merged into cells of `factor` blocks on every side.
+struct CoarseChunk {
+    size: i32,
+    cells: Vec<Block>,
+}
+
+impl CoarseChunk {
+    fn new(chunk: &Chunk, factor: i32) -> Self {
+        let size = CHUNK_SIZE / factor;
+        let mut cells = Vec::with_capacity((size * size * size) as usize);
+        for y in 0..size {
+            for z in 0..size {
+                for x in 0..size {
+                    cells.push(merge(chunk, Vector3::new(x, y, z) * factor, factor));
+                }
+            }
+        }
+        Self { size, cells }
+    }
+
+    fn contains(&self, cell: Vector3<i32>) -> bool {
+        (0..self.size).contains(&cell.x)
+            && (0..self.size).contains(&cell.y)
+            && (0..self.size).contains(&cell.z)
+    }
+}
+
+impl BlockSource for CoarseChunk {
+    /// Cells outside of the chunk are treated as air above it and as solid everywhere else, the
+    /// sides are closed by skirts instead.
+    fn block(&self, cell: Vector3<i32>) -> Block {
+        if self.contains(cell) {
+            self.cells[(cell.x + cell.z * self.size + cell.y * self.size * self.size) as usize]
+        } else if cell.y >= self.size {
+            Block::Air
+        } else {
+            Block::Stone
+        }
+    }
+}
+
+/// Most common full or liquid block of the cell, or air when less than half of the cell is filled.
+fn merge(chunk: &Chunk, origin: Vector3<i32>, factor: i32) -> Block {
+    let mut counts: Vec<(Block, i32)> = Vec::new();
+    let mut filled = 0;
+    for y in 0..factor {
+        for z in 0..factor {
+            for x in 0..factor {
+                let block = chunk.get(origin + Vector3::new(x, y, z));
+                if !block.is_full() && !block.is_liquid() {
+                    continue;
+                }
+                filled += 1;
+                match counts.iter_mut().find(|(kind, _)| *kind == block) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((block, 1)),
+                }
+            }
+        }
+    }
+    if filled * 2 < factor * factor * factor {
+        return Block::Air;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or(Block::Air, |(block, _)| block)
+}
+
+fn push_skirts(mesh: &mut ChunkMesh, coarse: &CoarseChunk) {
+    for y in 0..coarse.size {
+        for z in 0..coarse.size {
+            for x in 0..coarse.size {
+                let cell = Vector3::new(x, y, z);
+                if coarse.block(cell).is_air() || !coarse.block(cell + Vector3::y()).is_air() {
+                    continue;
+                }
+                for direction in Direction::iterator() {
+                    if direction.axis() == 1 || coarse.contains(direction.neighbour(cell)) {
+                        continue;
+                    }
+                    mesh.push_quad(
+                        box_face(direction, &SKIRT, cell.cast::<f32>()),
+                        direction.triangles(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Builds the mesh of a chunk with `factor` blocks merged into one cell, with skirts along the
+/// chunk sides. Positions are in blocks, like the full detail mesh.
+pub fn build_lod_mesh(chunk: &Chunk, factor: i32) -> ChunkMesh {
+    let coarse = CoarseChunk::new(chunk, factor);
+    let mut mesh = build_mesh(&coarse, Vector3::default(), coarse.size);
+    push_skirts(&mut mesh, &coarse);
+    mesh.scale(factor as f32);
+    mesh
+}
+
+/// Meshes for every entry of `LOD_FACTORS`.
+pub fn build_lod_meshes(chunk: &Chunk) -> Vec<ChunkMesh> {
+    LOD_FACTORS
+        .iter()
+        .map(|factor| build_lod_mesh(chunk, *factor))
+        .collect()
+}
@@ -0,0 +1,398 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::{self, File};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
+use fyrox::core::instant::Instant;
+use fyrox::core::log::Log;
+
+use crate::command::{Argument, Arguments, Command, CommandContext, CommandError, CommandRegistry};
+
+/// Messages a category may log per second, the rest are counted and reported when the second is
+/// over.
+pub const MAX_MESSAGES_PER_SECOND: u32 = 30;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Name of the log file, older ones get a number before the extension.
+#[cfg(not(target_arch = "wasm32"))]
+const FILE_NAME: &str = "game";
+
+/// Part of the game a message is about, each has its own level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Saves, chunk loading and generation, and the rules of the world.
+    World,
+    Mesh,
+    Input,
+    Physics,
+    /// For multiplayer, nothing logs to it yet.
+    Net,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::World,
+        Category::Mesh,
+        Category::Input,
+        Category::Physics,
+        Category::Net,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::World => "world",
+            Category::Mesh => "mesh",
+            Category::Input => "input",
+            Category::Physics => "physics",
+            Category::Net => "net",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl FromStr for Category {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Category::ALL
+            .into_iter()
+            .find(|category| category.name() == name)
+            .ok_or(())
+    }
+}
+
+/// How important a message is. A category logs the messages of its level and the levels before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl Level {
+    pub const ALL: [Level; 5] = [
+        Level::Off,
+        Level::Error,
+        Level::Warning,
+        Level::Info,
+        Level::Debug,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Level::ALL
+            .into_iter()
+            .find(|level| level.name() == name)
+            .ok_or(())
+    }
+}
+
+/// Messages of a category in the current second.
+#[derive(Debug, Clone, Copy, Default)]
+struct Rate {
+    start: Duration,
+    logged: u32,
+    suppressed: u32,
+}
+
+/// Decides which messages are logged and formats them. Times are since the game started.
+pub struct Logger {
+    levels: [Level; Category::ALL.len()],
+    rates: [Rate; Category::ALL.len()],
+    #[cfg(not(target_arch = "wasm32"))]
+    file: Option<LogFile>,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            levels: [Level::Info; Category::ALL.len()],
+            rates: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            file: None,
+        }
+    }
+}
+
+impl Logger {
+    pub fn level(&self, category: Category) -> Level {
+        self.levels[category.index()]
+    }
+
+    pub fn set_level(&mut self, category: Category, level: Level) {
+        self.levels[category.index()] = level;
+    }
+
+    /// Starts a new second for the category when the last one is over, with a line that tells
+    /// how many messages were suppressed in it, if any were.
+    fn end_window(&mut self, category: Category, now: Duration) -> Option<(Level, String)> {
+        let rate = &mut self.rates[category.index()];
+        if now.saturating_sub(rate.start) < RATE_WINDOW {
+            return None;
+        }
+        let suppressed = rate.suppressed;
+        *rate = Rate {
+            start: now,
+            ..Rate::default()
+        };
+        (suppressed > 0).then(|| {
+            (
+                Level::Warning,
+                format!(
+                    "[{}] {suppressed} messages were suppressed",
+                    category.name()
+                ),
+            )
+        })
+    }
+
+    /// Lines to log for a message at `now`, none when the level of the category is lower or the
+    /// category logged too much this second. A line that tells how many messages were suppressed
+    /// comes before the message of the next second, unless `flush` reported them already.
+    pub fn filter(
+        &mut self,
+        category: Category,
+        level: Level,
+        message: &str,
+        now: Duration,
+    ) -> Vec<(Level, String)> {
+        if level == Level::Off || level > self.level(category) {
+            return Vec::new();
+        }
+        let mut lines: Vec<_> = self.end_window(category, now).into_iter().collect();
+        let rate = &mut self.rates[category.index()];
+        if rate.logged >= MAX_MESSAGES_PER_SECOND {
+            rate.suppressed += 1;
+            return lines;
+        }
+        rate.logged += 1;
+        lines.push((level, format!("[{}] {message}", category.name())));
+        lines
+    }
+
+    /// Lines that tell how many messages were suppressed in seconds that are over at `now`, so
+    /// they are reported even when the category logs nothing afterwards.
+    pub fn flush(&mut self, now: Duration) -> Vec<(Level, String)> {
+        let mut lines = Vec::new();
+        for category in Category::ALL {
+            if self.rates[category.index()].suppressed > 0 {
+                lines.extend(self.end_window(category, now));
+            }
+        }
+        lines
+    }
+
+    /// Writes the lines to the log of the engine and to the log file.
+    fn write(&mut self, lines: Vec<(Level, String)>, now: Duration) {
+        for (level, line) in lines {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(file) = &mut self.file {
+                let line = format!("{:10.3} {:<7} {line}", now.as_secs_f32(), level.name());
+                if let Err(error) = file.write_line(&line) {
+                    Log::err(format!(
+                        "Failed to write the log file, it is closed: {error}"
+                    ));
+                    self.file = None;
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            let _ = now;
+            match level {
+                Level::Error => Log::err(line),
+                Level::Warning => Log::warn(line),
+                _ => Log::info(line),
+            }
+        }
+    }
+}
+
+/// Log file that is moved aside once it grows too large, keeping a few of the older ones.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct LogFile {
+    directory: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    /// Older files kept next to the current one.
+    kept: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LogFile {
+    fn path(directory: &Path, number: usize) -> PathBuf {
+        if number == 0 {
+            directory.join(format!("{FILE_NAME}.log"))
+        } else {
+            directory.join(format!("{FILE_NAME}.{number}.log"))
+        }
+    }
+
+    /// Starts a new file in the directory, the one of the last run becomes the first older one.
+    pub fn open(directory: impl Into<PathBuf>, max_size: u64, kept: usize) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Self::rotate(&directory, kept)?;
+        Ok(Self {
+            file: Self::create(&directory)?,
+            directory,
+            size: 0,
+            max_size,
+            kept,
+        })
+    }
+
+    fn create(directory: &Path) -> io::Result<File> {
+        File::create(Self::path(directory, 0))
+    }
+
+    /// Shifts the numbers of the files up by one, dropping the oldest.
+    fn rotate(directory: &Path, kept: usize) -> io::Result<()> {
+        for number in (0..=kept).rev() {
+            let path = Self::path(directory, number);
+            if !path.exists() {
+                continue;
+            }
+            if number == kept {
+                fs::remove_file(path)?;
+            } else {
+                fs::rename(path, Self::path(directory, number + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+        if self.size > 0 && self.size + length > self.max_size {
+            self.file.flush()?;
+            Self::rotate(&self.directory, self.kept)?;
+            self.file = Self::create(&self.directory)?;
+            self.size = 0;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += length;
+        Ok(())
+    }
+}
+
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Logger of the game, shared by the main thread and the chunk workers.
+pub fn logger() -> MutexGuard<'static, Logger> {
+    static LOGGER: OnceLock<Mutex<Logger>> = OnceLock::new();
+    LOGGER
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Logs from now on also into a file in the directory, see `LogFile`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_file(directory: &str, max_size: u64, kept: usize) -> io::Result<()> {
+    logger().file = Some(LogFile::open(directory, max_size, kept)?);
+    Ok(())
+}
+
+pub fn log(category: Category, level: Level, message: impl AsRef<str>) {
+    let epoch = epoch();
+    let now = Instant::now().duration_since(epoch);
+    let mut logger = logger();
+    let lines = logger.filter(category, level, message.as_ref(), now);
+    logger.write(lines, now);
+}
+
+/// Reports messages suppressed in seconds that are over, meant to run every frame.
+pub fn flush() {
+    let epoch = epoch();
+    let now = Instant::now().duration_since(epoch);
+    let mut logger = logger();
+    let lines = logger.flush(now);
+    logger.write(lines, now);
+}
+
+pub fn error(category: Category, message: impl AsRef<str>) {
+    log(category, Level::Error, message);
+}
+
+pub fn warn(category: Category, message: impl AsRef<str>) {
+    log(category, Level::Warning, message);
+}
+
+pub fn info(category: Category, message: impl AsRef<str>) {
+    log(category, Level::Info, message);
+}
+
+pub fn debug(category: Category, message: impl AsRef<str>) {
+    log(category, Level::Debug, message);
+}
+
+/// Shows the levels of the log categories or sets the level of one, or of all.
+fn log_level(_context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(0..=2)?;
+    let mut logger = logger();
+    let categories = match arguments.get(0).unwrap_or("all") {
+        "all" => Category::ALL.to_vec(),
+        name => vec![name
+            .parse()
+            .map_err(|_| CommandError::Failed(format!("unknown log category {name}")))?],
+    };
+    if arguments.len() < 2 {
+        let levels: Vec<String> = categories
+            .iter()
+            .map(|category| format!("{}: {}", category.name(), logger.level(*category).name()))
+            .collect();
+        return Ok(levels.join(", "));
+    }
+    let level: Level = arguments
+        .get(1)?
+        .parse()
+        .map_err(|_| arguments.usage_error())?;
+    for category in categories {
+        logger.set_level(category, level);
+    }
+    Ok(format!(
+        "Log level of {} is {}",
+        arguments.get(0)?,
+        level.name()
+    ))
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register(Command {
+        name: "log",
+        usage: "log [category|all] [off|error|warning|info|debug]",
+        help: "shows or sets which messages a log category writes",
+        arguments: &[
+            Argument::Choice(&["all", "input", "mesh", "net", "physics", "world"]),
+            Argument::Choice(&["debug", "error", "info", "off", "warning"]),
+        ],
+        run: log_level,
+    });
+}
@@ -0,0 +1,543 @@
+use fyrox::core::algebra::Vector2;
+use fyrox::core::color::Color;
+use fyrox::core::pool::Handle;
+use fyrox::gui::border::BorderBuilder;
+use fyrox::gui::brush::Brush;
+use fyrox::gui::button::{ButtonBuilder, ButtonMessage};
+use fyrox::gui::grid::{Column, GridBuilder, Row};
+use fyrox::gui::message::{MessageDirection, UiMessage};
+use fyrox::gui::stack_panel::StackPanelBuilder;
+use fyrox::gui::text::{TextBuilder, TextMessage};
+use fyrox::gui::text_box::TextBoxBuilder;
+use fyrox::gui::widget::{WidgetBuilder, WidgetMessage};
+use fyrox::gui::{
+    BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+    VerticalAlignment,
+};
+use fyrox::keyboard::KeyCode;
+
+use crate::settings::{Action, GameSettings, Setting, BINDABLE_KEYS};
+use crate::storage::Storage;
+use crate::worlds::{list_worlds, new_world_name, parse_seed};
+
+const BUTTON_WIDTH: f32 = 240.0;
+const BUTTON_HEIGHT: f32 = 32.0;
+const BACKGROUND_COLOR: Color = Color::from_rgba(0, 0, 0, 160);
+
+/// What the player chose in the menu.
+pub enum MenuAction {
+    NewWorld {
+        name: String,
+        seed: u32,
+    },
+    LoadWorld(String),
+    Resume,
+    ExitToTitle,
+    Quit,
+    /// Settings after the player changed one of them.
+    Settings(GameSettings),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Main,
+    NewWorld,
+    LoadWorld,
+    Settings,
+    Pause,
+}
+
+/// Line of the settings screen. Numbers have buttons to step them down and up around their text,
+/// the other settings are a button that shows them.
+struct SettingRow {
+    setting: Setting,
+    text: Handle<UiNode>,
+    button: Handle<UiNode>,
+    less: Handle<UiNode>,
+    more: Handle<UiNode>,
+}
+
+/// Main menu with new world, world selection, settings and quit, and the pause menu of a world
+/// that is being played. Only one screen is shown at a time.
+pub struct Menu {
+    root: Handle<UiNode>,
+    screen_size: Vector2<f32>,
+    screen: Option<Screen>,
+    /// Screen the settings go back to.
+    settings_parent: Screen,
+
+    main: Handle<UiNode>,
+    new_world: Handle<UiNode>,
+    load_world: Handle<UiNode>,
+    settings: Handle<UiNode>,
+    quit: Handle<UiNode>,
+
+    new_world_screen: Handle<UiNode>,
+    seed_box: Handle<UiNode>,
+    seed: String,
+    create: Handle<UiNode>,
+    new_world_back: Handle<UiNode>,
+
+    load_world_screen: Handle<UiNode>,
+    world_list: Handle<UiNode>,
+    /// Entries of the world list with the names of their worlds, a button for every world or
+    /// a text when there are none.
+    worlds: Vec<(Handle<UiNode>, String)>,
+    load_world_back: Handle<UiNode>,
+
+    settings_screen: Handle<UiNode>,
+    game_settings: GameSettings,
+    setting_rows: Vec<SettingRow>,
+    /// Binding that waits for a key to be pressed.
+    rebinding: Option<Action>,
+    settings_back: Handle<UiNode>,
+
+    pause: Handle<UiNode>,
+    resume: Handle<UiNode>,
+    pause_settings: Handle<UiNode>,
+    exit_to_title: Handle<UiNode>,
+}
+
+fn button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(BUTTON_WIDTH)
+            .with_height(BUTTON_HEIGHT)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn text(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(Thickness::uniform(8.0))
+            .with_foreground(Brush::Solid(Color::WHITE)),
+    )
+    .with_text(text)
+    .with_horizontal_text_alignment(HorizontalAlignment::Center)
+    .build(ctx)
+}
+
+fn small_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(BUTTON_HEIGHT)
+            .with_height(BUTTON_HEIGHT)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+/// Builds the widgets of a setting, the texts are set by `Menu::show_setting`.
+fn setting_row(ctx: &mut BuildContext, setting: Setting) -> (Handle<UiNode>, SettingRow) {
+    let text = TextBuilder::new(
+        WidgetBuilder::new()
+            .with_width(BUTTON_WIDTH)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_foreground(Brush::Solid(Color::WHITE)),
+    )
+    .with_horizontal_text_alignment(HorizontalAlignment::Center)
+    .with_vertical_text_alignment(VerticalAlignment::Center)
+    .build(ctx);
+    if setting.is_numeric() {
+        let less = small_button(ctx, "<");
+        let more = small_button(ctx, ">");
+        let widget = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_children([less, text, more]),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let row = SettingRow {
+            setting,
+            text,
+            button: Handle::NONE,
+            less,
+            more,
+        };
+        (widget, row)
+    } else {
+        let button = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(BUTTON_WIDTH + 2.0 * (BUTTON_HEIGHT + 8.0))
+                .with_height(BUTTON_HEIGHT)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_content(text)
+        .build(ctx);
+        let row = SettingRow {
+            setting,
+            text,
+            button,
+            less: Handle::NONE,
+            more: Handle::NONE,
+        };
+        (button, row)
+    }
+}
+
+/// Centered column with a title over the widgets, hidden until its screen is shown.
+fn screen(ctx: &mut BuildContext, title: &str, widgets: &[Handle<UiNode>]) -> Handle<UiNode> {
+    let title = text(ctx, title);
+    StackPanelBuilder::new(
+        WidgetBuilder::new()
+            .with_visibility(false)
+            .with_horizontal_alignment(HorizontalAlignment::Center)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_child(title)
+            .with_children(widgets.iter().copied()),
+    )
+    .build(ctx)
+}
+
+impl Menu {
+    /// Builds every screen and shows the main menu.
+    pub fn new(ui: &mut UserInterface, game_settings: &GameSettings) -> Self {
+        let screen_size = ui.screen_size();
+        let ctx = &mut ui.build_ctx();
+
+        let new_world = button(ctx, "New world");
+        let load_world = button(ctx, "Load world");
+        let settings = button(ctx, "Settings");
+        let quit = button(ctx, "Quit");
+        let main = screen(ctx, "Mine64", &[new_world, load_world, settings, quit]);
+
+        let seed_label = text(ctx, "Seed, leave empty for a random one");
+        let seed_box = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(BUTTON_WIDTH)
+                .with_height(BUTTON_HEIGHT)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .build(ctx);
+        let create = button(ctx, "Create");
+        let new_world_back = button(ctx, "Back");
+        let new_world_screen = screen(
+            ctx,
+            "New world",
+            &[seed_label, seed_box, create, new_world_back],
+        );
+
+        let world_list = StackPanelBuilder::new(WidgetBuilder::new()).build(ctx);
+        let load_world_back = button(ctx, "Back");
+        let load_world_screen = screen(ctx, "Load world", &[world_list, load_world_back]);
+
+        // Options and key bindings side by side, one column would not fit on small screens.
+        let mut setting_rows = Vec::new();
+        let mut columns = Vec::new();
+        let bindings = Action::ALL.map(Setting::Binding);
+        for settings in [&Setting::OPTIONS[..], &bindings[..]] {
+            let mut widgets = Vec::new();
+            for setting in settings {
+                let (widget, row) = setting_row(ctx, *setting);
+                widgets.push(widget);
+                setting_rows.push(row);
+            }
+            columns.push(
+                StackPanelBuilder::new(
+                    WidgetBuilder::new()
+                        .with_vertical_alignment(VerticalAlignment::Top)
+                        .with_children(widgets),
+                )
+                .build(ctx),
+            );
+        }
+        let setting_columns = StackPanelBuilder::new(WidgetBuilder::new().with_children(columns))
+            .with_orientation(Orientation::Horizontal)
+            .build(ctx);
+        let settings_back = button(ctx, "Back");
+        let settings_screen = screen(ctx, "Settings", &[setting_columns, settings_back]);
+
+        let resume = button(ctx, "Resume");
+        let pause_settings = button(ctx, "Settings");
+        let exit_to_title = button(ctx, "Save and exit to title");
+        let pause = screen(ctx, "Paused", &[resume, pause_settings, exit_to_title]);
+
+        let background = BorderBuilder::new(
+            WidgetBuilder::new().with_background(Brush::Solid(BACKGROUND_COLOR)),
+        )
+        .with_stroke_thickness(Thickness::zero())
+        .build(ctx);
+        let root = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_width(screen_size.x)
+                .with_height(screen_size.y)
+                .with_children([
+                    background,
+                    main,
+                    new_world_screen,
+                    load_world_screen,
+                    settings_screen,
+                    pause,
+                ]),
+        )
+        .add_row(Row::stretch())
+        .add_column(Column::stretch())
+        .build(ctx);
+
+        let mut menu = Self {
+            root,
+            screen_size,
+            screen: None,
+            settings_parent: Screen::Main,
+            main,
+            new_world,
+            load_world,
+            settings,
+            quit,
+            new_world_screen,
+            seed_box,
+            seed: String::new(),
+            create,
+            new_world_back,
+            load_world_screen,
+            world_list,
+            worlds: Vec::new(),
+            load_world_back,
+            settings_screen,
+            game_settings: game_settings.clone(),
+            setting_rows,
+            rebinding: None,
+            settings_back,
+            pause,
+            resume,
+            pause_settings,
+            exit_to_title,
+        };
+        for row in 0..menu.setting_rows.len() {
+            menu.show_setting(ui, row);
+        }
+        menu.show(ui, Some(Screen::Main));
+        menu
+    }
+
+    /// Whether a screen is shown, the world is paused then.
+    pub fn is_visible(&self) -> bool {
+        self.screen.is_some()
+    }
+
+    pub fn show_main(&mut self, ui: &UserInterface) {
+        self.show(ui, Some(Screen::Main));
+    }
+
+    pub fn show_pause(&mut self, ui: &UserInterface) {
+        self.show(ui, Some(Screen::Pause));
+    }
+
+    pub fn hide(&mut self, ui: &UserInterface) {
+        self.show(ui, None);
+    }
+
+    fn show(&mut self, ui: &UserInterface, screen: Option<Screen>) {
+        self.stop_rebinding(ui);
+        self.screen = screen;
+        for (handle, shown) in [
+            (self.main, Screen::Main),
+            (self.new_world_screen, Screen::NewWorld),
+            (self.load_world_screen, Screen::LoadWorld),
+            (self.settings_screen, Screen::Settings),
+            (self.pause, Screen::Pause),
+        ] {
+            ui.send_message(WidgetMessage::visibility(
+                handle,
+                MessageDirection::ToWidget,
+                screen == Some(shown),
+            ));
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            screen.is_some(),
+        ));
+    }
+
+    /// Keeps the menu covering the screen.
+    pub fn update(&mut self, ui: &UserInterface) {
+        let screen_size = ui.screen_size();
+        if screen_size != self.screen_size {
+            self.screen_size = screen_size;
+            ui.send_message(WidgetMessage::width(
+                self.root,
+                MessageDirection::ToWidget,
+                screen_size.x,
+            ));
+            ui.send_message(WidgetMessage::height(
+                self.root,
+                MessageDirection::ToWidget,
+                screen_size.y,
+            ));
+        }
+    }
+
+    /// Goes a screen back, as Escape does. Leaving the pause menu resumes the world.
+    pub fn back(&mut self, ui: &UserInterface) -> Option<MenuAction> {
+        match self.screen? {
+            Screen::Main => None,
+            Screen::Pause => Some(MenuAction::Resume),
+            Screen::Settings => {
+                self.show(ui, Some(self.settings_parent));
+                None
+            }
+            Screen::NewWorld | Screen::LoadWorld => {
+                self.show_main(ui);
+                None
+            }
+        }
+    }
+
+    /// Updates the text of the setting in the row of the settings screen.
+    fn show_setting(&self, ui: &UserInterface, row: usize) {
+        let row = &self.setting_rows[row];
+        let text = match row.setting {
+            Setting::Binding(action) if self.rebinding == Some(action) => {
+                format!("{}: press a key", action.name())
+            }
+            setting => self.game_settings.label(setting),
+        };
+        ui.send_message(TextMessage::text(
+            row.text,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+
+    fn show_bindings(&self, ui: &UserInterface) {
+        for (i, row) in self.setting_rows.iter().enumerate() {
+            if matches!(row.setting, Setting::Binding(_)) {
+                self.show_setting(ui, i);
+            }
+        }
+    }
+
+    /// Whether a binding waits for a key, the menu takes every key then.
+    pub fn is_rebinding(&self) -> bool {
+        self.rebinding.is_some()
+    }
+
+    fn stop_rebinding(&mut self, ui: &UserInterface) {
+        if self.rebinding.take().is_some() {
+            self.show_bindings(ui);
+        }
+    }
+
+    /// Binds the pressed key to the action that waits for one. Escape keeps the previous key,
+    /// keys that can't be bound, or not to this action, are ignored.
+    pub fn bind_key(&mut self, ui: &UserInterface, key: KeyCode) -> Option<MenuAction> {
+        let action = self.rebinding?;
+        if key == KeyCode::Escape {
+            self.stop_rebinding(ui);
+            return None;
+        }
+        if !BINDABLE_KEYS.contains(&key) || !self.game_settings.key_bindings.bind(action, key) {
+            return None;
+        }
+        self.stop_rebinding(ui);
+        Some(MenuAction::Settings(self.game_settings.clone()))
+    }
+
+    /// Changes the setting whose button was clicked.
+    fn click_setting(&mut self, ui: &UserInterface, clicked: Handle<UiNode>) -> Option<MenuAction> {
+        let row = self
+            .setting_rows
+            .iter()
+            .position(|row| clicked == row.button || clicked == row.less || clicked == row.more)?;
+        let setting = self.setting_rows[row].setting;
+        if let Setting::Binding(action) = setting {
+            self.stop_rebinding(ui);
+            self.rebinding = Some(action);
+            self.show_setting(ui, row);
+            return None;
+        }
+        let steps = if clicked == self.setting_rows[row].less {
+            -1
+        } else {
+            1
+        };
+        self.game_settings.step(setting, steps);
+        self.show_setting(ui, row);
+        Some(MenuAction::Settings(self.game_settings.clone()))
+    }
+
+    /// Lists the worlds in the saves as buttons, replacing the previous list.
+    fn list_worlds(&mut self, ui: &mut UserInterface, saves: &dyn Storage) {
+        for (button, _) in self.worlds.drain(..) {
+            ui.send_message(WidgetMessage::remove(button, MessageDirection::ToWidget));
+        }
+        for world in list_worlds(saves) {
+            let minutes = (world.time / 60.0) as u32;
+            let label = format!("{} ({} min, seed {})", world.name, minutes, world.seed);
+            let button = button(&mut ui.build_ctx(), &label);
+            ui.send_message(WidgetMessage::link(
+                button,
+                MessageDirection::ToWidget,
+                self.world_list,
+            ));
+            self.worlds.push((button, world.name));
+        }
+        if self.worlds.is_empty() {
+            let empty = text(&mut ui.build_ctx(), "No saved worlds");
+            ui.send_message(WidgetMessage::link(
+                empty,
+                MessageDirection::ToWidget,
+                self.world_list,
+            ));
+            self.worlds.push((empty, String::new()));
+        }
+    }
+
+    /// Follows clicks and typing in the menu, `saves` is the storage with the saved worlds.
+    pub fn handle_message(
+        &mut self,
+        ui: &mut UserInterface,
+        message: &UiMessage,
+        saves: &dyn Storage,
+    ) -> Option<MenuAction> {
+        if let Some(TextMessage::Text(text)) = message.data() {
+            if message.direction() == MessageDirection::FromWidget
+                && message.destination() == self.seed_box
+            {
+                self.seed = text.clone();
+            }
+            return None;
+        }
+        if !matches!(message.data(), Some(ButtonMessage::Click)) {
+            return None;
+        }
+        let clicked = message.destination();
+        if clicked == self.new_world {
+            self.show(ui, Some(Screen::NewWorld));
+        } else if clicked == self.create {
+            let name = new_world_name(&list_worlds(saves));
+            return Some(MenuAction::NewWorld {
+                name,
+                seed: parse_seed(&self.seed),
+            });
+        } else if clicked == self.load_world {
+            self.list_worlds(ui, saves);
+            self.show(ui, Some(Screen::LoadWorld));
+        } else if let Some((_, name)) = self.worlds.iter().find(|(button, _)| *button == clicked) {
+            return Some(MenuAction::LoadWorld(name.clone()));
+        } else if clicked == self.settings || clicked == self.pause_settings {
+            self.settings_parent = self.screen.unwrap_or(Screen::Main);
+            self.show(ui, Some(Screen::Settings));
+        } else if clicked == self.quit {
+            return Some(MenuAction::Quit);
+        } else if clicked == self.resume {
+            return Some(MenuAction::Resume);
+        } else if clicked == self.exit_to_title {
+            return Some(MenuAction::ExitToTitle);
+        } else if clicked == self.new_world_back
+            || clicked == self.load_world_back
+            || clicked == self.settings_back
+        {
+            return self.back(ui);
+        } else {
+            return self.click_setting(ui, clicked);
+        }
+        None
+    }
+}
@@ -0,0 +1,263 @@
+use fyrox::core::algebra::{Vector2, Vector3, Vector4};
+use fyrox::core::math::TriangleDefinition;
+use fyrox::scene::mesh::buffer::{TriangleBuffer, VertexBuffer};
+use fyrox::scene::mesh::surface::SurfaceData;
+use fyrox::scene::mesh::vertex::StaticVertex;
+
+use crate::block::{Block, BlockModel, ModelBox};
+use crate::chunk::BlockSource;
+use crate::direction::{Direction, CLOCK_WISE_TRIANGLE, COUNTER_CLOCK_WISE_TRIANGLE};
+
+/// Geometry generated for a box of blocks, with positions relative to the first block of the box.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkMesh {
+    pub vertices: Vec<StaticVertex>,
+    pub triangles: Vec<TriangleDefinition>,
+}
+
+impl ChunkMesh {
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Bytes the vertices and triangles take.
+    pub fn memory(&self) -> usize {
+        self.vertices.len() * std::mem::size_of::<StaticVertex>()
+            + self.triangles.len() * std::mem::size_of::<TriangleDefinition>()
+    }
+
+    pub(crate) fn push_quad(
+        &mut self,
+        vertices: impl IntoIterator<Item = StaticVertex>,
+        triangles: [TriangleDefinition; 2],
+    ) {
+        let base = self.vertices.len() as u32;
+        for triangle in triangles {
+            self.triangles.push(TriangleDefinition([
+                base + triangle[0],
+                base + triangle[1],
+                base + triangle[2],
+            ]));
+        }
+        self.vertices.extend(vertices);
+    }
+
+    /// Adds the geometry of another mesh moved by `offset`.
+    pub fn append(&mut self, other: ChunkMesh, offset: Vector3<f32>) {
+        let base = self.vertices.len() as u32;
+        self.triangles.extend(
+            other
+                .triangles
+                .iter()
+                .map(|triangle| TriangleDefinition(triangle.0.map(|index| base + index))),
+        );
+        self.vertices
+            .extend(other.vertices.into_iter().map(|vertex| StaticVertex {
+                position: vertex.position + offset,
+                ..vertex
+            }));
+    }
+
+    /// Scales positions, used for meshes built from downsampled blocks.
+    pub fn scale(&mut self, factor: f32) {
+        for vertex in self.vertices.iter_mut() {
+            vertex.position *= factor;
+        }
+    }
+
+    pub fn into_surface_data(self) -> SurfaceData {
+        let mut surface_data = SurfaceData::new(
+            VertexBuffer::new(self.vertices.len(), self.vertices).unwrap(),
+            TriangleBuffer::new(self.triangles),
+            false,
+        );
+        surface_data.calculate_tangents().unwrap();
+        surface_data
+    }
+}
+
+/// Axes of the texture coordinates `(u, v)` on a face.
+fn face_axes(direction: &Direction) -> (usize, usize) {
+    match direction {
+        Direction::Front | Direction::Back => (0, 1),
+        Direction::Top | Direction::Bottom => (0, 2),
+        Direction::Right | Direction::Left => (2, 1),
+    }
+}
+
+/// Fits the unit cube face from the `Direction` tables onto a face of `model_box`.
+pub(crate) fn box_face(
+    direction: &Direction,
+    model_box: &ModelBox,
+    offset: Vector3<f32>,
+) -> impl Iterator<Item = StaticVertex> {
+    let (u_axis, v_axis) = face_axes(direction);
+    let model_box = *model_box;
+    direction.verticles().into_iter().map(move |vertex| {
+        let corner = vertex.position.map(|v| v + 0.5);
+        let local = Vector3::from_fn(|axis, _| {
+            model_box.min[axis] + corner[axis] * (model_box.max[axis] - model_box.min[axis])
+        });
+        // Table coordinates either follow the axis or run against it.
+        let fit = |tex: f32, axis: usize| {
+            if (tex > 0.5) == (corner[axis] > 0.5) {
+                local[axis]
+            } else {
+                1.0 - local[axis]
+            }
+        };
+        StaticVertex {
+            position: offset + local,
+            tex_coord: Vector2::new(
+                fit(vertex.tex_coord.x, u_axis),
+                fit(vertex.tex_coord.y, v_axis),
+            ),
+            ..vertex
+        }
+    })
+}
+
+fn cross_quads(mesh: &mut ChunkMesh, offset: Vector3<f32>) {
+    const NEAR: f32 = 0.15;
+    const FAR: f32 = 0.85;
+    let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+    let quads = [
+        (
+            [NEAR, NEAR],
+            [FAR, FAR],
+            Vector3::new(-diagonal, 0.0, diagonal),
+        ),
+        (
+            [FAR, NEAR],
+            [NEAR, FAR],
+            Vector3::new(-diagonal, 0.0, -diagonal),
+        ),
+    ];
+    for (left, right, normal) in quads {
+        let vertex = |x: f32, y: f32, z: f32, u: f32, v: f32, normal: Vector3<f32>| StaticVertex {
+            position: offset + Vector3::new(x, y, z),
+            normal,
+            tex_coord: Vector2::new(u, v),
+            tangent: Vector4::default(),
+        };
+        // Plants are seen from both sides, so every quad is emitted twice with opposite winding.
+        for (normal, triangles) in [
+            (normal, CLOCK_WISE_TRIANGLE),
+            (-normal, COUNTER_CLOCK_WISE_TRIANGLE),
+        ] {
+            mesh.push_quad(
+                [
+                    vertex(left[0], 0.0, left[1], 0.0, 0.0, normal),
+                    vertex(left[0], 1.0, left[1], 0.0, 1.0, normal),
+                    vertex(right[0], 1.0, right[1], 1.0, 1.0, normal),
+                    vertex(right[0], 0.0, right[1], 1.0, 0.0, normal),
+                ],
+                triangles,
+            );
+        }
+    }
+}
+
+/// Whether a box of the neighbour in `direction` lies against the face of `model_box` pointing
+/// there and covers all of it.
+fn is_covered(neighbour: Block, model_box: &ModelBox, direction: &Direction) -> bool {
+    let BlockModel::Boxes { boxes, .. } = neighbour.model() else {
+        return false;
+    };
+    let axis = direction.axis();
+    let opposite = direction.opposite();
+    boxes.iter().any(|other| {
+        other.touches_side(&opposite)
+            && (0..3)
+                .filter(|other_axis| *other_axis != axis)
+                .all(|a| other.min[a] <= model_box.min[a] && other.max[a] >= model_box.max[a])
+    })
+}
+
+fn push_boxes(
+    mesh: &mut ChunkMesh,
+    source: &impl BlockSource,
+    block: Block,
+    position: Vector3<i32>,
+    boxes: &[ModelBox],
+    offset: Vector3<f32>,
+) {
+    for model_box in boxes {
+        for direction in Direction::iterator() {
+            if model_box.touches_side(direction) {
+                let neighbour = source.block(direction.neighbour(position));
+                // Liquids hide nothing but themselves, so the inside of water has no faces.
+                let hidden = if neighbour.is_liquid() {
+                    neighbour == block
+                } else {
+                    neighbour.is_face_full(&direction.opposite())
+                        || is_covered(neighbour, model_box, direction)
+                };
+                if hidden {
+                    continue;
+                }
+            }
+            mesh.push_quad(
+                box_face(direction, model_box, offset),
+                direction.triangles(),
+            );
+        }
+    }
+}
+
+/// Builds render geometry of blocks in the cube `origin..origin + size`, skipping faces hidden by
+/// full faces of neighbours, including those outside of the cube.
+pub fn build_mesh(source: &impl BlockSource, origin: Vector3<i32>, size: i32) -> ChunkMesh {
+    build(
+        source,
+        origin,
+        size,
+        |mesh, block, position, offset| match block.model() {
+            BlockModel::Empty => {}
+            BlockModel::Boxes { boxes, .. } => {
+                push_boxes(mesh, source, block, position, boxes, offset)
+            }
+            BlockModel::Cross => cross_quads(mesh, offset),
+        },
+    )
+}
+
+/// Builds geometry for the trimesh collider of blocks in the cube `origin..origin + size`.
+pub fn build_collision_mesh(
+    source: &impl BlockSource,
+    origin: Vector3<i32>,
+    size: i32,
+) -> ChunkMesh {
+    build(source, origin, size, |mesh, block, position, offset| {
+        push_boxes(
+            mesh,
+            source,
+            block,
+            position,
+            block.collision_boxes(),
+            offset,
+        )
+    })
+}
+
+fn build(
+    source: &impl BlockSource,
+    origin: Vector3<i32>,
+    size: i32,
+    mut emit: impl FnMut(&mut ChunkMesh, Block, Vector3<i32>, Vector3<f32>),
+) -> ChunkMesh {
+    let mut mesh = ChunkMesh::default();
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let local = Vector3::new(x, y, z);
+                let position = origin + local;
+                let block = source.block(position);
+                if !block.is_air() {
+                    emit(&mut mesh, block, position, local.cast::<f32>());
+                }
+            }
+        }
+    }
+    mesh
+}
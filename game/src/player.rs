@@ -0,0 +1,288 @@
+use fyrox::core::algebra::{UnitQuaternion, Vector3};
+
+use crate::chunk::BlockSource;
+use crate::inventory::Inventory;
+use crate::world::World;
+
+/// Blocks per second the player flies in creative.
+const FLY_SPEED: f32 = 6.0;
+
+/// Blocks per second the player walks.
+const WALK_SPEED: f32 = 4.3;
+
+/// Blocks per second squared.
+const GRAVITY: f32 = 25.0;
+
+/// The player never falls faster than this many blocks per second.
+const MAX_FALL_SPEED: f32 = 50.0;
+
+/// Upwards speed of a jump in blocks per second, high enough to get onto a block.
+const JUMP_SPEED: f32 = 8.5;
+
+/// Blocks per second the player swims up or down, and sinks at most, in liquids.
+const SWIM_SPEED: f32 = 3.0;
+
+/// Longest distance the player moves in one sub step, less than half of the width of the body so
+/// it can't pass through a block.
+const MAX_SUB_STEP: f32 = 0.25;
+
+/// Health of a player that is not hurt.
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// Seconds the player can stay under water before drowning.
+pub const MAX_AIR: f32 = 10.0;
+
+/// Health per second lost while out of air.
+const DROWNING_DAMAGE: f32 = 2.0;
+
+/// Air per second the player gets back above water.
+const AIR_RECOVERY: f32 = 5.0;
+
+/// Health per second the player gets back while not drowning.
+const REGENERATION: f32 = 0.5;
+
+/// Width and height of the box the player takes up.
+const BODY_SIZE: [f32; 2] = [0.6, 1.8];
+
+/// Height of the eyes above the bottom of the body.
+const EYE_HEIGHT: f32 = 1.6;
+
+/// Directions the player is asked to move in, held keys in the game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerInput {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    /// Jumps, swims up or flies up.
+    pub up: bool,
+    /// Swims down or flies down.
+    pub down: bool,
+}
+
+/// Rules the player plays by. Not saved, worlds open in survival.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    /// Can't be hurt, flies and places blocks without using them up.
+    Creative,
+}
+
+/// Player that walks, or flies in creative, and collides with the blocks. The camera follows its
+/// position and rotation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    position: Vector3<f32>,
+    /// Blocks per second.
+    velocity: Vector3<f32>,
+    on_ground: bool,
+    pitch: f32,
+    yaw: f32,
+    health: f32,
+    air: f32,
+    game_mode: GameMode,
+    inventory: Inventory,
+}
+
+impl Player {
+    pub fn new(position: Vector3<f32>) -> Self {
+        Self {
+            position,
+            velocity: Vector3::default(),
+            on_ground: false,
+            pitch: 0.0,
+            yaw: 0.0,
+            health: MAX_HEALTH,
+            air: MAX_AIR,
+            game_mode: GameMode::default(),
+            inventory: Inventory::default(),
+        }
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    /// Seconds of air left.
+    pub fn air(&self) -> f32 {
+        self.air
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        self.game_mode
+    }
+
+    /// Switching to creative heals the player.
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+        if game_mode == GameMode::Creative {
+            self.health = MAX_HEALTH;
+            self.air = MAX_AIR;
+        }
+    }
+
+    /// Uses up air while the eyes are under water and drowns once it ran out, otherwise gets air
+    /// and health back. `dt` is in seconds. In creative nothing changes.
+    pub fn breathe(&mut self, dt: f32, underwater: bool) {
+        if self.game_mode == GameMode::Creative {
+            return;
+        }
+        if underwater {
+            self.air = (self.air - dt).max(0.0);
+        } else {
+            self.air = (self.air + AIR_RECOVERY * dt).min(MAX_AIR);
+        }
+        if self.air <= 0.0 {
+            self.health = (self.health - DROWNING_DAMAGE * dt).max(0.0);
+        } else if !underwater {
+            self.health = (self.health + REGENERATION * dt).min(MAX_HEALTH);
+        }
+    }
+
+    /// Moves to the position with full health and air, the inventory is kept.
+    pub fn respawn(&mut self, position: Vector3<f32>) {
+        self.set_position(position);
+        self.health = MAX_HEALTH;
+        self.air = MAX_AIR;
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    /// Position of the eyes.
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    /// Minimum and maximum corner of the box the body of the player takes up, below the eyes.
+    pub fn bounding_box(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let [width, height] = BODY_SIZE;
+        let min = self.position - Vector3::new(width / 2.0, EYE_HEIGHT, width / 2.0);
+        (min, min + Vector3::new(width, height, width))
+    }
+
+    /// Moves there and stops.
+    pub fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+        self.velocity = Vector3::default();
+        self.on_ground = false;
+    }
+
+    /// Blocks per second.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// Whether the player stands on a block, only then it can jump.
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    pub fn rotation(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_euler_angles(self.pitch, self.yaw, 0.0)
+    }
+
+    pub fn look_vector(&self) -> Vector3<f32> {
+        self.rotation() * Vector3::z()
+    }
+
+    pub fn side_vector(&self) -> Vector3<f32> {
+        self.rotation() * Vector3::x()
+    }
+
+    /// Turns by angles in radians, looking up or down stops at straight up and down.
+    pub fn turn(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-90.0f32.to_radians(), 90.0f32.to_radians());
+    }
+
+    /// Moves for `dt` seconds in the directions of the input, walking in survival and flying in
+    /// creative, and stops at the blocks in the way.
+    pub fn step(&mut self, world: &World, dt: f32, input: &PlayerInput) {
+        let flying = self.game_mode == GameMode::Creative;
+        // Walking goes along the ground wherever the player looks.
+        let heading = if flying {
+            self.rotation()
+        } else {
+            UnitQuaternion::from_euler_angles(0.0, self.yaw, 0.0)
+        };
+        let mut offset = Vector3::default();
+        if input.forward {
+            offset += heading * Vector3::z();
+        }
+        if input.backward {
+            offset -= heading * Vector3::z();
+        }
+        if input.left {
+            offset += heading * Vector3::x();
+        }
+        if input.right {
+            offset -= heading * Vector3::x();
+        }
+        if flying {
+            if input.up {
+                offset.y += 1.0;
+            }
+            if input.down {
+                offset.y -= 1.0;
+            }
+            self.velocity = offset
+                .try_normalize(f32::EPSILON)
+                .map_or_else(Vector3::default, |offset| offset.scale(FLY_SPEED));
+        } else {
+            let walk = offset
+                .try_normalize(f32::EPSILON)
+                .map_or_else(Vector3::default, |offset| offset.scale(WALK_SPEED));
+            self.velocity.x = walk.x;
+            self.velocity.z = walk.z;
+            let (min, max) = self.bounding_box();
+            let middle = ((min + max) / 2.0).map(|v| v.floor() as i32);
+            self.velocity.y = if world.block(middle).is_liquid() {
+                if input.up {
+                    SWIM_SPEED
+                } else if input.down {
+                    -SWIM_SPEED
+                } else {
+                    (self.velocity.y - GRAVITY * dt).max(-SWIM_SPEED)
+                }
+            } else if input.up && self.on_ground {
+                JUMP_SPEED
+            } else {
+                (self.velocity.y - GRAVITY * dt).max(-MAX_FALL_SPEED)
+            };
+        }
+
+        let steps = (self.velocity.norm() * dt / MAX_SUB_STEP)
+            .ceil()
+            .clamp(1.0, 64.0) as usize;
+        let dt = dt / steps as f32;
+        self.on_ground = false;
+        for _ in 0..steps {
+            for axis in 0..3 {
+                let distance = self.velocity[axis] * dt;
+                if distance == 0.0 {
+                    continue;
+                }
+                let (min, max) = self.bounding_box();
+                let moved = world.sweep(min, max, axis, distance);
+                self.position[axis] += moved;
+                if moved != distance {
+                    if axis == 1 && distance < 0.0 {
+                        self.on_ground = true;
+                    }
+                    self.velocity[axis] = 0.0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
+use fyrox::core::instant::Instant;
+
+use crate::command::{Argument, Arguments, Command, CommandContext, CommandError, CommandRegistry};
+use crate::storage;
+
+/// Frames kept for the graph of the debug overlay.
+pub const FRAME_HISTORY: usize = 120;
+
+/// Events a trace keeps, later ones are dropped so a forgotten trace can't fill the memory.
+const MAX_TRACE_EVENTS: usize = 1_000_000;
+
+/// Name of the event of a whole frame in traces.
+const FRAME_EVENT: &str = "Frame";
+
+/// Where the trace command writes traces.
+const TRACES_DIRECTORY: &str = "traces";
+
+/// Part of the game whose time is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Generating blocks of chunks that were not saved.
+    Generation,
+    /// Building chunk meshes out of blocks, of every level of detail.
+    Meshing,
+    /// Turning chunk meshes into surfaces and nodes of the scene.
+    MeshUpload,
+    /// Building collision meshes and static bodies of chunks.
+    Colliders,
+    Raycast,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 5] = [
+        Scope::Generation,
+        Scope::Meshing,
+        Scope::MeshUpload,
+        Scope::Colliders,
+        Scope::Raycast,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::Generation => "Generation",
+            Scope::Meshing => "Meshing",
+            Scope::MeshUpload => "Mesh upload",
+            Scope::Colliders => "Colliders",
+            Scope::Raycast => "Raycast",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Where the time of a frame went.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameProfile {
+    /// Time `Plugin::update` took.
+    pub update: Duration,
+    /// Time spent in every scope, in the order of `Scope::ALL`. Scopes on worker threads count
+    /// to the frame they finished in, so together they can take longer than the update.
+    pub scopes: [Duration; Scope::ALL.len()],
+}
+
+impl FrameProfile {
+    pub fn scope(&self, scope: Scope) -> Duration {
+        self.scopes[scope.index()]
+    }
+}
+
+struct TraceEvent {
+    name: &'static str,
+    thread: u64,
+    start: Duration,
+    duration: Duration,
+}
+
+/// Sums up timed scopes per frame and keeps the last frames, optionally also every single scope
+/// for a trace. Times are since the first timer of the game started.
+#[derive(Default)]
+pub struct Profiler {
+    frame: FrameProfile,
+    frames: VecDeque<FrameProfile>,
+    trace: Option<Vec<TraceEvent>>,
+}
+
+impl Profiler {
+    fn trace(&mut self, name: &'static str, thread: u64, start: Duration, duration: Duration) {
+        if let Some(trace) = &mut self.trace {
+            if trace.len() < MAX_TRACE_EVENTS {
+                trace.push(TraceEvent {
+                    name,
+                    thread,
+                    start,
+                    duration,
+                });
+            }
+        }
+    }
+
+    /// Adds a scope that began at `start` on the thread to the current frame.
+    pub fn record(&mut self, scope: Scope, thread: u64, start: Duration, duration: Duration) {
+        self.frame.scopes[scope.index()] += duration;
+        self.trace(scope.name(), thread, start, duration);
+    }
+
+    /// Ends the current frame, whose update began at `start` and took `update`.
+    pub fn finish_frame(&mut self, thread: u64, start: Duration, update: Duration) {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.update = update;
+        if self.frames.len() == FRAME_HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        self.trace(FRAME_EVENT, thread, start, update);
+    }
+
+    /// Finished frames, the oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameProfile> {
+        self.frames.iter()
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Starts keeping every scope and frame, dropping a trace that was not stopped.
+    pub fn start_trace(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+    /// Stops the trace and returns it in the Chrome trace event format, which `chrome://tracing`
+    /// and Perfetto open. `None` when no trace was started.
+    pub fn stop_trace(&mut self) -> Option<String> {
+        let events = self.trace.take()?;
+        let mut json = String::from(r#"{"traceEvents":["#);
+        for (i, event) in events.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                r#"{{"name":"{}","cat":"mine64","ph":"X","pid":1,"tid":{},"ts":{:.3},"dur":{:.3}}}"#,
+                event.name,
+                event.thread,
+                event.start.as_secs_f64() * 1e6,
+                event.duration.as_secs_f64() * 1e6
+            )
+            .unwrap();
+        }
+        json.push_str(r#"],"displayTimeUnit":"ms"}"#);
+        Some(json)
+    }
+}
+
+/// When the first timer started, the times of the profiler begin there.
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// Number of the current thread in traces, in the order threads first measured something.
+fn thread_number() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    thread_local! {
+        static NUMBER: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    NUMBER.with(|number| *number)
+}
+
+/// Profiler of the game, shared by the main thread and the chunk workers.
+pub fn profiler() -> MutexGuard<'static, Profiler> {
+    static PROFILER: OnceLock<Mutex<Profiler>> = OnceLock::new();
+    PROFILER
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Measures a scope until it is dropped.
+pub struct ScopeTimer {
+    scope: Scope,
+    start: Instant,
+}
+
+impl Drop for ScopeTimer {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        let start = self.start.duration_since(epoch());
+        profiler().record(self.scope, thread_number(), start, duration);
+    }
+}
+
+/// Starts measuring the scope, it ends when the returned timer is dropped:
+/// `let _timer = profiler::time(Scope::Meshing);`
+pub fn time(scope: Scope) -> ScopeTimer {
+    epoch();
+    ScopeTimer {
+        scope,
+        start: Instant::now(),
+    }
+}
+
+/// Measures a frame until it is dropped, then finishes it in the profiler.
+pub struct FrameTimer {
+    start: Instant,
+}
+
+impl Drop for FrameTimer {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        let start = self.start.duration_since(epoch());
+        profiler().finish_frame(thread_number(), start, duration);
+    }
+}
+
+/// Starts measuring the update of a frame, see `FrameTimer`.
+pub fn frame() -> FrameTimer {
+    epoch();
+    FrameTimer {
+        start: Instant::now(),
+    }
+}
+
+/// Records every timed scope from `trace start` on, `trace stop` writes them as a Chrome trace.
+fn trace(_context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(1..=1)?;
+    let mut profiler = profiler();
+    match arguments.get(0)? {
+        "start" => {
+            profiler.start_trace();
+            Ok("Tracing, stop with trace stop".to_string())
+        }
+        "stop" => {
+            let json = profiler
+                .stop_trace()
+                .ok_or_else(|| CommandError::Failed("no trace was started".to_string()))?;
+            drop(profiler);
+            let name = "trace.json";
+            storage::open(TRACES_DIRECTORY)
+                .and_then(|storage| storage.write(name, json.as_bytes()))
+                .map_err(|error| {
+                    CommandError::Failed(format!("failed to write {name}: {error}"))
+                })?;
+            Ok(format!("Wrote {TRACES_DIRECTORY}/{name}"))
+        }
+        _ => Err(arguments.usage_error()),
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register(Command {
+        name: "trace",
+        usage: "trace start|stop",
+        help: "records where the time of frames goes, for chrome://tracing or Perfetto",
+        arguments: &[Argument::Choice(&["start", "stop"])],
+        run: trace,
+    });
+}
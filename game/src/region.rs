@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use fyrox::core::algebra::{Vector2, Vector3};
+
+use crate::block::Block;
+use crate::chunk::Chunk;
+use crate::item::{DroppedItem, ItemStack};
+use crate::save::{checksum, write_vector3, ByteReader, SaveError};
+use crate::world_generator::WORLD_HEIGHT;
+
+/// Regions are squares of `REGION_SIZE` by `REGION_SIZE` chunk columns.
+pub const REGION_SIZE: i32 = 32;
+
+const REGION_SLOTS: usize = (REGION_SIZE * REGION_SIZE * WORLD_HEIGHT) as usize;
+
+const MAGIC: &[u8; 4] = b"M64R";
+
+/// Bump when the layout changes and teach `Region::read` to read the old one.
+pub const REGION_VERSION: u32 = 3;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_RUN_LENGTH: u8 = 1;
+
+/// Region containing the chunk.
+pub fn region_position(chunk: Vector3<i32>) -> Vector2<i32> {
+    Vector2::new(
+        chunk.x.div_euclid(REGION_SIZE),
+        chunk.z.div_euclid(REGION_SIZE),
+    )
+}
+
+/// Position of the chunk stored in the slot of the region.
+pub fn slot_position(region: Vector2<i32>, slot: usize) -> Vector3<i32> {
+    let slot = slot as i32;
+    let column = slot / WORLD_HEIGHT;
+    Vector3::new(
+        region.x * REGION_SIZE + column % REGION_SIZE,
+        slot % WORLD_HEIGHT,
+        region.y * REGION_SIZE + column / REGION_SIZE,
+    )
+}
+
+/// Slot of the chunk in the header table of its region, `None` for chunks outside of the world
+/// height, which are never saved.
+fn slot(chunk: Vector3<i32>) -> Option<usize> {
+    if !(0..WORLD_HEIGHT).contains(&chunk.y) {
+        return None;
+    }
+    let x = chunk.x.rem_euclid(REGION_SIZE);
+    let z = chunk.z.rem_euclid(REGION_SIZE);
+    Some(((x + z * REGION_SIZE) * WORLD_HEIGHT + chunk.y) as usize)
+}
+
+/// Compresses block ids as runs of `(length: u16, id: u8)`, or stores them as they are when that
+/// turns out to be shorter.
+pub fn encode_blocks(blocks: &[Block]) -> Vec<u8> {
+    let mut runs = vec![COMPRESSION_RUN_LENGTH];
+    let mut start = 0;
+    while start < blocks.len() {
+        let block = blocks[start];
+        let length = blocks[start..]
+            .iter()
+            .take(u16::MAX as usize)
+            .take_while(|other| **other == block)
+            .count();
+        runs.extend_from_slice(&(length as u16).to_le_bytes());
+        runs.push(block.id());
+        start += length;
+    }
+    if runs.len() <= blocks.len() {
+        return runs;
+    }
+    let mut raw = vec![COMPRESSION_NONE];
+    raw.extend(blocks.iter().map(Block::id));
+    raw
+}
+
+pub fn decode_blocks(bytes: &[u8]) -> Result<Vec<Block>, SaveError> {
+    let mut reader = ByteReader::new(bytes);
+    let block = |id: u8| {
+        Block::from_id(id).ok_or_else(|| SaveError::Corrupted(format!("unknown block id {id}")))
+    };
+    let mut blocks = Vec::new();
+    match reader.u8()? {
+        COMPRESSION_NONE => {
+            while !reader.is_empty() {
+                blocks.push(block(reader.u8()?)?);
+            }
+        }
+        COMPRESSION_RUN_LENGTH => {
+            while !reader.is_empty() {
+                let length = reader.u16()? as usize;
+                let block = block(reader.u8()?)?;
+                blocks.extend(std::iter::repeat_n(block, length));
+            }
+        }
+        compression => {
+            return Err(SaveError::Corrupted(format!(
+                "unknown compression {compression}"
+            )))
+        }
+    }
+    Ok(blocks)
+}
+
+/// Record of version 2 and older regions, only blocks, in the current layout with no items.
+fn upgrade_record(blocks: &[u8]) -> Vec<u8> {
+    let mut record = (blocks.len() as u32).to_le_bytes().to_vec();
+    record.extend_from_slice(blocks);
+    record.extend_from_slice(&0u16.to_le_bytes());
+    record
+}
+
+/// Length of the encoded blocks, the blocks and the dropped items lying in the chunk.
+pub fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    let blocks = encode_blocks(chunk.blocks());
+    let mut bytes = (blocks.len() as u32).to_le_bytes().to_vec();
+    bytes.extend(blocks);
+    let items = &chunk.items()[..chunk.items().len().min(u16::MAX as usize)];
+    bytes.extend_from_slice(&(items.len() as u16).to_le_bytes());
+    for item in items {
+        bytes.push(item.stack.block.id());
+        bytes.extend_from_slice(&(item.stack.count as u16).to_le_bytes());
+        write_vector3(&mut bytes, &item.position);
+        write_vector3(&mut bytes, &item.velocity);
+        bytes.extend_from_slice(&item.age.to_le_bytes());
+    }
+    bytes
+}
+
+pub fn decode_chunk(position: Vector3<i32>, bytes: &[u8]) -> Result<Chunk, SaveError> {
+    let mut reader = ByteReader::new(bytes);
+    let length = reader.u32()? as usize;
+    let blocks = decode_blocks(reader.bytes(length)?)?;
+    let mut chunk = Chunk::from_blocks(position, blocks).ok_or_else(|| {
+        SaveError::Corrupted(format!("wrong number of blocks in chunk {position:?}"))
+    })?;
+    for _ in 0..reader.u16()? {
+        let id = reader.u8()?;
+        let block = Block::from_id(id)
+            .ok_or_else(|| SaveError::Corrupted(format!("unknown item block id {id}")))?;
+        let stack = ItemStack::new(block, reader.u16()? as u32);
+        let mut item = DroppedItem::new(stack, reader.vector3()?, reader.vector3()?);
+        item.age = reader.f32()?;
+        chunk.add_item(item);
+    }
+    if !reader.is_empty() {
+        return Err(SaveError::Corrupted(format!(
+            "unexpected data after chunk {position:?}"
+        )));
+    }
+    // Only modified chunks are ever saved.
+    chunk.set_modified(true);
+    Ok(chunk)
+}
+
+/// Record that failed its checksum or did not fit in the file.
+#[derive(Debug)]
+pub struct CorruptedRecord {
+    pub slot: usize,
+    /// Whatever was left of the record, empty when it was out of the file.
+    pub bytes: Vec<u8>,
+}
+
+/// Encoded chunk records of one region file.
+///
+/// The file starts with a magic, a version and a table with an `(offset, length, checksum)`
+/// entry per chunk slot, followed by the records. Empty slots have zero length. Version 1 files
+/// have no checksums and records of version 1 and 2 files have only blocks, they are upgraded
+/// while reading.
+#[derive(Debug, Default)]
+pub struct Region {
+    records: HashMap<usize, Vec<u8>>,
+    corrupted: Vec<CorruptedRecord>,
+}
+
+impl Region {
+    /// Reads the region, records that are damaged are left out and can be taken with
+    /// `take_corrupted`. Fails only when the header itself can't be read.
+    pub fn read(bytes: &[u8]) -> Result<Region, SaveError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(SaveError::Corrupted("not a region file".to_string()));
+        }
+        let (has_checksums, has_items) = match reader.u32()? {
+            1 => (false, false),
+            2 => (true, false),
+            REGION_VERSION => (true, true),
+            version => return Err(SaveError::UnsupportedVersion(version)),
+        };
+        let slots = reader.u32()? as usize;
+        if slots > REGION_SLOTS {
+            return Err(SaveError::Corrupted(format!("region has {slots} slots")));
+        }
+        let mut table = Vec::with_capacity(slots);
+        for _ in 0..slots {
+            let offset = reader.u32()? as usize;
+            let length = reader.u32()? as usize;
+            let expected = if has_checksums {
+                Some(reader.u32()?)
+            } else {
+                None
+            };
+            table.push((offset, length, expected));
+        }
+        let mut region = Region::default();
+        for (slot, (offset, length, expected)) in table.into_iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            match bytes.get(offset..offset.saturating_add(length)) {
+                Some(record) if expected.is_none_or(|expected| checksum(record) == expected) => {
+                    let record = if has_items {
+                        record.to_vec()
+                    } else {
+                        upgrade_record(record)
+                    };
+                    region.records.insert(slot, record);
+                }
+                record => region.corrupted.push(CorruptedRecord {
+                    slot,
+                    bytes: record.unwrap_or_default().to_vec(),
+                }),
+            }
+        }
+        Ok(region)
+    }
+
+    /// Records that were found damaged while reading, they are forgotten once taken.
+    pub fn take_corrupted(&mut self) -> Vec<CorruptedRecord> {
+        std::mem::take(&mut self.corrupted)
+    }
+
+    /// Decodes every record of the region at the position and moves those that fail to decode to
+    /// the corrupted ones.
+    pub fn validate(&mut self, position: Vector2<i32>) {
+        let invalid = self
+            .records
+            .iter()
+            .filter(|(slot, record)| decode_chunk(slot_position(position, **slot), record).is_err())
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<_>>();
+        for slot in invalid {
+            if let Some(bytes) = self.records.remove(&slot) {
+                self.corrupted.push(CorruptedRecord { slot, bytes });
+            }
+        }
+    }
+
+    pub fn write(&self) -> Vec<u8> {
+        let header = MAGIC.len() + 8 + REGION_SLOTS * 12;
+        let mut table = Vec::with_capacity(header);
+        let mut data = Vec::new();
+        table.extend_from_slice(MAGIC);
+        table.extend_from_slice(&REGION_VERSION.to_le_bytes());
+        table.extend_from_slice(&(REGION_SLOTS as u32).to_le_bytes());
+        for slot in 0..REGION_SLOTS {
+            let (offset, length, sum) = match self.records.get(&slot) {
+                Some(record) => {
+                    let offset = header + data.len();
+                    data.extend_from_slice(record);
+                    (offset, record.len(), checksum(record))
+                }
+                None => (0, 0, 0),
+            };
+            table.extend_from_slice(&(offset as u32).to_le_bytes());
+            table.extend_from_slice(&(length as u32).to_le_bytes());
+            table.extend_from_slice(&sum.to_le_bytes());
+        }
+        table.extend(data);
+        table
+    }
+
+    pub fn chunk(&self, position: Vector3<i32>) -> Result<Option<Chunk>, SaveError> {
+        match slot(position).and_then(|slot| self.records.get(&slot)) {
+            Some(record) => decode_chunk(position, record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the chunk, returns `false` when it is outside of the world height and can't be saved.
+    pub fn insert(&mut self, chunk: &Chunk) -> bool {
+        match slot(chunk.position()) {
+            Some(slot) => {
+                self.records.insert(slot, encode_chunk(chunk));
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use fyrox::core::algebra::{UnitQuaternion, Vector3};
+use fyrox::core::math::aabb::AxisAlignedBoundingBox;
+use fyrox::core::math::frustum::Frustum;
+use fyrox::core::pool::Handle;
+use fyrox::scene::base::{BaseBuilder, LevelOfDetail, LodGroup};
+use fyrox::scene::collider::{ColliderBuilder, ColliderShape, GeometrySource, TrimeshShape};
+use fyrox::scene::graph::Graph;
+use fyrox::scene::mesh::surface::{SurfaceBuilder, SurfaceSharedData};
+use fyrox::scene::mesh::MeshBuilder;
+use fyrox::scene::node::Node;
+use fyrox::scene::pivot::PivotBuilder;
+use fyrox::scene::rigidbody::{RigidBodyBuilder, RigidBodyType};
+use fyrox::scene::transform::TransformBuilder;
+
+use crate::block::Block;
+use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::direction::Direction;
+use crate::item::{DroppedItem, ITEM_SIZE};
+use crate::lod::build_lod_meshes;
+use crate::mesher::{build_collision_mesh, build_mesh, ChunkMesh};
+use crate::profiler::{self, Scope};
+use crate::visibility::{ChunkCulling, CullingStats};
+use crate::world::World;
+use crate::world_generator::WORLD_HEIGHT;
+
+/// Chunks closer to the camera than this many chunks use the full detail mesh and have colliders.
+pub const DETAIL_DISTANCE: i32 = 4;
+
+/// Distances in chunks where the levels of `lod::LOD_FACTORS` begin.
+const LOD_DISTANCES: [i32; 3] = [DETAIL_DISTANCE, 8, 16];
+
+/// Far clipping plane of the camera for chunks loaded `view_distance` chunks around it, level of
+/// detail distances are relative to it.
+pub fn z_far(view_distance: i32) -> f32 {
+    ((view_distance + 2) * CHUNK_SIZE) as f32
+}
+
+/// Full detail meshes are built on the main thread, so only a few of them per frame.
+const MAX_DETAIL_PER_FRAME: usize = 4;
+
+/// Radians per second dropped items turn by.
+const ITEM_SPIN: f32 = 1.5;
+
+/// Sizes of chunk meshes in the scene.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeshStats {
+    pub meshes: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Bytes of vertices and triangles.
+    pub memory: usize,
+}
+
+impl MeshStats {
+    fn of(mesh: &ChunkMesh) -> Self {
+        if mesh.is_empty() {
+            return Self::default();
+        }
+        Self {
+            meshes: 1,
+            vertices: mesh.vertices.len(),
+            triangles: mesh.triangles.len(),
+            memory: mesh.memory(),
+        }
+    }
+
+    fn add(&mut self, other: MeshStats) {
+        self.meshes += other.meshes;
+        self.vertices += other.vertices;
+        self.triangles += other.triangles;
+        self.memory += other.memory;
+    }
+}
+
+struct ChunkNodes {
+    pivot: Handle<Node>,
+    lods: Vec<Handle<Node>>,
+    /// Whether the full detail mesh and the collider were built, either can still be missing when
+    /// all faces are hidden.
+    detailed: bool,
+    detail: Handle<Node>,
+    body: Handle<Node>,
+    lod_stats: MeshStats,
+    detail_stats: MeshStats,
+}
+
+fn mesh_node(graph: &mut Graph, mesh: ChunkMesh, visible: bool) -> Handle<Node> {
+    if mesh.is_empty() {
+        return Handle::NONE;
+    }
+    let _timer = profiler::time(Scope::MeshUpload);
+    MeshBuilder::new(BaseBuilder::new().with_visibility(visible))
+        .with_surfaces(vec![SurfaceBuilder::new(SurfaceSharedData::new(
+            mesh.into_surface_data(),
+        ))
+        // .with_material(sector_builder.grass_shader.clone())
+        .build()])
+        .build(graph)
+}
+
+fn lod_group(nodes: &ChunkNodes, z_far: f32) -> LodGroup {
+    let normalize = |chunks: i32| ((chunks * CHUNK_SIZE) as f32 / z_far).min(1.0);
+    // Until the full detail mesh is built the first downsampled mesh stands in for it.
+    let nearest = if nodes.detailed {
+        nodes.detail
+    } else {
+        nodes.lods[0]
+    };
+    let mut levels = vec![LevelOfDetail::new(
+        0.0,
+        normalize(LOD_DISTANCES[0]),
+        vec![nearest],
+    )];
+    for (i, lod) in nodes.lods.iter().enumerate() {
+        let end = LOD_DISTANCES
+            .get(i + 1)
+            .map_or(1.0, |chunks| normalize(*chunks));
+        levels.push(LevelOfDetail::new(
+            normalize(LOD_DISTANCES[i]),
+            end,
+            vec![*lod],
+        ));
+    }
+    for level in levels.iter_mut() {
+        level.objects.retain(|object| object.is_some());
+    }
+    LodGroup { levels }
+}
+
+/// Full detail mesh of a chunk and a static body with a trimesh collider built from collision
+/// boxes of its blocks, with the size of the mesh.
+fn build_detail(
+    graph: &mut Graph,
+    world: &World,
+    position: Vector3<i32>,
+) -> (Handle<Node>, Handle<Node>, MeshStats) {
+    let origin = position * CHUNK_SIZE;
+    let mesh = {
+        let _timer = profiler::time(Scope::Meshing);
+        build_mesh(world, origin, CHUNK_SIZE)
+    };
+    let stats = MeshStats::of(&mesh);
+    let detail = mesh_node(graph, mesh, true);
+
+    let collision_mesh = {
+        let _timer = profiler::time(Scope::Colliders);
+        build_collision_mesh(world, origin, CHUNK_SIZE)
+    };
+    let collision_mesh = mesh_node(graph, collision_mesh, false);
+    if collision_mesh.is_none() {
+        return (detail, Handle::NONE, stats);
+    }
+    let _timer = profiler::time(Scope::Colliders);
+    graph[collision_mesh]
+        .local_transform_mut()
+        .set_position(origin.cast::<f32>());
+    let collider = ColliderBuilder::new(BaseBuilder::new())
+        .with_shape(ColliderShape::Trimesh(TrimeshShape {
+            sources: vec![GeometrySource(collision_mesh)],
+        }))
+        .build(graph);
+    let body = RigidBodyBuilder::new(BaseBuilder::new().with_children(&[collision_mesh, collider]))
+        .with_body_type(RigidBodyType::Static)
+        .build(graph);
+    (detail, body, stats)
+}
+
+/// Scene nodes of loaded chunks. Every chunk has a pivot with a `LodGroup` switching between the
+/// full detail mesh near the camera and downsampled meshes further away.
+pub struct ChunkRenderer {
+    chunks: HashMap<Vector3<i32>, ChunkNodes>,
+    /// Radius in chunks of the loaded world, chunks further away are never drawn.
+    view_distance: i32,
+}
+
+impl ChunkRenderer {
+    pub fn new(view_distance: i32) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            view_distance,
+        }
+    }
+
+    /// Far clipping plane the camera needs for the view distance.
+    pub fn z_far(&self) -> f32 {
+        z_far(self.view_distance)
+    }
+
+    /// Adds nodes of a freshly loaded chunk, `lods` are its meshes for `lod::LOD_FACTORS`.
+    pub fn add_chunk(&mut self, graph: &mut Graph, chunk: &Chunk, lods: Vec<ChunkMesh>) {
+        self.remove_chunk(graph, chunk.position());
+        if chunk.is_empty() {
+            return;
+        }
+        let mut lod_stats = MeshStats::default();
+        let lods: Vec<Handle<Node>> = lods
+            .into_iter()
+            .map(|mesh| {
+                lod_stats.add(MeshStats::of(&mesh));
+                mesh_node(graph, mesh, true)
+            })
+            .collect();
+        let children: Vec<Handle<Node>> = lods.iter().copied().filter(Handle::is_some).collect();
+        let pivot = PivotBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(chunk.origin().cast::<f32>())
+                        .build(),
+                )
+                .with_children(&children),
+        )
+        .build(graph);
+        let nodes = ChunkNodes {
+            pivot,
+            lods,
+            detailed: false,
+            detail: Handle::NONE,
+            body: Handle::NONE,
+            lod_stats,
+            detail_stats: MeshStats::default(),
+        };
+        graph[pivot].set_lod_group(Some(lod_group(&nodes, self.z_far())));
+        self.chunks.insert(chunk.position(), nodes);
+    }
+
+    /// Rebuilds the nodes of a chunk whose blocks changed, the full detail mesh comes back with
+    /// the next `update_detail`.
+    pub fn rebuild_chunk(&mut self, graph: &mut Graph, chunk: &Chunk) {
+        let lods = {
+            let _timer = profiler::time(Scope::Meshing);
+            build_lod_meshes(chunk)
+        };
+        self.add_chunk(graph, chunk, lods);
+    }
+
+    pub fn remove_chunk(&mut self, graph: &mut Graph, position: Vector3<i32>) {
+        if let Some(nodes) = self.chunks.remove(&position) {
+            graph.remove_node(nodes.pivot);
+            if nodes.body.is_some() {
+                graph.remove_node(nodes.body);
+            }
+        }
+    }
+
+    /// Sizes of all chunk meshes, of every level of detail.
+    pub fn mesh_stats(&self) -> MeshStats {
+        let mut stats = MeshStats::default();
+        for nodes in self.chunks.values() {
+            stats.add(nodes.lod_stats);
+            stats.add(nodes.detail_stats);
+        }
+        stats
+    }
+
+    fn neighbours_loaded(world: &World, position: Vector3<i32>) -> bool {
+        Direction::iterator().all(|direction| {
+            let neighbour = direction.neighbour(position);
+            !(0..WORLD_HEIGHT).contains(&neighbour.y) || world.chunk(neighbour).is_some()
+        })
+    }
+
+    /// Builds full detail meshes and colliders of chunks that came close to `center` and drops
+    /// them from chunks that went away.
+    pub fn update_detail(&mut self, graph: &mut Graph, world: &World, center: Vector3<i32>) {
+        let z_far = self.z_far();
+        let mut built = 0;
+        for (position, nodes) in self.chunks.iter_mut() {
+            let (dx, dz) = (position.x - center.x, position.z - center.z);
+            let distance = dx * dx + dz * dz;
+            if !nodes.detailed {
+                if distance > DETAIL_DISTANCE * DETAIL_DISTANCE
+                    || built >= MAX_DETAIL_PER_FRAME
+                    || !Self::neighbours_loaded(world, *position)
+                {
+                    continue;
+                }
+                built += 1;
+                let (detail, body, stats) = build_detail(graph, world, *position);
+                if detail.is_some() {
+                    graph.link_nodes(detail, nodes.pivot);
+                }
+                nodes.detailed = true;
+                nodes.detail = detail;
+                nodes.body = body;
+                nodes.detail_stats = stats;
+            } else if distance > (DETAIL_DISTANCE + 1) * (DETAIL_DISTANCE + 1) {
+                for handle in [nodes.detail, nodes.body] {
+                    if handle.is_some() {
+                        graph.remove_node(handle);
+                    }
+                }
+                nodes.detailed = false;
+                nodes.detail = Handle::NONE;
+                nodes.body = Handle::NONE;
+                nodes.detail_stats = MeshStats::default();
+            } else {
+                continue;
+            }
+            graph[nodes.pivot].set_lod_group(Some(lod_group(nodes, z_far)));
+        }
+    }
+
+    /// Hides chunks the camera can't see, either because they are out of the frustum or because
+    /// `culling` found no way to look into them from the camera chunk.
+    pub fn cull(
+        &self,
+        graph: &mut Graph,
+        culling: &ChunkCulling,
+        camera: Handle<Node>,
+        center: Vector3<i32>,
+    ) -> CullingStats {
+        let frustum = Frustum::from_view_projection_matrix(
+            graph[camera].as_camera().view_projection_matrix(),
+        );
+        let in_frustum = |position: Vector3<i32>| {
+            let min = (position * CHUNK_SIZE).cast::<f32>();
+            let aabb = AxisAlignedBoundingBox::from_min_max(min, min.add_scalar(CHUNK_SIZE as f32));
+            frustum
+                .as_ref()
+                .is_none_or(|frustum| frustum.is_intersects_aabb(&aabb))
+        };
+        let visible = culling.visible_chunks(center, self.view_distance, in_frustum);
+
+        let mut stats = CullingStats::default();
+        for (position, nodes) in self.chunks.iter() {
+            let is_visible = visible.contains(position);
+            if is_visible {
+                stats.drawn += 1;
+            } else if in_frustum(*position) {
+                stats.occlusion_culled += 1;
+            } else {
+                stats.frustum_culled += 1;
+            }
+            let pivot = &mut graph[nodes.pivot];
+            if pivot.visibility() != is_visible {
+                pivot.set_visibility(is_visible);
+            }
+        }
+        stats
+    }
+}
+
+/// Scene nodes of dropped items, a little cube of their block each.
+#[derive(Default)]
+pub struct ItemRenderer {
+    surfaces: HashMap<Block, SurfaceSharedData>,
+    /// Node of every item, in the order of the items.
+    nodes: Vec<(Block, Handle<Node>)>,
+}
+
+impl ItemRenderer {
+    fn item_node(&mut self, graph: &mut Graph, block: Block) -> Handle<Node> {
+        let surface = self
+            .surfaces
+            .entry(block)
+            .or_insert_with(|| {
+                let mut chunk = Chunk::new(Vector3::zeros());
+                chunk.set(Vector3::zeros(), block);
+                let mut mesh = ChunkMesh::default();
+                mesh.append(
+                    build_mesh(&chunk, Vector3::zeros(), 1),
+                    Vector3::repeat(-0.5),
+                );
+                mesh.scale(ITEM_SIZE);
+                SurfaceSharedData::new(mesh.into_surface_data())
+            })
+            .clone();
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(surface).build()])
+            .build(graph)
+    }
+
+    /// Moves the nodes to the items, adding and removing nodes as items come and go.
+    pub fn update(&mut self, graph: &mut Graph, items: &[DroppedItem]) {
+        while self.nodes.len() > items.len() {
+            if let Some((_, node)) = self.nodes.pop() {
+                graph.remove_node(node);
+            }
+        }
+        for (index, item) in items.iter().enumerate() {
+            let block = item.stack.block;
+            match self.nodes.get(index) {
+                Some((shown, _)) if *shown == block => (),
+                Some((_, node)) => {
+                    graph.remove_node(*node);
+                    self.nodes[index] = (block, self.item_node(graph, block));
+                }
+                None => {
+                    let node = self.item_node(graph, block);
+                    self.nodes.push((block, node));
+                }
+            }
+            let transform = graph[self.nodes[index].1].local_transform_mut();
+            transform.set_position(item.position);
+            transform.set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                item.age * ITEM_SPIN,
+            ));
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::sync::Mutex;
+
+use fyrox::core::algebra::{Vector2, Vector3};
+
+use crate::chunk::Chunk;
+use crate::inventory::Inventory;
+use crate::logging::{self, Category};
+use crate::region::{region_position, CorruptedRecord, Region};
+use crate::storage::Storage;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Corrupted(String),
+    UnsupportedVersion(u32),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "io error: {error}"),
+            SaveError::Corrupted(reason) => write!(f, "corrupted save: {reason}"),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "save version {version} is not supported")
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+/// Reads little endian values from a byte slice.
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    pub fn bytes(&mut self, count: usize) -> Result<&'a [u8], SaveError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| SaveError::Corrupted("unexpected end of data".to_string()))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    /// Everything that was not read yet.
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = self.bytes.get(self.position..).unwrap_or_default();
+        self.position = self.bytes.len();
+        rest
+    }
+
+    pub fn u8(&mut self) -> Result<u8, SaveError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, SaveError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, SaveError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, SaveError> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn vector3(&mut self) -> Result<Vector3<f32>, SaveError> {
+        Ok(Vector3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+}
+
+pub fn write_vector3(bytes: &mut Vec<u8>, vector: &Vector3<f32>) {
+    for value in vector.iter() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// CRC-32 of the bytes, the same one zip and png use.
+pub fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Region of a `r.<x>.<z>.region` key.
+fn parse_region_name(name: &str) -> Option<Vector2<i32>> {
+    let (x, z) = name
+        .strip_prefix("r.")?
+        .strip_suffix(".region")?
+        .split_once('.')?;
+    Some(Vector2::new(x.parse().ok()?, z.parse().ok()?))
+}
+
+/// What the startup check of a save found and fixed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Leftovers of saves that were interrupted, see `Storage::recover`.
+    pub removed_temporary: usize,
+    pub quarantined_records: usize,
+    /// Region files whose header could not be read at all.
+    pub quarantined_regions: usize,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+const METADATA_MAGIC: &[u8; 4] = b"M64W";
+
+/// Bump when the layout changes and teach `WorldMetadata::decode` to read the old one.
+pub const METADATA_VERSION: u32 = 2;
+
+/// Everything about a world that is not stored in chunks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldMetadata {
+    pub seed: u32,
+    pub spawn: Vector3<f32>,
+    /// Seconds the world was played for.
+    pub time: f32,
+    pub player_position: Vector3<f32>,
+    pub inventory: Inventory,
+}
+
+impl WorldMetadata {
+    pub fn new(seed: u32, spawn: Vector3<f32>) -> Self {
+        Self {
+            seed,
+            spawn,
+            time: 0.0,
+            player_position: spawn,
+            inventory: Inventory::default(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = METADATA_MAGIC.to_vec();
+        bytes.extend_from_slice(&METADATA_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        write_vector3(&mut bytes, &self.spawn);
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        write_vector3(&mut bytes, &self.player_position);
+        self.inventory.encode(&mut bytes);
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SaveError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(METADATA_MAGIC.len())? != METADATA_MAGIC {
+            return Err(SaveError::Corrupted(
+                "not a world metadata file".to_string(),
+            ));
+        }
+        let version = reader.u32()?;
+        if !(1..=METADATA_VERSION).contains(&version) {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        Ok(Self {
+            seed: reader.u32()?,
+            spawn: reader.vector3()?,
+            time: reader.f32()?,
+            player_position: reader.vector3()?,
+            // Worlds of version 1 start with an empty inventory.
+            inventory: if version >= 2 {
+                Inventory::decode(&mut reader)?
+            } else {
+                Inventory::default()
+            },
+        })
+    }
+}
+
+/// World saved in a storage: `world.meta` with the metadata and `r.<x>.<z>.region` with modified
+/// chunks. Chunks that were never modified are not saved, they are generated again from the seed.
+/// Damaged data is moved under `quarantine/` instead of being deleted.
+///
+/// Shared with the chunk loader threads, read regions are cached.
+pub struct WorldSave {
+    storage: Box<dyn Storage>,
+    regions: Mutex<HashMap<Vector2<i32>, Region>>,
+}
+
+pub(crate) const METADATA_KEY: &str = "world.meta";
+
+fn region_key(region: Vector2<i32>) -> String {
+    format!("r.{}.{}.region", region.x, region.y)
+}
+
+impl WorldSave {
+    pub fn new(storage: Box<dyn Storage>) -> Self {
+        Self {
+            storage,
+            regions: Default::default(),
+        }
+    }
+
+    /// Stores the bytes under `quarantine/<name>`, or `quarantine/<name>.<n>` when something was
+    /// quarantined under that name already.
+    fn quarantine(&self, name: &str, bytes: &[u8]) -> Result<(), SaveError> {
+        let mut key = format!("quarantine/{name}");
+        let mut copy = 0;
+        while self.storage.read(&key)?.is_some() {
+            copy += 1;
+            key = format!("quarantine/{name}.{copy}");
+        }
+        self.storage.write(&key, bytes)?;
+        Ok(())
+    }
+
+    fn quarantine_records(
+        &self,
+        position: Vector2<i32>,
+        records: Vec<CorruptedRecord>,
+    ) -> Result<(), SaveError> {
+        for record in records {
+            logging::warn(
+                Category::World,
+                format!(
+                    "Chunk record {} of region {:?} is corrupted and was quarantined",
+                    record.slot, position
+                ),
+            );
+            self.quarantine(
+                &format!("r.{}.{}.{}.record", position.x, position.y, record.slot),
+                &record.bytes,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes leftovers of interrupted saves and quarantines regions and chunk records that are
+    /// damaged, so the rest of the world can still be loaded. Meant to run once before any chunk
+    /// is loaded.
+    pub fn check_integrity(&self) -> Result<IntegrityReport, SaveError> {
+        let mut report = IntegrityReport {
+            removed_temporary: self.storage.recover()?,
+            ..Default::default()
+        };
+        for key in self.storage.keys()? {
+            let Some(position) = parse_region_name(&key) else {
+                continue;
+            };
+            let Some(bytes) = self.storage.read(&key)? else {
+                continue;
+            };
+            match Region::read(&bytes) {
+                Ok(mut region) => {
+                    region.validate(position);
+                    let corrupted = region.take_corrupted();
+                    if !corrupted.is_empty() {
+                        report.quarantined_records += corrupted.len();
+                        self.quarantine_records(position, corrupted)?;
+                        self.storage.write(&key, &region.write())?;
+                    }
+                    self.regions.lock().unwrap().insert(position, region);
+                }
+                Err(error) => {
+                    logging::warn(
+                        Category::World,
+                        format!("Region {position:?} is unreadable and was quarantined: {error}"),
+                    );
+                    self.quarantine(&key, &bytes)?;
+                    self.storage.remove(&key)?;
+                    report.quarantined_regions += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Metadata of the world, `None` when the world was never saved.
+    pub fn load_metadata(&self) -> Result<Option<WorldMetadata>, SaveError> {
+        match self.storage.read(METADATA_KEY)? {
+            Some(bytes) => WorldMetadata::decode(&bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save_metadata(&self, metadata: &WorldMetadata) -> Result<(), SaveError> {
+        self.storage.write(METADATA_KEY, &metadata.encode())?;
+        Ok(())
+    }
+
+    fn with_region<R>(
+        &self,
+        position: Vector2<i32>,
+        func: impl FnOnce(&mut Region) -> R,
+    ) -> Result<R, SaveError> {
+        let mut regions = self.regions.lock().unwrap();
+        let region = match regions.entry(position) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut region = match self.storage.read(&region_key(position))? {
+                    Some(bytes) => Region::read(&bytes)?,
+                    None => Region::default(),
+                };
+                self.quarantine_records(position, region.take_corrupted())?;
+                entry.insert(region)
+            }
+        };
+        Ok(func(region))
+    }
+
+    /// Saved version of the chunk, `None` when it was never modified.
+    pub fn load_chunk(&self, position: Vector3<i32>) -> Result<Option<Chunk>, SaveError> {
+        self.with_region(region_position(position), |region| region.chunk(position))?
+    }
+
+    /// Writes dirty chunks into their regions, chunks that did not change since they were last
+    /// saved are skipped. Does not clear the dirty flags, that is up to the caller once this
+    /// succeeds.
+    pub fn save_chunks<'a>(
+        &self,
+        chunks: impl IntoIterator<Item = &'a Chunk>,
+    ) -> Result<(), SaveError> {
+        let mut by_region: HashMap<Vector2<i32>, Vec<&Chunk>> = HashMap::new();
+        for chunk in chunks.into_iter().filter(|chunk| chunk.is_dirty()) {
+            by_region
+                .entry(region_position(chunk.position()))
+                .or_default()
+                .push(chunk);
+        }
+        for (position, chunks) in by_region {
+            let bytes = self.with_region(position, |region| {
+                for chunk in chunks {
+                    if !region.insert(chunk) {
+                        logging::warn(
+                            Category::World,
+                            format!(
+                                "Chunk {:?} is outside of the world height and was not saved",
+                                chunk.position()
+                            ),
+                        );
+                    }
+                }
+                region.write()
+            })?;
+            self.storage.write(&region_key(position), &bytes)?;
+        }
+        Ok(())
+    }
+}
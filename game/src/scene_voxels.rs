@@ -0,0 +1,45 @@
+//! Blocks painted in a scene with the editor, kept as a schematic next to the scene file.
+use std::path::{Path, PathBuf};
+
+use fyrox::core::algebra::Vector3;
+use fyrox::core::pool::Handle;
+use fyrox::scene::base::BaseBuilder;
+use fyrox::scene::graph::Graph;
+use fyrox::scene::mesh::surface::{SurfaceBuilder, SurfaceSharedData};
+use fyrox::scene::mesh::MeshBuilder;
+use fyrox::scene::node::Node;
+
+use crate::export::build_region_mesh;
+use crate::schematic::{Schematic, SCHEMATIC_EXTENSION};
+
+/// Blocks on every side of the volume painted in a scene.
+pub const VOLUME_SIZE: i32 = 64;
+
+/// File with the voxels of the scene at `scene_path`.
+pub fn voxels_path(scene_path: &Path) -> PathBuf {
+    scene_path.with_extension(SCHEMATIC_EXTENSION)
+}
+
+/// Voxels of a scene that has none painted yet.
+pub fn empty_volume() -> Schematic {
+    Schematic::new(Vector3::repeat(VOLUME_SIZE))
+}
+
+/// Adds the mesh of the voxels to the graph with the block at the origin of the schematic at the
+/// origin of the scene, `Handle::NONE` when there is no block to show.
+pub fn build_voxel_mesh(graph: &mut Graph, schematic: &Schematic, name: &str) -> Handle<Node> {
+    let size = schematic.size();
+    if size.iter().any(|v| *v == 0) {
+        return Handle::NONE;
+    }
+    let mesh = build_region_mesh(schematic, Vector3::zeros(), size.add_scalar(-1));
+    if mesh.is_empty() {
+        return Handle::NONE;
+    }
+    MeshBuilder::new(BaseBuilder::new().with_name(name))
+        .with_surfaces(vec![SurfaceBuilder::new(SurfaceSharedData::new(
+            mesh.into_surface_data(),
+        ))
+        .build()])
+        .build(graph)
+}
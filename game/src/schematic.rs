@@ -0,0 +1,369 @@
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::chunk::BlockSource;
+use crate::command::{
+    check_loaded, Argument, Arguments, Command, CommandContext, CommandEffect, CommandError,
+    CommandRegistry, MAX_FILL_VOLUME,
+};
+use crate::region::{decode_blocks, encode_blocks};
+use crate::save::{ByteReader, SaveError};
+use crate::storage;
+
+const MAGIC: &[u8; 4] = b"M64S";
+
+/// Bump when the layout changes and teach `Schematic::decode` to read the old one.
+pub const SCHEMATIC_VERSION: u32 = 1;
+
+/// Extension of schematic files.
+pub const SCHEMATIC_EXTENSION: &str = "m64s";
+
+/// Where the schematic command saves schematics and looks for ones to paste.
+const SCHEMATICS_DIRECTORY: &str = "schematics";
+
+const VOX_MAGIC: &[u8; 4] = b"VOX ";
+
+/// How a schematic is turned when it is pasted. Mirroring along x happens before rotating.
+///
+/// Blocks have no facing yet, so only their positions change, a stair keeps pointing the same way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Placement {
+    /// Quarter turns around the y axis, from x towards z.
+    pub quarter_turns: u8,
+    pub mirrored: bool,
+}
+
+impl Placement {
+    /// One of the eight placements picked by a value in `0.0..1.0`.
+    pub fn from_variation(variation: f32) -> Self {
+        let index = (variation * 8.0) as u8;
+        Self {
+            quarter_turns: index % 4,
+            mirrored: index >= 4,
+        }
+    }
+}
+
+/// Number of blocks in a box of the size, `None` when it is too big for the `i32` indices of a
+/// schematic.
+fn block_count(size: Vector3<i32>) -> Option<usize> {
+    let count = (size.x as usize)
+        .checked_mul(size.y as usize)?
+        .checked_mul(size.z as usize)?;
+    (count <= i32::MAX as usize).then_some(count)
+}
+
+/// Box of blocks that can be saved to a file and pasted back into the world.
+///
+/// Air in a schematic stands for "leave the world as it is", so prefabs can be pasted into the
+/// terrain without carving out their whole bounding box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    size: Vector3<i32>,
+    /// x changes fastest, then z, then y, like in chunks.
+    blocks: Vec<Block>,
+}
+
+impl Schematic {
+    /// Schematic full of air.
+    ///
+    /// # Panics
+    ///
+    /// When it would have more than `i32::MAX` blocks.
+    pub fn new(size: Vector3<i32>) -> Self {
+        let size = size.map(|v| v.max(0));
+        let count = block_count(size).expect("schematic is too big");
+        Self {
+            size,
+            blocks: vec![Block::Air; count],
+        }
+    }
+
+    /// Copies the blocks of the box between two opposite corners, both included.
+    pub fn copy(source: &impl BlockSource, a: Vector3<i32>, b: Vector3<i32>) -> Self {
+        let min = a.inf(&b);
+        let mut schematic = Self::new(a.sup(&b) - min + Vector3::repeat(1));
+        for index in 0..schematic.blocks.len() {
+            schematic.blocks[index] = source.block(min + schematic.local(index));
+        }
+        schematic
+    }
+
+    pub fn size(&self) -> Vector3<i32> {
+        self.size
+    }
+
+    fn index(&self, local: Vector3<i32>) -> Option<usize> {
+        if (0..self.size.x).contains(&local.x)
+            && (0..self.size.y).contains(&local.y)
+            && (0..self.size.z).contains(&local.z)
+        {
+            Some((local.x + (local.z + local.y * self.size.z) * self.size.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn local(&self, index: usize) -> Vector3<i32> {
+        let index = index as i32;
+        let layer = self.size.x * self.size.z;
+        Vector3::new(
+            index % self.size.x,
+            index / layer,
+            index % layer / self.size.x,
+        )
+    }
+
+    /// Block at local coordinates, or air when outside of the schematic.
+    pub fn get(&self, local: Vector3<i32>) -> Block {
+        self.index(local)
+            .map_or(Block::Air, |index| self.blocks[index])
+    }
+
+    pub fn set(&mut self, local: Vector3<i32>, block: Block) {
+        if let Some(index) = self.index(local) {
+            self.blocks[index] = block;
+        }
+    }
+
+    /// Size of the box the schematic covers once placed.
+    pub fn placed_size(&self, placement: Placement) -> Vector3<i32> {
+        if placement.quarter_turns % 2 == 1 {
+            Vector3::new(self.size.z, self.size.y, self.size.x)
+        } else {
+            self.size
+        }
+    }
+
+    /// Where a local position ends up inside of the placed box.
+    fn place(&self, local: Vector3<i32>, placement: Placement) -> Vector3<i32> {
+        let mut size = self.size;
+        let mut position = local;
+        if placement.mirrored {
+            position.x = size.x - 1 - position.x;
+        }
+        for _ in 0..placement.quarter_turns % 4 {
+            position = Vector3::new(size.z - 1 - position.z, position.y, position.x);
+            size = Vector3::new(size.z, size.y, size.x);
+        }
+        position
+    }
+
+    /// World positions and blocks of the schematic placed with its minimum corner at `origin`.
+    /// Air is left out.
+    pub fn blocks(
+        &self,
+        origin: Vector3<i32>,
+        placement: Placement,
+    ) -> impl Iterator<Item = (Vector3<i32>, Block)> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| !block.is_air())
+            .map(move |(index, block)| (origin + self.place(self.local(index), placement), *block))
+    }
+
+    /// Magic, version, size as three `u16` and the blocks compressed like chunk records.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+        for value in self.size.iter() {
+            bytes.extend_from_slice(&(*value as u16).to_le_bytes());
+        }
+        bytes.extend(encode_blocks(&self.blocks));
+        bytes
+    }
+
+    /// Reads our own format or a MagicaVoxel file, whichever the bytes turn out to be.
+    pub fn decode(bytes: &[u8]) -> Result<Self, SaveError> {
+        if bytes.starts_with(VOX_MAGIC) {
+            return Self::from_vox(bytes);
+        }
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(SaveError::Corrupted("not a schematic".to_string()));
+        }
+        match reader.u32()? {
+            SCHEMATIC_VERSION => (),
+            version => return Err(SaveError::UnsupportedVersion(version)),
+        }
+        let size = Vector3::new(
+            reader.u16()? as i32,
+            reader.u16()? as i32,
+            reader.u16()? as i32,
+        );
+        let count = block_count(size).ok_or_else(|| {
+            SaveError::Corrupted(format!("schematic of size {size:?} is too big"))
+        })?;
+        let blocks = decode_blocks(reader.rest())?;
+        if blocks.len() != count {
+            return Err(SaveError::Corrupted(format!(
+                "wrong number of blocks in schematic of size {size:?}"
+            )));
+        }
+        Ok(Self { size, blocks })
+    }
+
+    /// Reads the first model of a MagicaVoxel `.vox` file.
+    ///
+    /// Palette index `n` is the block with id `n`, so artists pick blocks by palette slot and can
+    /// color the slots however they like. MagicaVoxel has z pointing up, it becomes our y.
+    pub fn from_vox(bytes: &[u8]) -> Result<Self, SaveError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(VOX_MAGIC.len())? != VOX_MAGIC {
+            return Err(SaveError::Corrupted("not a vox file".to_string()));
+        }
+        reader.u32()?;
+        if reader.bytes(4)? != b"MAIN" {
+            return Err(SaveError::Corrupted(
+                "vox file has no main chunk".to_string(),
+            ));
+        }
+        let content = reader.u32()? as usize;
+        reader.u32()?;
+        reader.bytes(content)?;
+        let mut schematic = None;
+        // Children of the main chunk follow it, later models and the scene graph are skipped.
+        while !reader.is_empty() {
+            let id = reader.bytes(4)?;
+            let (content, children) = (reader.u32()? as usize, reader.u32()? as usize);
+            let mut content = ByteReader::new(reader.bytes(content)?);
+            reader.bytes(children)?;
+            match id {
+                b"SIZE" if schematic.is_none() => {
+                    let (x, y, z) = (content.u32()?, content.u32()?, content.u32()?);
+                    if [x, y, z].iter().any(|value| *value > 256) {
+                        return Err(SaveError::Corrupted(format!("vox model is {x}x{y}x{z}")));
+                    }
+                    schematic = Some(Self::new(Vector3::new(x as i32, z as i32, y as i32)));
+                }
+                b"XYZI" => {
+                    let Some(schematic) = schematic.as_mut() else {
+                        return Err(SaveError::Corrupted(
+                            "vox voxels come before size".to_string(),
+                        ));
+                    };
+                    for _ in 0..content.u32()? {
+                        let voxel = content.bytes(4)?;
+                        let block = Block::from_id(voxel[3]).ok_or_else(|| {
+                            SaveError::Corrupted(format!("no block for palette index {}", voxel[3]))
+                        })?;
+                        let local = Vector3::new(voxel[0] as i32, voxel[2] as i32, voxel[1] as i32);
+                        schematic.set(local, block);
+                    }
+                    break;
+                }
+                _ => (),
+            }
+        }
+        schematic.ok_or_else(|| SaveError::Corrupted("vox file has no model".to_string()))
+    }
+}
+
+/// Local coordinates, air outside of the schematic.
+impl BlockSource for Schematic {
+    fn block(&self, position: Vector3<i32>) -> Block {
+        self.get(position)
+    }
+}
+
+/// Saves the blocks of a box as a schematic, or pastes a saved one with its minimum corner at a
+/// position. MagicaVoxel files put into the schematics directory can be pasted as well.
+fn schematic(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(5..=8)?;
+    let name = arguments.get(1)?;
+    if name.is_empty() || name.contains(['/', '\\', '.']) {
+        return Err(CommandError::Failed(format!(
+            "invalid schematic name {name}"
+        )));
+    }
+    let storage = storage::open(SCHEMATICS_DIRECTORY)
+        .map_err(|error| CommandError::Failed(format!("schematics can't be opened: {error}")))?;
+    let origin = context.simulation.player().position();
+    match arguments.get(0)? {
+        "save" => {
+            arguments.expect_count(8..=8)?;
+            let a = arguments.block_position(2, origin)?;
+            let b = arguments.block_position(5, origin)?;
+            let (min, max) = (a.inf(&b), a.sup(&b));
+            let size = (max - min).add_scalar(1).cast::<i64>();
+            let volume = size.x * size.y * size.z;
+            if volume > MAX_FILL_VOLUME {
+                return Err(CommandError::Failed(format!(
+                    "{volume} blocks are too many, at most {MAX_FILL_VOLUME} can be saved"
+                )));
+            }
+            check_loaded(context.simulation, min, max)?;
+            let file = format!("{name}.{SCHEMATIC_EXTENSION}");
+            let schematic = Schematic::copy(context.simulation.world(), min, max);
+            storage.write(&file, &schematic.encode()).map_err(|error| {
+                CommandError::Failed(format!("failed to write {file}: {error}"))
+            })?;
+            Ok(format!(
+                "Saved {volume} blocks to {SCHEMATICS_DIRECTORY}/{file}"
+            ))
+        }
+        "paste" => {
+            arguments.expect_count(5..=7)?;
+            let position = arguments.block_position(2, origin)?;
+            let quarter_turns: u8 = if arguments.len() > 5 {
+                arguments.parse(5)?
+            } else {
+                0
+            };
+            let mirrored = match arguments.get(6) {
+                Ok("mirror") => true,
+                Ok(_) => return Err(arguments.usage_error()),
+                Err(_) => false,
+            };
+            let mut bytes = None;
+            for file in [
+                format!("{name}.{SCHEMATIC_EXTENSION}"),
+                format!("{name}.vox"),
+            ] {
+                bytes = storage.read(&file).map_err(|error| {
+                    CommandError::Failed(format!("failed to read {file}: {error}"))
+                })?;
+                if bytes.is_some() {
+                    break;
+                }
+            }
+            let bytes = bytes.ok_or_else(|| {
+                CommandError::Failed(format!("no schematic {name} in {SCHEMATICS_DIRECTORY}"))
+            })?;
+            let schematic = Schematic::decode(&bytes)
+                .map_err(|error| CommandError::Failed(format!("failed to read {name}: {error}")))?;
+            let placement = Placement {
+                quarter_turns: quarter_turns % 4,
+                mirrored,
+            };
+            let size = schematic.placed_size(placement);
+            if size.iter().any(|value| *value == 0) {
+                return Ok(format!("{name} is empty"));
+            }
+            let max = position + size.add_scalar(-1);
+            check_loaded(context.simulation, position, max)?;
+            for (block_position, block) in schematic.blocks(position, placement) {
+                context.simulation.set_block(block_position, block);
+            }
+            context
+                .effects
+                .push(CommandEffect::BlocksChanged { min: position, max });
+            Ok(format!(
+                "Pasted {name} at {} {} {}",
+                position.x, position.y, position.z
+            ))
+        }
+        _ => Err(arguments.usage_error()),
+    }
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register(Command {
+        name: "schematic",
+        usage: "schematic save <name> <x1> <y1> <z1> <x2> <y2> <z2> | paste <name> <x> <y> <z> [turns] [mirror]",
+        help: "saves the blocks of a box to the schematics directory or pastes a saved schematic",
+        arguments: &[Argument::Choice(&["paste", "save"])],
+        run: schematic,
+    });
+}
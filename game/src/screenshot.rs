@@ -0,0 +1,193 @@
+//! Screenshots of what the player sees. They are traced through the blocks of the world rather
+//! than read back from the renderer, which gives plugins no access to the frames it draws.
+use fyrox::core::algebra::{UnitQuaternion, Vector3};
+
+use crate::algorithm::raycast;
+use crate::chunk::{BlockSource, CHUNK_SIZE};
+use crate::command::{Argument, Arguments, Command, CommandContext, CommandError, CommandRegistry};
+use crate::direction::Direction;
+use crate::save::checksum;
+use crate::settings::load_settings;
+use crate::storage;
+
+/// Where the screenshot command saves screenshots.
+pub const SCREENSHOTS_DIRECTORY: &str = "screenshots";
+
+const DEFAULT_SIZE: (u32, u32) = (640, 360);
+const MAX_SIZE: (u32, u32) = (3840, 2160);
+
+/// Color of rays that hit no block.
+const SKY_COLOR: [u8; 3] = [150, 200, 240];
+
+/// Bytes a stored deflate block holds at most.
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+/// Picture with rows from the top, pixels as RGB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+/// Light on a face by the direction it faces, so the sides of blocks stand apart.
+fn face_light(face: Direction) -> f32 {
+    match face {
+        Direction::Top => 1.0,
+        Direction::Bottom => 0.5,
+        Direction::Left | Direction::Right => 0.8,
+        Direction::Front | Direction::Back => 0.65,
+    }
+}
+
+/// Traces a ray for every pixel from the eye, with the rotation and vertical field of view of a
+/// camera. Blocks farther than `max_distance` fade into the sky.
+pub fn render(
+    source: &impl BlockSource,
+    eye: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+    fov_degrees: f32,
+    (width, height): (u32, u32),
+    max_distance: f32,
+) -> Image {
+    let half_height = (fov_degrees.to_radians() / 2.0).tan();
+    let half_width = half_height * width as f32 / height as f32;
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            // The camera looks along its z with x to its left.
+            let right = (2.0 * (x as f32 + 0.5) / width as f32 - 1.0) * half_width;
+            let up = (1.0 - 2.0 * (y as f32 + 0.5) / height as f32) * half_height;
+            let direction = rotation * Vector3::new(-right, up, 1.0);
+            let pixel = match raycast(source, eye, direction, max_distance) {
+                Some(hit) => {
+                    let color = source.block(hit.block).color();
+                    let light = face_light(hit.face);
+                    let fog = (hit.distance / max_distance).powi(2);
+                    std::array::from_fn(|channel| {
+                        let lit = color[channel] as f32 * light;
+                        (lit + (SKY_COLOR[channel] as f32 - lit) * fog).round() as u8
+                    })
+                }
+                None => SKY_COLOR,
+            };
+            pixels.push(pixel);
+        }
+    }
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Adler-32 of the bytes, the checksum that ends zlib streams.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = checksum(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// PNG of the image, stored without compression so it needs no deflate encoder.
+pub fn encode_png(image: &Image) -> Vec<u8> {
+    let mut rows = Vec::with_capacity(image.pixels.len() * 3 + image.height as usize);
+    for row in image.pixels.chunks(image.width.max(1) as usize) {
+        // No filter.
+        rows.push(0);
+        rows.extend(row.iter().flatten());
+    }
+    // Deflate streams need a block even when there is nothing to store.
+    let blocks: Vec<&[u8]> = if rows.is_empty() {
+        vec![&[]]
+    } else {
+        rows.chunks(MAX_STORED_BLOCK).collect()
+    };
+    let mut zlib = vec![0x78, 0x01];
+    for (index, block) in blocks.iter().enumerate() {
+        // The first bit marks the last block, the two after it are 0 for stored blocks.
+        zlib.push((index + 1 == blocks.len()) as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&rows).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    // 8 bits per channel of RGB, deflate, no filters beyond the per row ones, not interlaced.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib);
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Saves what the player sees as a PNG, at a size that defaults to 640 by 360.
+fn screenshot(context: &mut CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    arguments.expect_count(0..=2)?;
+    let size = match arguments.len() {
+        0 => DEFAULT_SIZE,
+        1 => return Err(arguments.usage_error()),
+        _ => (arguments.parse(0)?, arguments.parse(1)?),
+    };
+    if !(1..=MAX_SIZE.0).contains(&size.0) || !(1..=MAX_SIZE.1).contains(&size.1) {
+        return Err(CommandError::Failed(format!(
+            "screenshots are {} by {} at most",
+            MAX_SIZE.0, MAX_SIZE.1
+        )));
+    }
+    let simulation = &context.simulation;
+    let player = simulation.player();
+    let image = render(
+        simulation.world(),
+        player.position(),
+        player.rotation(),
+        load_settings().fov,
+        size,
+        (simulation.view_distance() * CHUNK_SIZE) as f32,
+    );
+    let storage = storage::open(SCREENSHOTS_DIRECTORY)
+        .map_err(|error| CommandError::Failed(format!("screenshots can't be opened: {error}")))?;
+    let taken = storage
+        .keys()
+        .map_err(|error| CommandError::Failed(format!("screenshots can't be listed: {error}")))?;
+    let name = (1..)
+        .map(|number| format!("screenshot-{number}.png"))
+        .find(|name| !taken.contains(name))
+        .expect("screenshot numbers never run out");
+    storage
+        .write(&name, &encode_png(&image))
+        .map_err(|error| CommandError::Failed(format!("failed to write {name}: {error}")))?;
+    Ok(format!("Saved {SCREENSHOTS_DIRECTORY}/{name}"))
+}
+
+pub fn register_commands(registry: &mut CommandRegistry) {
+    registry.register(Command {
+        name: "screenshot",
+        usage: "screenshot [width height]",
+        help: "saves what the player sees to the screenshots directory",
+        arguments: &[Argument::Any, Argument::Any],
+        run: screenshot,
+    });
+}
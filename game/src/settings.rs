@@ -0,0 +1,560 @@
+use std::fmt::{Display, Formatter};
+
+use fyrox::engine::GraphicsContextParams;
+use fyrox::keyboard::KeyCode;
+use fyrox::window::{Fullscreen, WindowAttributes};
+use serde::{Deserialize, Serialize};
+
+use crate::chunk_loader::VIEW_DISTANCE;
+use crate::logging::{self, Category};
+use crate::storage::{self, Storage};
+
+/// Directory of the settings of the player, the `settings.ron` at the root belongs to the editor.
+pub const SETTINGS_DIRECTORY: &str = "config";
+
+const SETTINGS_KEY: &str = "settings.ron";
+
+const WINDOW_TITLE: &str = "Mine64";
+
+/// View distances the settings step through, in chunks.
+const MIN_VIEW_DISTANCE: i32 = 4;
+const MAX_VIEW_DISTANCE: i32 = 64;
+const VIEW_DISTANCE_STEP: i32 = 4;
+
+/// Vertical fields of view in degrees.
+const MIN_FOV: f32 = 50.0;
+const MAX_FOV: f32 = 110.0;
+const FOV_STEP: f32 = 5.0;
+
+const MIN_MOUSE_SENSITIVITY: f32 = 0.1;
+const MAX_MOUSE_SENSITIVITY: f32 = 3.0;
+const MOUSE_SENSITIVITY_STEP: f32 = 0.1;
+
+/// Frame caps in frames per second, stepping past the highest one leaves frames uncapped.
+const MIN_MAX_FPS: u32 = 30;
+const MAX_MAX_FPS: u32 = 240;
+const MAX_FPS_STEP: u32 = 30;
+
+/// Keys that can be bound to actions. The number keys 1 to 9 select hotbar slots and F4 to F8
+/// toggle debug drawing, so those are left out.
+pub const BINDABLE_KEYS: [KeyCode; 61] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Backspace,
+    KeyCode::CapsLock,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Backquote,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Insert,
+];
+
+/// Keys the open console uses itself, to run, complete and browse the typed lines. The console
+/// can't be bound to them.
+const CONSOLE_KEYS: [KeyCode; 4] = [
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+];
+
+/// Name a key is written with in the settings file, like `KeyW`.
+pub fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+/// Character the key types on a US layout without shift, `None` for keys that type none.
+pub fn key_character(key: KeyCode) -> Option<char> {
+    let name = key_name(key);
+    if let Some(character) = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+    {
+        return character.to_lowercase().chars().next();
+    }
+    match key {
+        KeyCode::Space => Some(' '),
+        KeyCode::Backquote => Some('`'),
+        KeyCode::Minus => Some('-'),
+        KeyCode::Equal => Some('='),
+        KeyCode::BracketLeft => Some('['),
+        KeyCode::BracketRight => Some(']'),
+        KeyCode::Backslash => Some('\\'),
+        KeyCode::Semicolon => Some(';'),
+        KeyCode::Quote => Some('\''),
+        KeyCode::Comma => Some(','),
+        KeyCode::Period => Some('.'),
+        KeyCode::Slash => Some('/'),
+        _ => None,
+    }
+}
+
+/// Text of the console input without the character the key typed into it, when the key opened
+/// the console. `None` when the text has no such character.
+pub fn strip_key_character(text: &str, key: KeyCode) -> Option<String> {
+    let character = key_character(key)?;
+    let index = text.find(|typed: char| typed.eq_ignore_ascii_case(&character))?;
+    let mut text = text.to_string();
+    text.remove(index);
+    Some(text)
+}
+
+pub fn key_by_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| key_name(*key) == name)
+}
+
+/// Keys are written to the settings file by their names.
+mod serde_key {
+    use fyrox::keyboard::KeyCode;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{key_by_name, key_name};
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key_name(*key))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        key_by_name(&name).ok_or_else(|| D::Error::custom(format!("unknown key {name}")))
+    }
+}
+
+/// What a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+    Console,
+    DebugOverlay,
+    /// Writes meshes of the blocks around the player.
+    Export,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Forward,
+        Action::Backward,
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Console,
+        Action::DebugOverlay,
+        Action::Export,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Forward => "Forward",
+            Action::Backward => "Backward",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Console => "Console",
+            Action::DebugOverlay => "Debug overlay",
+            Action::Export => "Export area",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(with = "serde_key")]
+    pub forward: KeyCode,
+    #[serde(with = "serde_key")]
+    pub backward: KeyCode,
+    #[serde(with = "serde_key")]
+    pub left: KeyCode,
+    #[serde(with = "serde_key")]
+    pub right: KeyCode,
+    #[serde(with = "serde_key")]
+    pub up: KeyCode,
+    #[serde(with = "serde_key")]
+    pub down: KeyCode,
+    #[serde(with = "serde_key")]
+    pub console: KeyCode,
+    #[serde(with = "serde_key")]
+    pub debug_overlay: KeyCode,
+    #[serde(with = "serde_key")]
+    pub export: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            backward: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            up: KeyCode::ShiftLeft,
+            down: KeyCode::ControlLeft,
+            console: KeyCode::Backquote,
+            debug_overlay: KeyCode::F3,
+            export: KeyCode::F9,
+        }
+    }
+}
+
+impl KeyBindings {
+    fn key_mut(&mut self, action: Action) -> &mut KeyCode {
+        match action {
+            Action::Forward => &mut self.forward,
+            Action::Backward => &mut self.backward,
+            Action::Left => &mut self.left,
+            Action::Right => &mut self.right,
+            Action::Up => &mut self.up,
+            Action::Down => &mut self.down,
+            Action::Console => &mut self.console,
+            Action::DebugOverlay => &mut self.debug_overlay,
+            Action::Export => &mut self.export,
+        }
+    }
+
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::Forward => self.forward,
+            Action::Backward => self.backward,
+            Action::Left => self.left,
+            Action::Right => self.right,
+            Action::Up => self.up,
+            Action::Down => self.down,
+            Action::Console => self.console,
+            Action::DebugOverlay => self.debug_overlay,
+            Action::Export => self.export,
+        }
+    }
+
+    /// Binds the key to the action. An action the key was bound to before gets the previous key
+    /// of this one, so every key stays bound to one action at most. `false` and nothing changes
+    /// when the console would get a key it uses while it is open.
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> bool {
+        let previous = self.key(action);
+        let other = self.action(key);
+        let console = match (action, other) {
+            (Action::Console, _) => key,
+            (_, Some(Action::Console)) => previous,
+            _ => self.console,
+        };
+        if CONSOLE_KEYS.contains(&console) {
+            return false;
+        }
+        if let Some(other) = other {
+            *self.key_mut(other) = previous;
+        }
+        *self.key_mut(action) = key;
+        true
+    }
+
+    /// Action the key is bound to.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.key(*action) == key)
+    }
+}
+
+/// Something on the settings screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    ViewDistance,
+    Fov,
+    MouseSensitivity,
+    InvertMouse,
+    Vsync,
+    MaxFps,
+    Fullscreen,
+    AmbientOcclusion,
+    Shadows,
+    Binding(Action),
+}
+
+impl Setting {
+    /// Settings that are not key bindings, in the order of the settings screen.
+    pub const OPTIONS: [Setting; 9] = [
+        Setting::ViewDistance,
+        Setting::Fov,
+        Setting::MouseSensitivity,
+        Setting::InvertMouse,
+        Setting::Vsync,
+        Setting::MaxFps,
+        Setting::Fullscreen,
+        Setting::AmbientOcclusion,
+        Setting::Shadows,
+    ];
+
+    /// Settings changed by stepping a value up or down, the others are switched on and off or,
+    /// for bindings, get a key pressed.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Setting::ViewDistance | Setting::Fov | Setting::MouseSensitivity | Setting::MaxFps
+        )
+    }
+}
+
+/// Settings of the player, read from `config/settings.ron`. The executors read the window and
+/// vertical sync settings before the window is created, the game applies the rest while running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Chunks kept loaded around the player, in worlds opened after a change.
+    pub view_distance: i32,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    /// Multiplies how far moving the mouse turns the camera.
+    pub mouse_sensitivity: f32,
+    /// Moving the mouse up looks down.
+    pub invert_mouse: bool,
+    /// Only read when the window is created.
+    pub vsync: bool,
+    /// Frames per second the game runs at most, 0 for no cap. Browsers cap frames themselves.
+    pub max_fps: u32,
+    pub fullscreen: bool,
+    pub ambient_occlusion: bool,
+    pub shadows: bool,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            view_distance: VIEW_DISTANCE,
+            fov: 75.0,
+            mouse_sensitivity: 1.0,
+            invert_mouse: false,
+            vsync: true,
+            max_fps: 0,
+            fullscreen: false,
+            ambient_occlusion: true,
+            shadows: true,
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    Ron(String),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Io(error) => write!(f, "{error}"),
+            SettingsError::Ron(error) => write!(f, "invalid settings: {error}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(error: std::io::Error) -> Self {
+        SettingsError::Io(error)
+    }
+}
+
+impl GameSettings {
+    /// Settings in the storage, defaults when there are none yet.
+    pub fn load(storage: &dyn Storage) -> Result<Self, SettingsError> {
+        let Some(bytes) = storage.read(SETTINGS_KEY)? else {
+            return Ok(Self::default());
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let mut settings: Self =
+            ron::from_str(&text).map_err(|error| SettingsError::Ron(error.to_string()))?;
+        settings.clamp();
+        Ok(settings)
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), SettingsError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| SettingsError::Ron(error.to_string()))?;
+        storage.write(SETTINGS_KEY, text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Keeps values edited by hand in the ranges the settings screen offers.
+    fn clamp(&mut self) {
+        self.view_distance = self
+            .view_distance
+            .clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE);
+        self.fov = self.fov.clamp(MIN_FOV, MAX_FOV);
+        self.mouse_sensitivity = self
+            .mouse_sensitivity
+            .clamp(MIN_MOUSE_SENSITIVITY, MAX_MOUSE_SENSITIVITY);
+        if self.max_fps != 0 {
+            self.max_fps = self.max_fps.clamp(MIN_MAX_FPS, MAX_MAX_FPS);
+        }
+        if CONSOLE_KEYS.contains(&self.key_bindings.console) {
+            let console = KeyBindings::default().console;
+            self.key_bindings.bind(Action::Console, console);
+        }
+    }
+
+    /// Steps a numeric setting up or down, `steps` can be negative. Other settings are switched
+    /// on or off whatever the steps.
+    pub fn step(&mut self, setting: Setting, steps: i32) {
+        match setting {
+            Setting::ViewDistance => self.view_distance += steps * VIEW_DISTANCE_STEP,
+            Setting::Fov => self.fov += steps as f32 * FOV_STEP,
+            Setting::MouseSensitivity => {
+                let sensitivity = self.mouse_sensitivity + steps as f32 * MOUSE_SENSITIVITY_STEP;
+                // Keeps the value a multiple of the step, which sums of floats drift away from.
+                self.mouse_sensitivity =
+                    (sensitivity / MOUSE_SENSITIVITY_STEP).round() * MOUSE_SENSITIVITY_STEP;
+            }
+            Setting::InvertMouse => self.invert_mouse = !self.invert_mouse,
+            Setting::Vsync => self.vsync = !self.vsync,
+            Setting::MaxFps => {
+                // No cap comes after the highest cap.
+                let uncapped = (MAX_MAX_FPS + MAX_FPS_STEP) as i32;
+                let fps = match self.max_fps {
+                    0 => uncapped,
+                    fps => fps as i32,
+                };
+                let fps = (fps + steps * MAX_FPS_STEP as i32).clamp(MIN_MAX_FPS as i32, uncapped);
+                self.max_fps = if fps == uncapped { 0 } else { fps as u32 };
+            }
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::AmbientOcclusion => self.ambient_occlusion = !self.ambient_occlusion,
+            Setting::Shadows => self.shadows = !self.shadows,
+            Setting::Binding(_) => (),
+        }
+        self.clamp();
+    }
+
+    /// Setting with its value, as the settings screen shows it.
+    pub fn label(&self, setting: Setting) -> String {
+        match setting {
+            Setting::ViewDistance => format!("View distance: {} chunks", self.view_distance),
+            Setting::Fov => format!("Field of view: {:.0}", self.fov),
+            Setting::MouseSensitivity => {
+                format!("Mouse sensitivity: {:.1}", self.mouse_sensitivity)
+            }
+            Setting::InvertMouse => format!("Invert mouse: {}", on_off(self.invert_mouse)),
+            Setting::Vsync => format!("Vertical sync: {} (on restart)", on_off(self.vsync)),
+            Setting::MaxFps => match self.max_fps {
+                0 => "Frame cap: off".to_string(),
+                fps => format!("Frame cap: {fps} fps"),
+            },
+            Setting::Fullscreen => format!("Fullscreen: {}", on_off(self.fullscreen)),
+            Setting::AmbientOcclusion => {
+                format!("Ambient occlusion: {}", on_off(self.ambient_occlusion))
+            }
+            Setting::Shadows => format!("Shadows: {}", on_off(self.shadows)),
+            Setting::Binding(action) => format!(
+                "{}: {}",
+                action.name(),
+                key_name(self.key_bindings.key(action))
+            ),
+        }
+    }
+
+    /// Window and vertical sync of the settings, for the executors.
+    pub fn graphics_context_params(&self) -> GraphicsContextParams {
+        GraphicsContextParams {
+            window_attributes: WindowAttributes {
+                title: WINDOW_TITLE.to_string(),
+                resizable: true,
+                fullscreen: self.fullscreen.then_some(Fullscreen::Borderless(None)),
+                ..Default::default()
+            },
+            vsync: self.vsync,
+        }
+    }
+}
+
+/// Settings of the player from the storage of the platform. Settings that can't be read are
+/// logged and replaced by the defaults.
+pub fn load_settings() -> GameSettings {
+    let result = storage::open_small(SETTINGS_DIRECTORY)
+        .map_err(SettingsError::Io)
+        .and_then(|storage| GameSettings::load(storage.as_ref()));
+    result.unwrap_or_else(|error| {
+        logging::error(
+            Category::World,
+            format!("Failed to read the settings, the defaults are used: {error}"),
+        );
+        GameSettings::default()
+    })
+}
+
+pub fn save_settings(settings: &GameSettings) {
+    let result = storage::open_small(SETTINGS_DIRECTORY)
+        .map_err(SettingsError::Io)
+        .and_then(|storage| settings.save(storage.as_ref()));
+    if let Err(error) = result {
+        logging::error(
+            Category::World,
+            format!("Failed to save the settings: {error}"),
+        );
+    }
+}
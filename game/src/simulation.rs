@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use fyrox::core::algebra::Vector3;
+
+use crate::algorithm::{hash, raycast, RayHit};
+use crate::block::Block;
+use crate::chunk::{chunk_position, BlockSource, Chunk};
+use crate::chunk_loader::{ChunkLoader, ChunkRenderData};
+use crate::generation_settings::GenerationSettings;
+use crate::item::{DroppedItem, ItemStack};
+use crate::logging::{self, Category};
+use crate::player::{GameMode, Player, PlayerInput};
+use crate::profiler::{self, Scope};
+use crate::save::{WorldMetadata, WorldSave};
+use crate::storage::Storage;
+use crate::world::World;
+use crate::world_generator::WorldGenerator;
+
+/// Seconds between saves of the changed chunks and the player.
+const AUTOSAVE_INTERVAL: f32 = 60.0;
+
+/// Items closer than this many blocks to the body of the player are collected.
+const PICKUP_DISTANCE: f32 = 1.0;
+
+/// Chunks that came into the world or left it during a step, whatever draws the world follows
+/// them.
+#[derive(Default)]
+pub struct WorldChanges {
+    pub loaded: Vec<ChunkRenderData>,
+    pub unloaded: Vec<Vector3<i32>>,
+    /// Items the player picked up.
+    pub collected: Vec<ItemStack>,
+}
+
+/// Hands the items over to the chunks they lie in, so they are saved with them. Returns items
+/// lying in none of the chunks.
+fn attach_items<'a>(
+    items: Vec<DroppedItem>,
+    chunks: impl IntoIterator<Item = &'a mut Chunk>,
+) -> Vec<DroppedItem> {
+    let mut chunks: HashMap<Vector3<i32>, &mut Chunk> = chunks
+        .into_iter()
+        .map(|chunk| (chunk.position(), chunk))
+        .collect();
+    let mut rest = Vec::new();
+    for item in items {
+        match chunks.get_mut(&item.chunk()) {
+            Some(chunk) => {
+                chunk.add_item(item);
+                chunk.set_modified(true);
+                chunk.set_dirty(true);
+            }
+            None => rest.push(item),
+        }
+    }
+    rest
+}
+
+/// Everything about a world that needs neither a window nor a GPU: chunks with their generation
+/// and saving, block edits, dropped items and the player. The game draws it, tests run it on its own.
+pub struct Simulation {
+    world: World,
+    loader: ChunkLoader,
+    save: Arc<WorldSave>,
+    metadata: WorldMetadata,
+    player: Player,
+    /// Items in loaded chunks, they move into their chunks when those are saved or unloaded.
+    items: Vec<DroppedItem>,
+    autosave_timer: f32,
+}
+
+impl Simulation {
+    /// Opens the world saved in the storage, or starts a new one from `seed` when there is none.
+    /// Chunks `view_distance` chunks around the player are kept loaded.
+    pub fn new(
+        storage: Box<dyn Storage>,
+        seed: u32,
+        settings: GenerationSettings,
+        view_distance: i32,
+    ) -> Self {
+        let save = Arc::new(WorldSave::new(storage));
+        match save.check_integrity() {
+            Ok(report) if report.is_clean() => {}
+            Ok(report) => logging::warn(
+                Category::World,
+                format!("World save was repaired: {report:?}"),
+            ),
+            Err(error) => logging::error(
+                Category::World,
+                format!("Failed to check the world save: {error}"),
+            ),
+        }
+        let metadata = match save.load_metadata() {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                logging::error(
+                    Category::World,
+                    format!("Failed to load world metadata: {error}"),
+                );
+                None
+            }
+        }
+        .unwrap_or_else(|| {
+            let generator = WorldGenerator::new(seed, settings.clone());
+            WorldMetadata::new(seed, generator.spawn())
+        });
+        let generator = WorldGenerator::new(metadata.seed, settings);
+        let mut player = Player::new(metadata.player_position);
+        *player.inventory_mut() = metadata.inventory.clone();
+        Self {
+            world: World::default(),
+            loader: ChunkLoader::new(generator, save.clone(), view_distance),
+            save,
+            player,
+            metadata,
+            items: Vec::new(),
+            autosave_timer: 0.0,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn generator(&self) -> &WorldGenerator {
+        self.loader.generator()
+    }
+
+    pub fn metadata(&self) -> &WorldMetadata {
+        &self.metadata
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn player_mut(&mut self) -> &mut Player {
+        &mut self.player
+    }
+
+    pub fn items(&self) -> &[DroppedItem] {
+        &self.items
+    }
+
+    /// Chunk the player is in, chunks are loaded around it.
+    pub fn center(&self) -> Vector3<i32> {
+        chunk_position(self.player.position().map(|v| v.floor() as i32))
+    }
+
+    /// Whether every chunk around the player is loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.loader.is_idle()
+    }
+
+    /// Chunks closer than this many chunks to the chunk of the player are kept loaded.
+    pub fn view_distance(&self) -> i32 {
+        self.loader.view_distance()
+    }
+
+    /// Chunks around the player that wait to be loaded.
+    pub fn queued_chunks(&self) -> usize {
+        self.loader.queued()
+    }
+
+    /// Chunks that are being generated or read and meshed.
+    pub fn loading_chunks(&self) -> usize {
+        self.loader.loading()
+    }
+
+    /// Moves the player and dropped items, lets the player breathe, loads and unloads chunks
+    /// around the player and saves the world when it is time to. `dt` is in seconds.
+    pub fn step(&mut self, dt: f32, input: &PlayerInput) -> WorldChanges {
+        self.player.step(&self.world, dt, input);
+        let eyes = self.player.position().map(|v| v.floor() as i32);
+        self.player.breathe(dt, self.world.block(eyes).is_liquid());
+        if self.player.is_dead() {
+            logging::info(Category::World, "Player drowned");
+            self.player.respawn(self.metadata.spawn);
+        }
+
+        self.metadata.time += dt;
+        self.metadata.player_position = self.player.position();
+        self.autosave_timer += dt;
+        if self.autosave_timer >= AUTOSAVE_INTERVAL {
+            self.save();
+        }
+
+        let mut changes = WorldChanges::default();
+        let mut unloaded = Vec::new();
+        for position in self.loader.recenter(self.center(), &self.world) {
+            unloaded.extend(self.world.remove_chunk(position));
+            changes.unloaded.push(position);
+        }
+        if !unloaded.is_empty() {
+            self.items = attach_items(std::mem::take(&mut self.items), unloaded.iter_mut());
+        }
+        self.save_chunks(&unloaded);
+        for loaded in self.loader.update() {
+            let mut chunk = loaded.chunk;
+            self.items.extend(chunk.take_items());
+            self.world.insert_chunk(chunk);
+            changes.loaded.push(loaded.render_data);
+        }
+        changes.collected = self.update_items(dt);
+        changes
+    }
+
+    /// Moves the items, merges those lying together, drops old ones and returns the ones the
+    /// player collected into the inventory.
+    fn update_items(&mut self, dt: f32) -> Vec<ItemStack> {
+        let (body_min, body_max) = self.player.bounding_box();
+        // Chunks items left, their saved records are out of date.
+        let mut left = Vec::new();
+        for item in self.items.iter_mut() {
+            let chunk = item.chunk();
+            item.step(&self.world, dt);
+            if item.chunk() != chunk {
+                left.push(chunk);
+            }
+        }
+
+        let mut collected = Vec::new();
+        let mut index = 0;
+        while index < self.items.len() {
+            let item = &self.items[index];
+            if item.is_expired() {
+                left.push(self.items.swap_remove(index).chunk());
+            } else if item.can_be_collected()
+                && (item.position.sup(&body_min).inf(&body_max) - item.position).norm()
+                    < PICKUP_DISTANCE
+            {
+                let stack = item.stack;
+                let rest = self.player.inventory_mut().add(stack);
+                let taken = stack.count - rest.map_or(0, |rest| rest.count);
+                if taken > 0 {
+                    collected.push(ItemStack::new(stack.block, taken));
+                }
+                match rest {
+                    // Whatever did not fit stays on the ground.
+                    Some(rest) => {
+                        self.items[index].stack = rest;
+                        index += 1;
+                    }
+                    None => left.push(self.items.swap_remove(index).chunk()),
+                }
+            } else if let Some(other) = (0..index).find(|other| self.items[*other].can_merge(item))
+            {
+                let item = self.items.swap_remove(index);
+                left.push(item.chunk());
+                let other = &mut self.items[other];
+                other.stack.count += item.stack.count;
+                other.age = other.age.min(item.age);
+            } else {
+                index += 1;
+            }
+        }
+
+        for position in left {
+            if let Some(chunk) = self.world.chunk_mut(position) {
+                if chunk.is_modified() {
+                    chunk.set_dirty(true);
+                }
+            }
+        }
+        collected
+    }
+
+    /// Changes a block, returns the position of the chunk that changed. `None` when the chunk is
+    /// not loaded.
+    pub fn set_block(&mut self, position: Vector3<i32>, block: Block) -> Option<Vector3<i32>> {
+        self.world.set_block(position, block)
+    }
+
+    /// Breaks the block, it drops as an item unless it is a liquid or the player is in creative.
+    /// Returns the position of the chunk that changed, `None` when there was nothing to break.
+    pub fn break_block(&mut self, position: Vector3<i32>) -> Option<Vector3<i32>> {
+        let block = self.world.block(position);
+        if block.is_air() {
+            return None;
+        }
+        let chunk = self.world.set_block(position, Block::Air)?;
+        if !block.is_liquid() && self.player.game_mode() != GameMode::Creative {
+            // Pops up and to a side that the position picks.
+            let angle = hash(self.metadata.seed, position.x, position.y, position.z)
+                * std::f32::consts::TAU;
+            self.drop_item(
+                ItemStack::new(block, 1),
+                position.cast::<f32>().add_scalar(0.5),
+                Vector3::new(angle.cos() * 1.5, 4.0, angle.sin() * 1.5),
+            );
+        }
+        Some(chunk)
+    }
+
+    /// Places a block of the selected hotbar stack against the face of the hit block. Returns the
+    /// position of the placed block, `None` when nothing is selected or the place is taken.
+    pub fn place_block(&mut self, hit: &RayHit) -> Option<Vector3<i32>> {
+        let position = hit.face.neighbour(hit.block);
+        let replaced = self.world.block(position);
+        if !(replaced.is_air() || replaced.is_liquid())
+            || self.world.chunk(chunk_position(position)).is_none()
+            || self.player.position().map(|v| v.floor() as i32) == position
+        {
+            return None;
+        }
+        let block = if self.player.game_mode() == GameMode::Creative {
+            self.player.inventory().selected_stack()?.block
+        } else {
+            self.player.inventory_mut().take_selected()?
+        };
+        self.world.set_block(position, block);
+        Some(position)
+    }
+
+    /// Sets the seconds the world was played for.
+    pub fn set_time(&mut self, time: f32) {
+        self.metadata.time = time;
+    }
+
+    /// Unloads every chunk, saving the changed ones with their items, so that they are read from
+    /// the save or generated again. Returns the positions of the unloaded chunks.
+    pub fn reload_chunks(&mut self) -> Vec<Vector3<i32>> {
+        let positions: Vec<Vector3<i32>> = self.world.chunks().map(Chunk::position).collect();
+        let mut unloaded: Vec<Chunk> = positions
+            .iter()
+            .filter_map(|position| self.world.remove_chunk(*position))
+            .collect();
+        self.items = attach_items(std::mem::take(&mut self.items), unloaded.iter_mut());
+        self.save_chunks(&unloaded);
+        self.loader.reset();
+        positions
+    }
+
+    /// Puts the items into the world, `velocity` is in blocks per second.
+    pub fn drop_item(&mut self, stack: ItemStack, position: Vector3<f32>, velocity: Vector3<f32>) {
+        self.items.push(DroppedItem::new(stack, position, velocity));
+    }
+
+    /// Block the player looks at, at most `max_distance` blocks away.
+    pub fn target(&self, max_distance: f32) -> Option<RayHit> {
+        let _timer = profiler::time(Scope::Raycast);
+        raycast(
+            &self.world,
+            self.player.position(),
+            self.player.look_vector(),
+            max_distance,
+        )
+    }
+
+    /// Returns whether the chunks were saved.
+    fn save_chunks<'a>(&self, chunks: impl IntoIterator<Item = &'a Chunk>) -> bool {
+        match self.save.save_chunks(chunks) {
+            Ok(()) => true,
+            Err(error) => {
+                logging::error(Category::World, format!("Failed to save chunks: {error}"));
+                false
+            }
+        }
+    }
+
+    /// Saves loaded chunks that changed since the last save, with the items in them, and the
+    /// world metadata.
+    pub fn save(&mut self) {
+        self.autosave_timer = 0.0;
+        attach_items(self.items.clone(), self.world.chunks_mut());
+        if self.save_chunks(self.world.chunks()) {
+            for chunk in self.world.chunks_mut() {
+                chunk.set_dirty(false);
+            }
+        }
+        for chunk in self.world.chunks_mut() {
+            chunk.take_items();
+        }
+        self.metadata.inventory = self.player.inventory().clone();
+        if let Err(error) = self.save.save_metadata(&self.metadata) {
+            logging::error(
+                Category::World,
+                format!("Failed to save world metadata: {error}"),
+            );
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{ErrorKind, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+/// Key value store that saves are kept in. Keys look like relative paths with `/` separators.
+///
+/// Shared with the chunk loader threads.
+pub trait Storage: Send + Sync {
+    /// Stored value, `None` when nothing is stored under the key.
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Replaces the value as a whole, an interrupted write must leave the old value intact.
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+
+    /// Removing a key that is not stored is not an error.
+    fn remove(&self, key: &str) -> io::Result<()>;
+
+    /// Stored keys that have no `/` in them.
+    fn keys(&self) -> io::Result<Vec<String>>;
+
+    /// Distinct parts before the first `/` of keys that have one, like directories of files.
+    fn directories(&self) -> io::Result<Vec<String>>;
+
+    /// Cleans up after writes that were interrupted, returns how many leftovers were removed.
+    fn recover(&self) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+/// Shared storage, so the same storage can be handed to a save more than once.
+impl<S: Storage + ?Sized> Storage for Arc<S> {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        (**self).read(key)
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        (**self).write(key, bytes)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        (**self).remove(key)
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        (**self).keys()
+    }
+
+    fn directories(&self) -> io::Result<Vec<String>> {
+        (**self).directories()
+    }
+
+    fn recover(&self) -> io::Result<usize> {
+        (**self).recover()
+    }
+}
+
+/// Storage of the platform: a directory on desktop, IndexedDB of the browser on wasm.
+///
+/// On wasm [`preload`] has to be done first.
+pub fn open(name: &str) -> io::Result<Box<dyn Storage>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let storage = FileStorage::open(name)?;
+    #[cfg(target_arch = "wasm32")]
+    let storage = IndexedDbStorage::open(name)?;
+    Ok(Box::new(storage))
+}
+
+/// Storage for a few small values like settings: the same as [`open`] on desktop, local
+/// storage of the browser on wasm, which can be used before [`preload`] is done.
+pub fn open_small(name: &str) -> io::Result<Box<dyn Storage>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let storage = FileStorage::open(name)?;
+    #[cfg(target_arch = "wasm32")]
+    let storage = BrowserStorage::open(name)?;
+    Ok(Box::new(storage))
+}
+
+/// First parts of the keys that have a `/`, each once and sorted.
+fn directories_of<'a>(keys: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut directories: Vec<String> = keys
+        .into_iter()
+        .filter_map(|key| key.split_once('/'))
+        .map(|(directory, _)| directory.to_string())
+        .collect();
+    directories.sort();
+    directories.dedup();
+    directories
+}
+
+/// Keeps everything in memory, nothing survives the process. Used when there is nowhere to save
+/// to and in tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.values.lock().unwrap().get(key).cloned())
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        self.values
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        Ok(self
+            .values
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| !key.contains('/'))
+            .cloned()
+            .collect())
+    }
+
+    fn directories(&self) -> io::Result<Vec<String>> {
+        Ok(directories_of(self.values.lock().unwrap().keys()))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const TEMPORARY_EXTENSION: &str = ".tmp";
+
+/// Every key is a file in the directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn open(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(key)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the file next to its destination first and renames it over, so a crash in the
+    /// middle leaves either the old or the new file but never a half written one.
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(TEMPORARY_EXTENSION);
+        let temporary = PathBuf::from(temporary);
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temporary, path)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                if !name.ends_with(TEMPORARY_EXTENSION) {
+                    keys.push(name.to_string());
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    fn directories(&self) -> io::Result<Vec<String>> {
+        let mut directories = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    directories.push(name.to_string());
+                }
+            }
+        }
+        directories.sort();
+        Ok(directories)
+    }
+
+    /// Removes temporary files of writes that did not get to the rename, the files they were
+    /// meant to replace are intact.
+    fn recover(&self) -> io::Result<usize> {
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(TEMPORARY_EXTENSION))
+            {
+                fs::remove_file(path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Local storage of the browser, values are base64 encoded strings under `<name>/<key>`.
+///
+/// Setting an item is atomic, but browsers only allow a few megabytes per site, writes that
+/// don't fit fail. Only for small values, worlds go to [`IndexedDbStorage`].
+#[cfg(target_arch = "wasm32")]
+pub struct BrowserStorage {
+    prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl BrowserStorage {
+    pub fn open(name: &str) -> io::Result<Self> {
+        local_storage()?;
+        Ok(Self {
+            prefix: format!("{name}/"),
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "local storage is not available"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(error: impl std::fmt::Debug) -> io::Error {
+    io::Error::other(format!("{error:?}"))
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for BrowserStorage {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        local_storage()?
+            .get_item(&format!("{}{key}", self.prefix))
+            .map_err(js_error)?
+            .map(|value| base64_decode(&value))
+            .transpose()
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        local_storage()?
+            .set_item(&format!("{}{key}", self.prefix), &base64_encode(bytes))
+            .map_err(js_error)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        local_storage()?
+            .remove_item(&format!("{}{key}", self.prefix))
+            .map_err(js_error)
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        let storage = local_storage()?;
+        let mut keys = Vec::new();
+        for index in 0..storage.length().map_err(js_error)? {
+            if let Some(key) = storage.key(index).map_err(js_error)? {
+                if let Some(key) = key.strip_prefix(&self.prefix) {
+                    if !key.contains('/') {
+                        keys.push(key.to_string());
+                    }
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    fn directories(&self) -> io::Result<Vec<String>> {
+        let storage = local_storage()?;
+        let mut keys = Vec::new();
+        for index in 0..storage.length().map_err(js_error)? {
+            if let Some(key) = storage.key(index).map_err(js_error)? {
+                if let Some(key) = key.strip_prefix(&self.prefix) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+        Ok(directories_of(&keys))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[cfg(target_arch = "wasm32")]
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let value = group.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value | (*byte as u32) << (16 - i * 8)
+        });
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(BASE64_ALPHABET[(value >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(target_arch = "wasm32")]
+fn base64_decode(encoded: &str) -> io::Result<Vec<u8>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid base64");
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+    for group in encoded.as_bytes().chunks(4) {
+        let digits = group.iter().take_while(|digit| **digit != b'=').count();
+        if group.len() != 4 || digits < 2 {
+            return Err(invalid());
+        }
+        let mut value = 0u32;
+        for (i, digit) in group[..digits].iter().enumerate() {
+            let sextet = BASE64_ALPHABET
+                .iter()
+                .position(|other| other == digit)
+                .ok_or_else(invalid)?;
+            value |= (sextet as u32) << (18 - i * 6);
+        }
+        for i in 0..digits - 1 {
+            bytes.push((value >> (16 - i * 8)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Name of the IndexedDB database of the game.
+#[cfg(target_arch = "wasm32")]
+const DATABASE_NAME: &str = "mine64";
+
+/// The only object store of the database, values are byte arrays under `<name>/<key>`.
+#[cfg(target_arch = "wasm32")]
+const STORE_NAME: &str = "values";
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    /// Database opened by [`preload`], the browser runs the game on a single thread.
+    static DATABASE: std::cell::RefCell<Option<web_sys::IdbDatabase>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Every value of the database, read by [`preload`] and kept in step with the writes.
+#[cfg(target_arch = "wasm32")]
+fn database_values() -> std::sync::MutexGuard<'static, HashMap<String, Vec<u8>>> {
+    static VALUES: std::sync::OnceLock<Mutex<HashMap<String, Vec<u8>>>> =
+        std::sync::OnceLock::new();
+    VALUES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Opens the database and reads all of it, then calls `then`. IndexedDB can only be read
+/// asynchronously while saves are read as the game needs them, so the game has to be started
+/// from `then`. Without IndexedDB the error is logged and [`open`] fails later on.
+#[cfg(target_arch = "wasm32")]
+pub fn preload(then: impl FnOnce() + 'static) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = load_database().await {
+            crate::logging::error(
+                crate::logging::Category::World,
+                format!("IndexedDB is not available, worlds will not be saved: {error}"),
+            );
+        }
+        then();
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn load_database() -> io::Result<()> {
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use web_sys::{IdbDatabase, IdbTransactionMode};
+
+    let factory = web_sys::window()
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "IndexedDB is not available"))?;
+    let request = factory.open_with_u32(DATABASE_NAME, 1).map_err(js_error)?;
+    let upgraded = request.clone();
+    let upgrade = Closure::once_into_js(move || {
+        if let Ok(database) = upgraded.result() {
+            let _ = database
+                .unchecked_into::<IdbDatabase>()
+                .create_object_store(STORE_NAME);
+        }
+    });
+    request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+    let database: IdbDatabase = finished(&request).await?.unchecked_into();
+
+    let transaction = database
+        .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readonly)
+        .map_err(js_error)?;
+    let store = transaction.object_store(STORE_NAME).map_err(js_error)?;
+    let keys = store.get_all_keys().map_err(js_error)?;
+    let values = store.get_all().map_err(js_error)?;
+    // Both lists come in the order of the keys.
+    let keys: js_sys::Array = finished(&keys).await?.unchecked_into();
+    let values: js_sys::Array = finished(&values).await?.unchecked_into();
+    let mut loaded = database_values();
+    for (key, value) in keys.iter().zip(values.iter()) {
+        if let Some(key) = key.as_string() {
+            loaded.insert(key, js_sys::Uint8Array::new(&value).to_vec());
+        }
+    }
+    drop(loaded);
+    DATABASE.with(|cell| *cell.borrow_mut() = Some(database));
+    Ok(())
+}
+
+/// Waits for the request to succeed and returns its result.
+#[cfg(target_arch = "wasm32")]
+async fn finished(request: &web_sys::IdbRequest) -> io::Result<wasm_bindgen::JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map_err(js_error)?;
+    request.result().map_err(js_error)
+}
+
+/// Puts the value into the database in a transaction of its own, or deletes it when there is
+/// none. The transaction finishes after this returns, a failure like a full quota is logged.
+#[cfg(target_arch = "wasm32")]
+fn store_value(key: &str, bytes: Option<&[u8]>) -> io::Result<()> {
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use web_sys::IdbTransactionMode;
+
+    DATABASE.with(|cell| {
+        let database = cell.borrow();
+        let database = database
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "IndexedDB is not open"))?;
+        let transaction = database
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+            .map_err(js_error)?;
+        let store = transaction.object_store(STORE_NAME).map_err(js_error)?;
+        let key_value = JsValue::from_str(key);
+        match bytes {
+            Some(bytes) => store.put_with_key(&js_sys::Uint8Array::from(bytes), &key_value),
+            None => store.delete(&key_value),
+        }
+        .map_err(js_error)?;
+        let key = key.to_string();
+        let failed = transaction.clone();
+        let abort = Closure::once_into_js(move || {
+            crate::logging::error(
+                crate::logging::Category::World,
+                format!("Failed to store {key}: {:?}", failed.error()),
+            );
+        });
+        transaction.set_onabort(Some(abort.unchecked_ref()));
+        Ok(())
+    })
+}
+
+/// IndexedDB of the browser, which holds far more than local storage. The values are read once
+/// by [`preload`] and then served from memory, each write goes to the database as a transaction
+/// of its own, which replaces the old value as a whole or not at all.
+#[cfg(target_arch = "wasm32")]
+pub struct IndexedDbStorage {
+    prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl IndexedDbStorage {
+    pub fn open(name: &str) -> io::Result<Self> {
+        if !DATABASE.with(|cell| cell.borrow().is_some()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "IndexedDB is not open",
+            ));
+        }
+        Ok(Self {
+            prefix: format!("{name}/"),
+        })
+    }
+
+    fn stored_keys(&self) -> Vec<String> {
+        database_values()
+            .keys()
+            .filter_map(|key| key.strip_prefix(&self.prefix))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for IndexedDbStorage {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(database_values()
+            .get(&format!("{}{key}", self.prefix))
+            .cloned())
+    }
+
+    fn write(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        let key = format!("{}{key}", self.prefix);
+        store_value(&key, Some(bytes))?;
+        database_values().insert(key, bytes.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        let key = format!("{}{key}", self.prefix);
+        store_value(&key, None)?;
+        database_values().remove(&key);
+        Ok(())
+    }
+
+    fn keys(&self) -> io::Result<Vec<String>> {
+        Ok(self
+            .stored_keys()
+            .into_iter()
+            .filter(|key| !key.contains('/'))
+            .collect())
+    }
+
+    fn directories(&self) -> io::Result<Vec<String>> {
+        Ok(directories_of(&self.stored_keys()))
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use fyrox::core::algebra::Vector3;
+
+use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::direction::{Direction, DirectionSet};
+use crate::world_generator::WORLD_HEIGHT;
+
+/// Which sides of a chunk can see each other through the non-full blocks inside of it. Indexed
+/// with [`Direction::index`], every entry is the set of sides connected to that side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connectivity([DirectionSet; 6]);
+
+impl Connectivity {
+    pub const OPEN: Connectivity = Connectivity([DirectionSet::ALL; 6]);
+
+    pub fn connects(&self, from: &Direction, to: &Direction) -> bool {
+        self.0[from.index()].contains(to)
+    }
+
+    /// Flood fills every pocket of non-full blocks and connects all sides each pocket touches.
+    pub fn compute(chunk: &Chunk) -> Self {
+        if chunk.is_empty() {
+            return Self::OPEN;
+        }
+        let size = CHUNK_SIZE as usize;
+        let index =
+            |p: Vector3<i32>| p.x as usize + p.z as usize * size + p.y as usize * size * size;
+        let mut visited = vec![false; size * size * size];
+        let mut connections = [DirectionSet::EMPTY; 6];
+        let mut stack = Vec::new();
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let start = Vector3::new(x, y, z);
+                    if visited[index(start)] || chunk.get(start).is_full() {
+                        continue;
+                    }
+                    visited[index(start)] = true;
+                    stack.push(start);
+                    let mut sides = DirectionSet::EMPTY;
+                    while let Some(cell) = stack.pop() {
+                        for direction in Direction::iterator() {
+                            let next = direction.neighbour(cell);
+                            if !(0..CHUNK_SIZE).contains(&next.x)
+                                || !(0..CHUNK_SIZE).contains(&next.y)
+                                || !(0..CHUNK_SIZE).contains(&next.z)
+                            {
+                                sides.insert(direction);
+                                continue;
+                            }
+                            if !visited[index(next)] && !chunk.get(next).is_full() {
+                                visited[index(next)] = true;
+                                stack.push(next);
+                            }
+                        }
+                    }
+                    for side in sides {
+                        connections[side.index()] = connections[side.index()].union(sides);
+                    }
+                }
+            }
+        }
+        Self(connections)
+    }
+}
+
+/// Drawn and culled chunks of the last visibility pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CullingStats {
+    pub drawn: usize,
+    pub frustum_culled: usize,
+    pub occlusion_culled: usize,
+}
+
+/// Connectivity of loaded chunks, used to find chunks that can be seen from the camera chunk.
+#[derive(Debug, Default)]
+pub struct ChunkCulling {
+    connectivity: HashMap<Vector3<i32>, Connectivity>,
+}
+
+impl ChunkCulling {
+    pub fn insert(&mut self, position: Vector3<i32>, connectivity: Connectivity) {
+        self.connectivity.insert(position, connectivity);
+    }
+
+    pub fn remove(&mut self, position: Vector3<i32>) {
+        self.connectivity.remove(&position);
+    }
+
+    /// Searches outward from the camera chunk. A chunk is entered through one side and left
+    /// through another only when the two sides see each other inside of it, and the search never
+    /// turns back towards the camera. Chunks failing `in_frustum` are not entered.
+    ///
+    /// Chunks which are not loaded yet are treated as air, chunks further than `view_distance`
+    /// chunks away are never entered.
+    pub fn visible_chunks(
+        &self,
+        camera: Vector3<i32>,
+        view_distance: i32,
+        in_frustum: impl Fn(Vector3<i32>) -> bool,
+    ) -> HashSet<Vector3<i32>> {
+        let lowest = camera.y.min(0);
+        let highest = camera.y.max(WORLD_HEIGHT);
+        let in_range = |position: Vector3<i32>| {
+            let (dx, dz) = (position.x - camera.x, position.z - camera.z);
+            dx * dx + dz * dz <= view_distance * view_distance
+                && (lowest..=highest).contains(&position.y)
+        };
+
+        let mut visible = HashSet::from([camera]);
+        // Chunk, side it was entered through and directions travelled to reach it.
+        let mut queue = VecDeque::from([(camera, None::<Direction>, DirectionSet::EMPTY)]);
+        while let Some((position, entered, travelled)) = queue.pop_front() {
+            let connectivity = self
+                .connectivity
+                .get(&position)
+                .copied()
+                .unwrap_or(Connectivity::OPEN);
+            for direction in Direction::iterator() {
+                let back = direction.opposite();
+                if travelled.contains(&back) {
+                    continue;
+                }
+                if entered.is_some_and(|entered| !connectivity.connects(&entered, direction)) {
+                    continue;
+                }
+                let next = direction.neighbour(position);
+                if visible.contains(&next) || !in_range(next) || !in_frustum(next) {
+                    continue;
+                }
+                visible.insert(next);
+                let mut travelled = travelled;
+                travelled.insert(direction);
+                queue.push_back((next, Some(back), travelled));
+            }
+        }
+        visible
+    }
+}
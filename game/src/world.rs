@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use fyrox::core::algebra::Vector3;
+
+use crate::block::Block;
+use crate::chunk::{chunk_position, local_position, BlockSource, Chunk};
+use crate::world_generator::WORLD_HEIGHT;
+
+/// All chunks that are currently in memory.
+#[derive(Debug, Default)]
+pub struct World {
+    chunks: HashMap<Vector3<i32>, Chunk>,
+}
+
+impl World {
+    pub fn chunk(&self, position: Vector3<i32>) -> Option<&Chunk> {
+        self.chunks.get(&position)
+    }
+
+    pub fn chunk_mut(&mut self, position: Vector3<i32>) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&position)
+    }
+
+    pub fn insert_chunk(&mut self, chunk: Chunk) {
+        self.chunks.insert(chunk.position(), chunk);
+    }
+
+    pub fn remove_chunk(&mut self, position: Vector3<i32>) -> Option<Chunk> {
+        self.chunks.remove(&position)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = &mut Chunk> {
+        self.chunks.values_mut()
+    }
+
+    /// Sets a block and marks its chunk as modified and dirty. Returns the position of the
+    /// changed chunk, `None` when the chunk is not loaded. Writing into an empty chunk in its
+    /// place would save air over the terrain of the chunk.
+    pub fn set_block(&mut self, position: Vector3<i32>, block: Block) -> Option<Vector3<i32>> {
+        let chunk_position = chunk_position(position);
+        let chunk = self.chunks.get_mut(&chunk_position)?;
+        chunk.set(local_position(position), block);
+        chunk.set_modified(true);
+        chunk.set_dirty(true);
+        Some(chunk_position)
+    }
+
+    /// Boxes things collide with in the cell, as world space minimum and maximum corners. Chunks
+    /// that are not loaded are solid, so nothing leaves the loaded part of the world.
+    fn solid_boxes(&self, cell: Vector3<i32>) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+        let origin = cell.cast::<f32>();
+        let chunk = chunk_position(cell);
+        let Some(loaded) = self.chunk(chunk) else {
+            if (0..WORLD_HEIGHT).contains(&chunk.y) {
+                return vec![(origin, origin.add_scalar(1.0))];
+            }
+            return Vec::new();
+        };
+        loaded
+            .get(cell - loaded.origin())
+            .collision_boxes()
+            .iter()
+            .map(|model_box| {
+                (
+                    origin + Vector3::from(model_box.min),
+                    origin + Vector3::from(model_box.max),
+                )
+            })
+            .collect()
+    }
+
+    /// How far the box between the corners gets when it moves `distance` along `axis`, it stops
+    /// at the first block in the way. Blocks the box already overlaps don't stop it, so whatever
+    /// got stuck in a block can move out of it.
+    pub fn sweep(&self, min: Vector3<f32>, max: Vector3<f32>, axis: usize, distance: f32) -> f32 {
+        let (mut moved_min, mut moved_max) = (min, max);
+        moved_min[axis] += distance;
+        moved_max[axis] += distance;
+        let first = min.inf(&moved_min).map(|v| v.floor() as i32);
+        let last = max.sup(&moved_max).map(|v| v.floor() as i32);
+        let mut allowed = distance;
+        for y in first.y..=last.y {
+            for z in first.z..=last.z {
+                for x in first.x..=last.x {
+                    for (box_min, box_max) in self.solid_boxes(Vector3::new(x, y, z)) {
+                        let overlaps = |min: &Vector3<f32>, max: &Vector3<f32>| {
+                            (0..3).all(|i| min[i] < box_max[i] && max[i] > box_min[i])
+                        };
+                        if !overlaps(&moved_min, &moved_max) || overlaps(&min, &max) {
+                            continue;
+                        }
+                        allowed = if distance > 0.0 {
+                            allowed.min(box_min[axis] - max[axis]).max(0.0)
+                        } else {
+                            allowed.max(box_max[axis] - min[axis]).min(0.0)
+                        };
+                    }
+                }
+            }
+        }
+        allowed
+    }
+}
+
+impl BlockSource for World {
+    fn block(&self, position: Vector3<i32>) -> Block {
+        self.chunks
+            .get(&chunk_position(position))
+            .map_or(Block::Air, |chunk| chunk.get(local_position(position)))
+    }
+}
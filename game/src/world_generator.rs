@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use fyrox::core::algebra::{Vector2, Vector3};
+
+use crate::algorithm::{fractal_noise_2d, fractal_noise_3d, hash};
+use crate::biome::Biome;
+use crate::block::Block;
+use crate::chunk::{Chunk, CHUNK_SIZE};
+use crate::features::PendingEdits;
+use crate::generation_settings::{BiomeSettings, GenerationSettings};
+
+/// Number of chunks stacked on top of each other, starting at chunk `y = 0`.
+pub const WORLD_HEIGHT: i32 = 4;
+
+// Every noise gets its own seed derived from the world seed.
+const HEIGHT_NOISE: u32 = 0;
+const OVERHANG_NOISE: u32 = 1;
+const CHEESE_NOISE: u32 = 2;
+const WORM_NOISES: [u32; 2] = [3, 4];
+const PLANT_NOISE: u32 = 5;
+const TEMPERATURE_NOISE: u32 = 6;
+const HUMIDITY_NOISE: u32 = 7;
+const FEATURE_NOISE: u32 = 8;
+const VARIATION_NOISE: u32 = 9;
+const FIRST_ORE_NOISE: u32 = 16;
+
+/// Planned regions kept around before the cache is emptied.
+const MAX_PLANNED_REGIONS: usize = 2048;
+
+/// Temperature and humidity of a column, both in `0.0..1.0`.
+#[derive(Debug, Clone, Copy)]
+struct Climate {
+    temperature: f32,
+    humidity: f32,
+}
+
+impl Climate {
+    /// Squared distance to the climate typical for the biome.
+    fn distance(&self, biome: &BiomeSettings) -> f32 {
+        (self.temperature - biome.temperature).powi(2) + (self.humidity - biome.humidity).powi(2)
+    }
+}
+
+/// Generates chunks from a seed, the result only depends on the seed, the settings and the chunk
+/// position, so chunks can be generated in any order.
+///
+/// Features are planned per region, a column of chunks, and every chunk applies the plans of its
+/// region and the regions around it. Clones share the planned regions.
+#[derive(Debug, Clone)]
+pub struct WorldGenerator {
+    seed: u32,
+    settings: Arc<GenerationSettings>,
+    planned: Arc<Mutex<HashMap<Vector2<i32>, Arc<PendingEdits>>>>,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u32, settings: GenerationSettings) -> Self {
+        Self {
+            seed,
+            settings: Arc::new(settings),
+            planned: Default::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    fn noise_seed(&self, noise: u32) -> u32 {
+        self.seed ^ noise.wrapping_mul(0x9e37_79b9)
+    }
+
+    fn climate(&self, x: i32, z: i32) -> Climate {
+        let climate = &self.settings.climate;
+        let noise = |noise| {
+            let value = fractal_noise_2d(
+                self.noise_seed(noise),
+                x as f32 * climate.scale,
+                z as f32 * climate.scale,
+                3,
+            );
+            ((value - 0.5) * climate.contrast + 0.5).clamp(0.0, 1.0)
+        };
+        Climate {
+            temperature: noise(TEMPERATURE_NOISE),
+            humidity: noise(HUMIDITY_NOISE),
+        }
+    }
+
+    /// Biome closest to the climate.
+    fn biome_settings(&self, climate: Climate) -> &BiomeSettings {
+        self.settings
+            .biomes
+            .iter()
+            .min_by(|a, b| climate.distance(a).total_cmp(&climate.distance(b)))
+            .expect("generation settings must have a biome")
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.biome_settings(self.climate(x, z)).biome
+    }
+
+    /// Height of the ground before the density noise moves it. Heights of all biomes are weighted
+    /// by how close they are to the climate, so the ground changes smoothly across biome borders.
+    fn base_height(&self, x: i32, z: i32, climate: Climate) -> f32 {
+        let noise = fractal_noise_2d(
+            self.noise_seed(HEIGHT_NOISE),
+            x as f32 * self.settings.terrain.horizontal_scale,
+            z as f32 * self.settings.terrain.horizontal_scale,
+            4,
+        );
+        let closest = climate.distance(self.biome_settings(climate));
+        let (height, total) =
+            self.settings
+                .biomes
+                .iter()
+                .fold((0.0, 0.0), |(height, total), biome| {
+                    let weight = ((closest - climate.distance(biome))
+                        * self.settings.climate.blend_sharpness)
+                        .exp();
+                    (
+                        height + (biome.base_height + noise * biome.height_variation) * weight,
+                        total + weight,
+                    )
+                });
+        height / total
+    }
+
+    /// Whether the terrain is solid at the block, before caves are carved.
+    fn is_solid(&self, x: i32, y: i32, z: i32, base_height: f32) -> bool {
+        let terrain = &self.settings.terrain;
+        let below = base_height - y as f32;
+        if below.abs() > terrain.overhang_strength {
+            return below > 0.0;
+        }
+        let noise = fractal_noise_3d(
+            self.noise_seed(OVERHANG_NOISE),
+            x as f32 * terrain.overhang_scale,
+            y as f32 * terrain.overhang_scale,
+            z as f32 * terrain.overhang_scale,
+            3,
+        );
+        below + (noise * 2.0 - 1.0) * terrain.overhang_strength > 0.0
+    }
+
+    fn is_cave(&self, x: i32, y: i32, z: i32) -> bool {
+        let caves = &self.settings.caves;
+        if y < caves.min_height {
+            return false;
+        }
+        let cheese = fractal_noise_3d(
+            self.noise_seed(CHEESE_NOISE),
+            x as f32 * caves.cheese_scale,
+            y as f32 * caves.cheese_scale * 2.0,
+            z as f32 * caves.cheese_scale,
+            2,
+        );
+        if cheese > caves.cheese_threshold {
+            return true;
+        }
+        WORM_NOISES.iter().all(|noise| {
+            let worm = fractal_noise_3d(
+                self.noise_seed(*noise),
+                x as f32 * caves.worm_scale,
+                y as f32 * caves.worm_scale,
+                z as f32 * caves.worm_scale,
+                2,
+            );
+            (worm - 0.5).abs() < caves.worm_radius
+        })
+    }
+
+    fn ore(&self, x: i32, y: i32, z: i32) -> Option<Block> {
+        self.settings
+            .ores
+            .iter()
+            .enumerate()
+            .find(|(index, ore)| {
+                (ore.min_height..=ore.max_height).contains(&y)
+                    && fractal_noise_3d(
+                        self.noise_seed(FIRST_ORE_NOISE + *index as u32),
+                        x as f32 * ore.frequency,
+                        y as f32 * ore.frequency,
+                        z as f32 * ore.frequency,
+                        2,
+                    ) > ore.threshold
+            })
+            .map(|(_, ore)| ore.block)
+    }
+
+    /// Height of the topmost solid block of the column.
+    pub fn height(&self, x: i32, z: i32) -> i32 {
+        let base_height = self.base_height(x, z, self.climate(x, z));
+        let top = (base_height + self.settings.terrain.overhang_strength).ceil() as i32;
+        (0..=top)
+            .rev()
+            .find(|y| self.is_solid(x, *y, z, base_height))
+            .unwrap_or(0)
+    }
+
+    /// Where players of a new world start, a bit above the ground at the origin.
+    pub fn spawn(&self) -> Vector3<f32> {
+        Vector3::new(0.5, self.height(0, 0) as f32 + 3.0, 0.5)
+    }
+
+    pub fn generate(&self, position: Vector3<i32>) -> Chunk {
+        let mut chunk = Chunk::new(position);
+        if !(0..WORLD_HEIGHT).contains(&position.y) {
+            return chunk;
+        }
+        let origin = chunk.origin();
+        let terrain = &self.settings.terrain;
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let (world_x, world_z) = (origin.x + x, origin.z + z);
+                let climate = self.climate(world_x, world_z);
+                let biome = self.biome_settings(climate);
+                let base_height = self.base_height(world_x, world_z, climate);
+                // Solid blocks right above the current one. Walking starts far enough above the
+                // chunk that the count is right, or at least deep enough for stone, once inside.
+                let mut depth = 0;
+                let top = origin.y + CHUNK_SIZE + terrain.subsurface_depth;
+                for world_y in (origin.y - 1..=top).rev() {
+                    let local = Vector3::new(x, world_y - origin.y, z);
+                    if !self.is_solid(world_x, world_y, world_z, base_height) {
+                        if world_y < terrain.sea_level {
+                            chunk.set(local, Block::Water);
+                        }
+                        depth = 0;
+                        continue;
+                    }
+                    let underwater = world_y + 1 < terrain.sea_level;
+                    let block = if depth == 0 && underwater {
+                        terrain.underwater_surface
+                    } else if depth == 0 {
+                        biome.surface
+                    } else if depth <= terrain.subsurface_depth {
+                        biome.subsurface
+                    } else if self.is_cave(world_x, world_y, world_z) {
+                        Block::Air
+                    } else {
+                        self.ore(world_x, world_y, world_z).unwrap_or(Block::Stone)
+                    };
+                    depth += 1;
+                    chunk.set(local, block);
+                    if depth == 1 && !underwater {
+                        let decoration = self.decoration(biome, world_x, world_y + 1, world_z);
+                        chunk.set(local + Vector3::y(), decoration);
+                    }
+                }
+            }
+        }
+        self.place_features(&mut chunk);
+        chunk
+    }
+
+    /// Writes blocks of features from the chunk's region and the regions around it. Regions are
+    /// always applied in the same order, so overlapping features end up the same no matter which
+    /// chunk is generated first.
+    fn place_features(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        for z in -1..=1 {
+            for x in -1..=1 {
+                self.planned_region(Vector2::new(position.x + x, position.z + z))
+                    .apply(chunk);
+            }
+        }
+    }
+
+    fn planned_region(&self, region: Vector2<i32>) -> Arc<PendingEdits> {
+        if let Some(edits) = self.planned.lock().unwrap().get(&region) {
+            return edits.clone();
+        }
+        // Planning is deterministic, two threads planning the same region at once agree.
+        let edits = Arc::new(self.plan_features(region));
+        let mut planned = self.planned.lock().unwrap();
+        if planned.len() >= MAX_PLANNED_REGIONS {
+            planned.clear();
+        }
+        planned.insert(region, edits.clone());
+        edits
+    }
+
+    /// Blocks of every feature standing in the region, seeded by the region alone.
+    fn plan_features(&self, region: Vector2<i32>) -> PendingEdits {
+        let mut edits = PendingEdits::default();
+        let most_likely = self
+            .settings
+            .biomes
+            .iter()
+            .map(|biome| biome.features.iter().map(|feature| feature.chance).sum())
+            .fold(0.0, f32::max);
+        let origin = region * CHUNK_SIZE;
+        for z in origin.y..origin.y + CHUNK_SIZE {
+            for x in origin.x..origin.x + CHUNK_SIZE {
+                let mut roll = hash(self.noise_seed(FEATURE_NOISE), x, 0, z);
+                if roll >= most_likely {
+                    continue;
+                }
+                let biome = self.biome_settings(self.climate(x, z));
+                let Some(feature) = biome.features.iter().find(|feature| {
+                    roll -= feature.chance;
+                    roll < 0.0
+                }) else {
+                    continue;
+                };
+                let ground = self.height(x, z);
+                if ground + 1 < self.settings.terrain.sea_level {
+                    continue;
+                }
+                let variation = hash(self.noise_seed(VARIATION_NOISE), x, 0, z);
+                let anchor = Vector3::new(x, ground + 1, z);
+                for (position, block) in feature.feature.blocks(self, anchor, variation) {
+                    edits.push(position, block);
+                }
+            }
+        }
+        edits
+    }
+
+    fn decoration(&self, biome: &BiomeSettings, x: i32, y: i32, z: i32) -> Block {
+        let mut roll = hash(self.noise_seed(PLANT_NOISE), x, y, z);
+        for decoration in &biome.decorations {
+            if roll < decoration.chance {
+                return decoration.block;
+            }
+            roll -= decoration.chance;
+        }
+        Block::Air
+    }
+}
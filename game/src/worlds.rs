@@ -0,0 +1,89 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::logging::{self, Category};
+use crate::save::{WorldMetadata, METADATA_KEY};
+use crate::storage::Storage;
+
+/// Name new worlds get, followed by a number when it is taken.
+const DEFAULT_NAME: &str = "world";
+
+/// Saved world, as the world selection lists it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldInfo {
+    /// Directory of the world in the saves.
+    pub name: String,
+    pub seed: u32,
+    /// Seconds the world was played for.
+    pub time: f32,
+}
+
+/// Worlds in the saves storage, every directory with world metadata in it is one. Worlds whose
+/// metadata can't be read are left out, sorted by name.
+pub fn list_worlds(saves: &dyn Storage) -> Vec<WorldInfo> {
+    let directories = match saves.directories() {
+        Ok(directories) => directories,
+        Err(error) => {
+            logging::error(
+                Category::World,
+                format!("Failed to list saved worlds: {error}"),
+            );
+            return Vec::new();
+        }
+    };
+    let mut worlds = Vec::new();
+    for name in directories {
+        let bytes = match saves.read(&format!("{name}/{METADATA_KEY}")) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => continue,
+            Err(error) => {
+                logging::error(
+                    Category::World,
+                    format!("Failed to read world {name}: {error}"),
+                );
+                continue;
+            }
+        };
+        match WorldMetadata::decode(&bytes) {
+            Ok(metadata) => worlds.push(WorldInfo {
+                name,
+                seed: metadata.seed,
+                time: metadata.time,
+            }),
+            Err(error) => logging::error(
+                Category::World,
+                format!("Failed to read world {name}: {error}"),
+            ),
+        }
+    }
+    worlds.sort_by(|a, b| a.name.cmp(&b.name));
+    worlds
+}
+
+/// `world`, or `world-2`, `world-3` and so on when the name is taken.
+pub fn new_world_name(worlds: &[WorldInfo]) -> String {
+    let taken = |name: &str| worlds.iter().any(|world| world.name == name);
+    if !taken(DEFAULT_NAME) {
+        return DEFAULT_NAME.to_string();
+    }
+    (2..)
+        .map(|number| format!("{DEFAULT_NAME}-{number}"))
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+/// Seed typed in by the player: a number is used as it is, other text is hashed and no text at
+/// all picks a random seed.
+pub fn parse_seed(text: &str) -> u32 {
+    let text = text.trim();
+    if text.is_empty() {
+        return RandomState::new().build_hasher().finish() as u32;
+    }
+    if let Ok(seed) = text.parse() {
+        return seed;
+    }
+    // FNV-1a, so the same text gives the same world on every platform.
+    text.bytes().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
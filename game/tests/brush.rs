@@ -0,0 +1,118 @@
+use fyrox::core::algebra::Vector3;
+use mine64::block::Block;
+use mine64::brush::{apply_edits, pick, Brush, BrushTarget, BrushTool};
+use mine64::chunk::BlockSource;
+use mine64::direction::Direction;
+use mine64::schematic::Schematic;
+
+fn volume() -> Schematic {
+    Schematic::new(Vector3::repeat(16))
+}
+
+#[test]
+fn picks_blocks_and_the_floor_of_empty_columns() {
+    let mut volume = volume();
+    volume.set(Vector3::new(4, 0, 4), Block::Stone);
+    let down = -Vector3::y();
+    assert_eq!(
+        pick(&volume, Vector3::new(4.5, 10.0, 4.5), down, 100.0),
+        Some(BrushTarget {
+            block: Vector3::new(4, 0, 4),
+            face: Direction::Top,
+        })
+    );
+    assert_eq!(
+        pick(&volume, Vector3::new(8.5, 10.0, 2.5), down, 100.0),
+        Some(BrushTarget {
+            block: Vector3::new(8, -1, 2),
+            face: Direction::Top,
+        })
+    );
+    assert_eq!(pick(&volume, Vector3::new(8.5, 10.0, 2.5), down, 5.0), None);
+    assert_eq!(
+        pick(&volume, Vector3::new(-3.5, 10.0, 2.5), down, 100.0),
+        None
+    );
+}
+
+#[test]
+fn edits_undo_to_the_previous_blocks() {
+    let mut volume = volume();
+    let floor = BrushTarget {
+        block: Vector3::new(2, -1, 2),
+        face: Direction::Top,
+    };
+    let mut brush = Brush::new(BrushTool::Place, Block::Planks);
+    let placed = brush.click(&volume, &floor);
+    assert_eq!(placed.len(), 1);
+    apply_edits(&mut volume, &placed, false);
+    assert_eq!(volume.block(Vector3::new(2, 0, 2)), Block::Planks);
+    // The block is taken, so placing on the floor again changes nothing.
+    assert!(brush.click(&volume, &floor).is_empty());
+
+    let top = BrushTarget {
+        block: Vector3::new(2, 0, 2),
+        face: Direction::Top,
+    };
+    brush.set_tool(BrushTool::Replace);
+    brush.block = Block::Stone;
+    let replaced = brush.click(&volume, &top);
+    apply_edits(&mut volume, &replaced, false);
+    assert_eq!(volume.block(top.block), Block::Stone);
+
+    brush.set_tool(BrushTool::Remove);
+    let removed = brush.click(&volume, &top);
+    apply_edits(&mut volume, &removed, false);
+    assert_eq!(volume.block(top.block), Block::Air);
+
+    apply_edits(&mut volume, &removed, true);
+    apply_edits(&mut volume, &replaced, true);
+    assert_eq!(volume.block(top.block), Block::Planks);
+}
+
+#[test]
+fn box_fill_takes_two_clicks_and_stays_in_the_volume() {
+    let volume = volume();
+    let mut brush = Brush::new(BrushTool::BoxFill, Block::Sand);
+    let first = BrushTarget {
+        block: Vector3::new(1, -1, 1),
+        face: Direction::Top,
+    };
+    assert!(brush.click(&volume, &first).is_empty());
+    assert_eq!(brush.corner(), Some(Vector3::new(1, 0, 1)));
+    let second = BrushTarget {
+        block: Vector3::new(20, 2, 3),
+        face: Direction::Top,
+    };
+    let edits = brush.click(&volume, &second);
+    assert_eq!(brush.corner(), None);
+    // x is cut off at the edge of the volume: 15 columns, 3 deep and 4 high.
+    assert_eq!(edits.len(), 15 * 3 * 4);
+    assert!(edits.iter().all(|edit| edit.new == Block::Sand));
+}
+
+#[test]
+fn sphere_is_round_and_centered_in_front_of_the_face() {
+    let volume = volume();
+    let mut brush = Brush::new(BrushTool::Sphere, Block::Stone);
+    brush.radius = 2;
+    let target = BrushTarget {
+        block: Vector3::new(8, 7, 8),
+        face: Direction::Top,
+    };
+    let edits = brush.click(&volume, &target);
+    let positions: Vec<_> = edits.iter().map(|edit| edit.position).collect();
+    let center = Vector3::new(8, 8, 8);
+    assert!(positions.contains(&center));
+    assert!(positions.contains(&(center + Vector3::new(2, 0, 0))));
+    assert!(positions.contains(&(center + Vector3::new(1, 1, 1))));
+    assert!(!positions.contains(&(center + Vector3::new(2, 2, 0))));
+    assert_eq!(
+        positions.len(),
+        positions
+            .iter()
+            .map(|p| 2 * center - p)
+            .filter(|p| positions.contains(p))
+            .count()
+    );
+}
@@ -0,0 +1,202 @@
+use std::time::{Duration, Instant};
+
+use fyrox::core::algebra::Vector3;
+use mine64::block::Block;
+use mine64::chunk::BlockSource;
+use mine64::command::{
+    register_world_commands, CommandContext, CommandEffect, CommandError, CommandRegistry,
+};
+use mine64::generation_settings::GenerationSettings;
+use mine64::player::{GameMode, PlayerInput};
+use mine64::simulation::Simulation;
+use mine64::storage::MemoryStorage;
+
+const SEED: u32 = 7;
+
+fn registry() -> CommandRegistry {
+    let mut registry = CommandRegistry::default();
+    register_world_commands(&mut registry);
+    registry
+}
+
+/// Simulation with every chunk around the player loaded.
+fn loaded_simulation() -> Simulation {
+    let mut simulation = Simulation::new(
+        Box::new(MemoryStorage::default()),
+        SEED,
+        GenerationSettings::default(),
+        2,
+    );
+    let start = Instant::now();
+    simulation.step(0.0, &PlayerInput::default());
+    while !simulation.is_loaded() {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "chunks did not load in time"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+        simulation.step(0.0, &PlayerInput::default());
+    }
+    simulation
+}
+
+#[test]
+fn completes_command_names_and_arguments() {
+    let registry = registry();
+    assert_eq!(registry.complete("se"), ["seed", "setblock"]);
+    assert_eq!(registry.complete("gamemode c"), ["gamemode creative"]);
+    assert_eq!(
+        registry.complete("setblock 1 2 3 stone"),
+        ["setblock 1 2 3 stone", "setblock 1 2 3 stone_slab"]
+    );
+    assert!(registry.complete("tp ").is_empty());
+    assert!(registry.complete("nothing ").is_empty());
+}
+
+#[test]
+fn reports_unknown_commands_and_wrong_arguments() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let mut context = CommandContext::new(&mut simulation);
+    assert_eq!(
+        registry.run(&mut context, "fly"),
+        Err(CommandError::Unknown("fly".to_string()))
+    );
+    assert_eq!(
+        registry.run(&mut context, "tp 1 2"),
+        Err(CommandError::Usage("tp <x> <y> <z>"))
+    );
+    assert!(matches!(
+        registry.run(&mut context, "give bedrock"),
+        Err(CommandError::Failed(_))
+    ));
+    assert!(registry.run(&mut context, "help").unwrap().contains("fill"));
+}
+
+#[test]
+fn teleports_relative_to_the_player() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let start = simulation.player().position();
+    let mut context = CommandContext::new(&mut simulation);
+    registry.run(&mut context, "tp ~1 ~ ~-2.5").unwrap();
+    let moved = simulation.player().position() - start;
+    assert!((moved - Vector3::new(1.0, 0.0, -2.5)).norm() < 1e-4);
+}
+
+#[test]
+fn script_fills_blocks_and_skips_comments() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let corner = simulation.player().position().map(|v| v.floor() as i32);
+    let script = "# Builds a platform under the player.\n\
+        \n\
+        fill ~-1 ~-1 ~-1 ~1 ~-1 ~1 planks\n\
+        setblock ~ ~-1 ~ stone\n\
+        gamemode flying\n\
+        seed\n";
+    let mut context = CommandContext::new(&mut simulation);
+    let results = registry.run_script(&mut context, script);
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok() && results[1].is_ok());
+    assert_eq!(
+        results[2],
+        Err(CommandError::Usage("gamemode survival|creative"))
+    );
+    assert_eq!(results[3], Ok(format!("Seed: {SEED}")));
+    let floor = corner - Vector3::y();
+    assert_eq!(
+        context.effects,
+        [
+            CommandEffect::BlocksChanged {
+                min: floor - Vector3::new(1, 0, 1),
+                max: floor + Vector3::new(1, 0, 1),
+            },
+            CommandEffect::BlocksChanged {
+                min: floor,
+                max: floor,
+            },
+        ]
+    );
+    let world = simulation.world();
+    assert_eq!(world.block(floor), Block::Stone);
+    assert_eq!(world.block(floor + Vector3::new(1, 0, -1)), Block::Planks);
+}
+
+#[test]
+fn fill_refuses_unloaded_and_large_areas() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let mut context = CommandContext::new(&mut simulation);
+    assert!(matches!(
+        registry.run(&mut context, "setblock 100000 10 0 stone"),
+        Err(CommandError::Failed(_))
+    ));
+    assert!(matches!(
+        registry.run(&mut context, "fill ~ ~ ~ ~40 ~40 ~40 air"),
+        Err(CommandError::Failed(_))
+    ));
+    assert!(context.effects.is_empty());
+}
+
+#[test]
+fn creative_places_blocks_without_using_them_up() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let column = simulation.player().position().map(|v| v.floor() as i32);
+    let ground = simulation.generator().height(column.x, column.z);
+    for y in ground + 1..=ground + 3 {
+        simulation.set_block(Vector3::new(column.x, y, column.z), Block::Air);
+    }
+    let player = simulation.player_mut();
+    player.set_position(Vector3::new(
+        column.x as f32 + 0.5,
+        ground as f32 + 3.5,
+        column.z as f32 + 0.5,
+    ));
+    player.turn(0.0, 90.0f32.to_radians());
+
+    let mut context = CommandContext::new(&mut simulation);
+    assert_eq!(
+        registry.run(&mut context, "give stone 3"),
+        Ok("Gave 3 stone".to_string())
+    );
+    registry.run(&mut context, "gamemode creative").unwrap();
+    assert_eq!(simulation.player().game_mode(), GameMode::Creative);
+
+    let hit = simulation
+        .target(10.0)
+        .expect("the ground is below the player");
+    assert_eq!(
+        simulation.place_block(&hit),
+        Some(Vector3::new(column.x, ground + 1, column.z))
+    );
+    assert_eq!(simulation.player().inventory().count(Block::Stone), 3);
+}
+
+#[test]
+fn reload_chunks_unloads_and_loads_them_again() {
+    let registry = registry();
+    let mut simulation = loaded_simulation();
+    let loaded = simulation.world().chunks().count();
+    let mut context = CommandContext::new(&mut simulation);
+    registry
+        .run(&mut context, "setblock ~ ~-1 ~ gold_ore")
+        .unwrap();
+    registry.run(&mut context, "reload_chunks").unwrap();
+    let Some(CommandEffect::ChunksUnloaded(unloaded)) = context.effects.last() else {
+        panic!("chunks were not unloaded");
+    };
+    assert_eq!(unloaded.len(), loaded);
+    assert_eq!(simulation.world().chunks().count(), 0);
+
+    let below = simulation.player().position().map(|v| v.floor() as i32) - Vector3::y();
+    let start = Instant::now();
+    simulation.step(0.0, &PlayerInput::default());
+    while !simulation.is_loaded() {
+        assert!(start.elapsed() < Duration::from_secs(30));
+        std::thread::sleep(Duration::from_millis(1));
+        simulation.step(0.0, &PlayerInput::default());
+    }
+    assert_eq!(simulation.world().block(below), Block::GoldOre);
+}
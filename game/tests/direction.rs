@@ -0,0 +1,109 @@
+use fyrox::core::algebra::Vector3;
+use mine64::direction::{Direction, DirectionSet};
+
+#[test]
+fn opposites_point_back() {
+    for direction in Direction::iterator() {
+        let opposite = direction.opposite();
+        assert_ne!(opposite, *direction);
+        assert_eq!(opposite.opposite(), *direction);
+        assert_eq!(opposite.offset(), -direction.offset());
+        assert_eq!(
+            opposite.neighbour(direction.neighbour(Vector3::new(3, -2, 7))),
+            Vector3::new(3, -2, 7)
+        );
+    }
+    assert_eq!(Direction::Top.opposite(), Direction::Bottom);
+    assert_eq!(Direction::Front.opposite(), Direction::Back);
+    assert_eq!(Direction::Right.opposite(), Direction::Left);
+}
+
+#[test]
+fn axis_and_sign_make_the_offset() {
+    for (index, direction) in Direction::iterator().enumerate() {
+        assert_eq!(direction.index(), index);
+        assert_eq!(Direction::from_index(index), Some(*direction));
+        assert_eq!(
+            Direction::from_axis(direction.axis(), direction.sign() > 0),
+            Some(*direction)
+        );
+        assert_eq!(Direction::from_offset(direction.offset()), Some(*direction));
+        #[allow(deprecated)]
+        let (x, y, z) = direction.vector();
+        assert_eq!(Vector3::new(x, y, z), direction.offset());
+    }
+    assert_eq!(Direction::Front.offset(), Vector3::new(0, 0, 1));
+    assert_eq!(Direction::Top.axis(), 1);
+    assert_eq!(Direction::Left.sign(), -1);
+    assert_eq!(Direction::from_axis(3, true), None);
+    assert_eq!(Direction::from_index(6), None);
+    assert_eq!(Direction::from_offset(Vector3::new(1, 1, 0)), None);
+}
+
+#[test]
+fn normals_pick_the_face_of_their_longest_component() {
+    assert_eq!(
+        Direction::from_normal(Vector3::new(0.2, -0.9, 0.3)),
+        Some(Direction::Bottom)
+    );
+    assert_eq!(
+        Direction::from_normal(Vector3::new(-2.0, 1.0, 0.5)),
+        Some(Direction::Left)
+    );
+    assert_eq!(
+        Direction::from_normal(Vector3::new(0.0, 0.0, 1.0)),
+        Some(Direction::Front)
+    );
+    assert_eq!(Direction::from_normal(Vector3::zeros()), None);
+    assert_eq!(
+        Direction::from_normal(Vector3::new(f32::NAN, 0.0, 0.0)),
+        None
+    );
+}
+
+#[test]
+fn quarter_turns_go_from_x_towards_z() {
+    assert_eq!(Direction::Right.rotate_y(1), Direction::Front);
+    assert_eq!(Direction::Front.rotate_y(1), Direction::Left);
+    assert_eq!(Direction::Right.rotate_y(2), Direction::Left);
+    assert_eq!(Direction::Back.rotate_y(3), Direction::Left);
+    for direction in Direction::iterator() {
+        assert_eq!(direction.rotate_y(0), *direction);
+        assert_eq!(direction.rotate_y(4), *direction);
+        assert_eq!(direction.rotate_y(2), direction.rotate_y(1).rotate_y(1));
+    }
+    assert_eq!(Direction::Top.rotate_y(1), Direction::Top);
+    assert_eq!(Direction::Bottom.rotate_y(3), Direction::Bottom);
+}
+
+#[test]
+fn direction_sets_hold_each_direction_once() {
+    let mut set = DirectionSet::EMPTY;
+    assert!(set.is_empty());
+    set.insert(&Direction::Left);
+    set.insert(&Direction::Front);
+    set.insert(&Direction::Left);
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&Direction::Front));
+    assert!(!set.contains(&Direction::Top));
+    assert_eq!(set, DirectionSet::of(&[Direction::Front, Direction::Left]));
+    // In the order of `Direction::iterator`, whatever the order they were added in.
+    assert_eq!(
+        set.iter().collect::<Vec<_>>(),
+        [Direction::Front, Direction::Left]
+    );
+    assert_eq!(set.iter().len(), 2);
+
+    set.remove(&Direction::Front);
+    set.remove(&Direction::Back);
+    assert_eq!(set, DirectionSet::of(&[Direction::Left]));
+
+    let all: DirectionSet = Direction::iterator().copied().collect();
+    assert_eq!(all, DirectionSet::ALL);
+    assert_eq!(all.len(), 6);
+    assert_eq!(
+        set.union(DirectionSet::of(&[Direction::Top])),
+        DirectionSet::of(&[Direction::Top, Direction::Left])
+    );
+    assert_eq!(all.into_iter().count(), 6);
+}
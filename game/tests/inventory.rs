@@ -0,0 +1,85 @@
+use mine64::block::Block;
+use mine64::inventory::{Inventory, HOTBAR_SIZE, INVENTORY_SIZE};
+use mine64::item::ItemStack;
+
+fn stack(block: Block, count: u32) -> ItemStack {
+    ItemStack::new(block, count)
+}
+
+#[test]
+fn adding_fills_matching_stacks_before_empty_slots() {
+    let mut inventory = Inventory::default();
+    inventory.set_slot(4, Some(stack(Block::Dirt, 60)));
+    assert_eq!(inventory.add(stack(Block::Dirt, 10)), None);
+    assert_eq!(inventory.slot(4), Some(stack(Block::Dirt, 64)));
+    assert_eq!(inventory.slot(0), Some(stack(Block::Dirt, 6)));
+    assert_eq!(inventory.count(Block::Dirt), 70);
+
+    assert_eq!(inventory.add(stack(Block::Stone, 130)), None);
+    assert_eq!(inventory.slot(1), Some(stack(Block::Stone, 64)));
+    assert_eq!(inventory.slot(2), Some(stack(Block::Stone, 64)));
+    assert_eq!(inventory.slot(3), Some(stack(Block::Stone, 2)));
+}
+
+#[test]
+fn full_inventory_returns_what_did_not_fit() {
+    let mut inventory = Inventory::default();
+    for slot in 0..INVENTORY_SIZE {
+        inventory.set_slot(slot, Some(stack(Block::Sand, 64)));
+    }
+    inventory.set_slot(HOTBAR_SIZE + 3, Some(stack(Block::Planks, 62)));
+    assert_eq!(
+        inventory.add(stack(Block::Planks, 5)),
+        Some(stack(Block::Planks, 3))
+    );
+    assert_eq!(
+        inventory.slot(HOTBAR_SIZE + 3),
+        Some(stack(Block::Planks, 64))
+    );
+    assert_eq!(
+        inventory.add(stack(Block::Air, 1)),
+        Some(stack(Block::Air, 1))
+    );
+}
+
+#[test]
+fn selection_stays_in_the_hotbar() {
+    let mut inventory = Inventory::default();
+    inventory.select(4);
+    assert_eq!(inventory.selected(), 4);
+    inventory.select(HOTBAR_SIZE);
+    assert_eq!(inventory.selected(), 4);
+    inventory.scroll(5);
+    assert_eq!(inventory.selected(), 0);
+    inventory.scroll(-1);
+    assert_eq!(inventory.selected(), HOTBAR_SIZE - 1);
+}
+
+#[test]
+fn placing_takes_from_the_selected_stack() {
+    let mut inventory = Inventory::default();
+    inventory.set_slot(2, Some(stack(Block::Fence, 2)));
+    assert_eq!(inventory.take_selected(), None);
+    inventory.select(2);
+    assert_eq!(inventory.selected_stack(), Some(stack(Block::Fence, 2)));
+    assert_eq!(inventory.take_selected(), Some(Block::Fence));
+    assert_eq!(inventory.take_selected(), Some(Block::Fence));
+    assert_eq!(inventory.take_selected(), None);
+    assert_eq!(inventory.slot(2), None);
+}
+
+#[test]
+fn empty_stacks_leave_slots_empty() {
+    let mut inventory = Inventory::default();
+    inventory.set_slot(0, Some(stack(Block::Dirt, 0)));
+    assert_eq!(inventory.slot(0), None);
+    inventory.set_slot(INVENTORY_SIZE, Some(stack(Block::Dirt, 1)));
+    assert_eq!(inventory.count(Block::Dirt), 0);
+    inventory.set_slot(0, Some(stack(Block::Dirt, 1)));
+    inventory.swap(0, INVENTORY_SIZE - 1);
+    assert_eq!(
+        inventory.slot(INVENTORY_SIZE - 1),
+        Some(stack(Block::Dirt, 1))
+    );
+    assert_eq!(inventory.slot(0), None);
+}
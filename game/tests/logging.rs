@@ -0,0 +1,129 @@
+use std::fs;
+use std::time::Duration;
+
+use mine64::logging::{Category, Level, LogFile, Logger, MAX_MESSAGES_PER_SECOND};
+
+#[test]
+fn filters_messages_by_the_level_of_their_category() {
+    let mut logger = Logger::default();
+    let now = Duration::ZERO;
+    assert_eq!(
+        logger.filter(Category::Mesh, Level::Info, "built", now),
+        [(Level::Info, "[mesh] built".to_string())]
+    );
+    assert!(logger
+        .filter(Category::Mesh, Level::Debug, "details", now)
+        .is_empty());
+
+    logger.set_level(Category::Mesh, Level::Debug);
+    logger.set_level(Category::Input, Level::Off);
+    assert_eq!(logger.level(Category::Mesh), Level::Debug);
+    assert_eq!(
+        logger
+            .filter(Category::Mesh, Level::Debug, "details", now)
+            .len(),
+        1
+    );
+    assert!(logger
+        .filter(Category::Input, Level::Error, "key", now)
+        .is_empty());
+    assert_eq!("physics".parse(), Ok(Category::Physics));
+    assert_eq!("warning".parse(), Ok(Level::Warning));
+}
+
+#[test]
+fn rate_limits_every_category_on_its_own() {
+    let mut logger = Logger::default();
+    let mut logged = 0;
+    for i in 0..100 {
+        let now = Duration::from_millis(i * 5);
+        logged += logger
+            .filter(Category::World, Level::Info, "spam", now)
+            .len();
+    }
+    assert_eq!(logged, MAX_MESSAGES_PER_SECOND as usize);
+    assert_eq!(
+        logger
+            .filter(
+                Category::Physics,
+                Level::Info,
+                "other",
+                Duration::from_millis(500)
+            )
+            .len(),
+        1
+    );
+
+    let lines = logger.filter(Category::World, Level::Info, "spam", Duration::from_secs(1));
+    assert_eq!(
+        lines,
+        [
+            (
+                Level::Warning,
+                format!(
+                    "[world] {} messages were suppressed",
+                    100 - MAX_MESSAGES_PER_SECOND
+                )
+            ),
+            (Level::Info, "[world] spam".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn suppressed_messages_are_reported_when_their_second_is_over() {
+    let mut logger = Logger::default();
+    for i in 0..40 {
+        logger.filter(
+            Category::Mesh,
+            Level::Info,
+            "spam",
+            Duration::from_millis(i * 10),
+        );
+    }
+    assert!(logger.flush(Duration::from_millis(900)).is_empty());
+    assert_eq!(
+        logger.flush(Duration::from_millis(1200)),
+        [(
+            Level::Warning,
+            format!(
+                "[mesh] {} messages were suppressed",
+                40 - MAX_MESSAGES_PER_SECOND
+            )
+        )]
+    );
+    assert!(logger.flush(Duration::from_secs(5)).is_empty());
+    // The next message does not report them again.
+    assert_eq!(
+        logger.filter(Category::Mesh, Level::Info, "quiet", Duration::from_secs(6)),
+        [(Level::Info, "[mesh] quiet".to_string())]
+    );
+}
+
+#[test]
+fn log_files_rotate_when_they_grow_and_on_every_run() {
+    let directory = std::env::temp_dir().join(format!("mine64-logs-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let read = |name: &str| fs::read_to_string(directory.join(name)).unwrap_or_default();
+
+    let mut file = LogFile::open(&directory, 16, 2).unwrap();
+    for line in ["first", "second", "third", "fourth"] {
+        file.write_line(line).unwrap();
+    }
+    drop(file);
+    // Every file takes two lines of six or seven bytes before it is over 16 bytes.
+    assert_eq!(read("game.log"), "third\nfourth\n");
+    assert_eq!(read("game.1.log"), "first\nsecond\n");
+
+    let mut file = LogFile::open(&directory, 16, 2).unwrap();
+    file.write_line("next run").unwrap();
+    drop(file);
+    assert_eq!(read("game.log"), "next run\n");
+    assert_eq!(read("game.1.log"), "third\nfourth\n");
+    assert_eq!(read("game.2.log"), "first\nsecond\n");
+    assert!(!directory.join("game.3.log").exists());
+
+    LogFile::open(&directory, 16, 2).unwrap();
+    assert_eq!(read("game.2.log"), "third\nfourth\n");
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::PathBuf;
+
+use fyrox::core::algebra::Vector3;
+use mine64::block::{Block, BlockModel};
+use mine64::chunk::{chunk_position, BlockSource, Chunk};
+use mine64::direction::Direction;
+use mine64::export::{export_mesh, ExportFormat};
+use mine64::mesher::{build_mesh, ChunkMesh};
+use mine64::world::World;
+
+/// Set to accept the current output of the mesher as the new golden files.
+const UPDATE_VARIABLE: &str = "UPDATE_GOLDEN";
+
+fn world(blocks: &[(Vector3<i32>, Block)]) -> World {
+    let mut world = World::default();
+    for (position, block) in blocks {
+        let chunk = chunk_position(*position);
+        if world.chunk(chunk).is_none() {
+            world.insert_chunk(Chunk::new(chunk));
+        }
+        world.set_block(*position, *block);
+    }
+    world
+}
+
+fn at(x: i32, y: i32, z: i32) -> Vector3<i32> {
+    Vector3::new(x, y, z)
+}
+
+/// Triangles are counter clockwise when looked at from the side their normal points to, every
+/// vertex normal is a unit vector, texture coordinates stay inside of the texture and faces of
+/// boxes point out of the box they belong to.
+fn check_mesh(source: &impl BlockSource, mesh: &ChunkMesh) {
+    for vertex in &mesh.vertices {
+        assert!((vertex.normal.norm() - 1.0).abs() < 1e-5, "{vertex:?}");
+        assert!(
+            vertex.tex_coord.iter().all(|v| (0.0..=1.0).contains(v)),
+            "{vertex:?}"
+        );
+    }
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle.0.map(|index| mesh.vertices[index as usize]);
+        assert_eq!(a.normal, b.normal);
+        assert_eq!(a.normal, c.normal);
+        let winding = (b.position - a.position).cross(&(c.position - a.position));
+        assert!(winding.dot(&a.normal) > 0.0, "inside out: {triangle:?}");
+
+        let center = (a.position + b.position + c.position) / 3.0;
+        let inside = center - a.normal * 0.01;
+        let cell = inside.map(|v| v.floor() as i32);
+        let block = source.block(cell);
+        match block.model() {
+            BlockModel::Boxes { boxes, .. } => {
+                let local = inside - cell.cast::<f32>();
+                assert!(
+                    boxes.iter().any(|model_box| (0..3)
+                        .all(|axis| (model_box.min[axis]..=model_box.max[axis])
+                            .contains(&local[axis]))),
+                    "face of {block:?} points inwards: {triangle:?}"
+                );
+            }
+            BlockModel::Cross => assert_eq!(a.normal.y, 0.0),
+            BlockModel::Empty => panic!("face without a block: {triangle:?}"),
+        }
+    }
+}
+
+/// Compares the mesh written as OBJ with `tests/golden/<name>.obj`.
+fn check_golden(name: &str, mesh: &ChunkMesh) {
+    let obj = export_mesh(mesh, ExportFormat::Obj, name, None).remove(0);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(&obj.name);
+    if std::env::var_os(UPDATE_VARIABLE).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &obj.bytes).unwrap();
+        return;
+    }
+    let expected =
+        fs::read(&path).unwrap_or_else(|error| panic!("can't read {}: {error}", path.display()));
+    assert!(
+        expected == obj.bytes,
+        "mesh differs from {}, run with {UPDATE_VARIABLE}=1 to accept it",
+        path.display()
+    );
+}
+
+/// Meshes the chunk at the origin, blocks may also be placed in the chunks around it.
+fn check(name: &str, blocks: &[(Vector3<i32>, Block)], vertices: usize, triangles: usize) {
+    let world = world(blocks);
+    let mesh = build_mesh(&world, Vector3::zeros(), 16);
+    check_mesh(&world, &mesh);
+    assert_eq!(mesh.vertices.len(), vertices, "vertices of {name}");
+    assert_eq!(mesh.triangles.len(), triangles, "triangles of {name}");
+    check_golden(name, &mesh);
+}
+
+#[test]
+fn direction_tables_face_outward() {
+    for direction in Direction::iterator() {
+        let outward = direction.offset().cast::<f32>();
+        let vertices = direction.verticles();
+        for vertex in &vertices {
+            assert_eq!(vertex.normal, outward, "{direction:?}");
+            assert_eq!(vertex.position.dot(&outward), 0.5, "{direction:?}");
+        }
+        for triangle in direction.triangles() {
+            let [a, b, c] = triangle.0.map(|index| vertices[index as usize].position);
+            let winding = (b - a).cross(&(c - a));
+            assert!(winding.dot(&outward) > 0.0, "{direction:?} is inside out");
+        }
+    }
+}
+
+#[test]
+fn empty_chunk_has_no_faces() {
+    check("empty", &[], 0, 0);
+}
+
+#[test]
+fn cube() {
+    check("cube", &[(at(1, 1, 1), Block::Stone)], 24, 12);
+}
+
+#[test]
+fn touching_cubes_hide_shared_faces() {
+    check(
+        "two_cubes",
+        &[(at(1, 1, 1), Block::Stone), (at(2, 1, 1), Block::Dirt)],
+        40,
+        20,
+    );
+}
+
+#[test]
+fn slab() {
+    check("slab", &[(at(1, 1, 1), Block::StoneSlab)], 24, 12);
+}
+
+#[test]
+fn slab_on_cube_hides_faces_between_them() {
+    check(
+        "slab_on_cube",
+        &[(at(1, 1, 1), Block::Stone), (at(1, 2, 1), Block::StoneSlab)],
+        40,
+        20,
+    );
+}
+
+#[test]
+fn slab_on_slab_keeps_the_faces_between_them() {
+    // Neither face lies against the other, there is half a block between them.
+    check(
+        "slab_on_slab",
+        &[
+            (at(1, 1, 1), Block::StoneSlab),
+            (at(1, 2, 1), Block::StoneSlab),
+        ],
+        48,
+        24,
+    );
+}
+
+#[test]
+fn stairs_in_a_row_hide_only_covered_faces() {
+    // The lower boxes hide each other, the back of the upper box of the second stair stays.
+    check(
+        "stairs_in_a_row",
+        &[
+            (at(1, 1, 1), Block::PlanksStairs),
+            (at(1, 1, 2), Block::PlanksStairs),
+        ],
+        88,
+        44,
+    );
+}
+
+#[test]
+fn stairs() {
+    check("stairs", &[(at(1, 1, 1), Block::PlanksStairs)], 48, 24);
+}
+
+#[test]
+fn fence() {
+    check("fence", &[(at(1, 1, 1), Block::Fence)], 24, 12);
+}
+
+#[test]
+fn plant_is_two_sided_crossed_quads() {
+    check("plant", &[(at(1, 1, 1), Block::Flower)], 16, 8);
+}
+
+#[test]
+fn water_hides_faces_inside_of_it_but_not_behind_it() {
+    check(
+        "water",
+        &[
+            (at(1, 1, 1), Block::Water),
+            (at(2, 1, 1), Block::Water),
+            (at(3, 1, 1), Block::Stone),
+        ],
+        // Nine faces of the water and all six of the stone, which water doesn't hide.
+        60,
+        30,
+    );
+}
+
+#[test]
+fn neighbour_chunks_hide_faces_on_the_border() {
+    check(
+        "border",
+        &[(at(15, 0, 0), Block::Grass), (at(16, 0, 0), Block::Stone)],
+        20,
+        10,
+    );
+}
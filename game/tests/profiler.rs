@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use mine64::profiler::{Profiler, Scope, FRAME_HISTORY};
+
+fn ms(milliseconds: u64) -> Duration {
+    Duration::from_millis(milliseconds)
+}
+
+#[test]
+fn sums_scopes_per_frame_and_keeps_the_last_frames() {
+    let mut profiler = Profiler::default();
+    profiler.record(Scope::Meshing, 0, ms(0), ms(2));
+    profiler.record(Scope::Meshing, 1, ms(1), ms(3));
+    profiler.record(Scope::Raycast, 0, ms(4), ms(1));
+    profiler.finish_frame(0, ms(0), ms(10));
+
+    let frame = profiler.frames().next().unwrap();
+    assert_eq!(frame.update, ms(10));
+    assert_eq!(frame.scope(Scope::Meshing), ms(5));
+    assert_eq!(frame.scope(Scope::Raycast), ms(1));
+    assert_eq!(frame.scope(Scope::Generation), Duration::ZERO);
+
+    for i in 0..FRAME_HISTORY as u64 {
+        profiler.finish_frame(0, ms(10 + i), ms(i));
+    }
+    assert_eq!(profiler.frames().count(), FRAME_HISTORY);
+    assert_eq!(profiler.frames().next().unwrap().update, ms(0));
+    assert_eq!(
+        profiler.frames().last().unwrap().update,
+        ms(FRAME_HISTORY as u64 - 1)
+    );
+}
+
+#[test]
+fn writes_traces_in_the_chrome_format() {
+    let mut profiler = Profiler::default();
+    assert_eq!(profiler.stop_trace(), None);
+    profiler.record(Scope::Generation, 0, ms(0), ms(1));
+
+    profiler.start_trace();
+    assert!(profiler.is_tracing());
+    profiler.record(Scope::Generation, 2, ms(1), Duration::from_micros(1500));
+    profiler.finish_frame(0, ms(0), ms(4));
+    let json = profiler.stop_trace().unwrap();
+    assert!(!profiler.is_tracing());
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"traceEvents":["#,
+            r#"{"name":"Generation","cat":"mine64","ph":"X","pid":1,"tid":2,"ts":1000.000,"dur":1500.000},"#,
+            r#"{"name":"Frame","cat":"mine64","ph":"X","pid":1,"tid":0,"ts":0.000,"dur":4000.000}"#,
+            r#"],"displayTimeUnit":"ms"}"#
+        )
+    );
+}
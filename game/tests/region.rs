@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use fyrox::core::algebra::Vector3;
+use mine64::block::Block;
+use mine64::chunk::Chunk;
+use mine64::item::{DroppedItem, ItemStack};
+use mine64::region::{encode_blocks, Region, REGION_VERSION};
+use mine64::save::{checksum, WorldSave};
+use mine64::storage::{MemoryStorage, Storage};
+
+/// Chunks in a column of a region file, as many as the world is high.
+const WORLD_HEIGHT: i32 = 4;
+
+/// Magic, version and slot count ahead of the table.
+const TABLE_START: usize = 12;
+
+/// Slot of the chunk in the table of a region at the origin.
+fn slot(position: Vector3<i32>) -> usize {
+    ((position.x + position.z * 32) * WORLD_HEIGHT + position.y) as usize
+}
+
+/// Modified chunk with a few blocks set and an item lying in it.
+fn chunk(position: Vector3<i32>, block: Block) -> Chunk {
+    let mut chunk = Chunk::new(position);
+    for x in 0..5 {
+        chunk.set(Vector3::new(x, 0, x), block);
+    }
+    chunk.set(Vector3::new(3, 7, 1), Block::Planks);
+    let origin = chunk.origin().cast::<f32>();
+    let mut item = DroppedItem::new(
+        ItemStack::new(block, 3),
+        origin + Vector3::new(1.5, 2.25, 3.5),
+        Vector3::new(0.0, -1.0, 0.5),
+    );
+    item.age = 4.5;
+    chunk.add_item(item);
+    chunk.set_modified(true);
+    chunk
+}
+
+/// Offset and length of the record in the slot, read from the table of a version 3 file.
+fn record_range(bytes: &[u8], slot: usize) -> std::ops::Range<usize> {
+    let entry = TABLE_START + slot * 12;
+    let field = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let offset = field(entry);
+    offset..offset + field(entry + 4)
+}
+
+/// Region file of an older version with the blocks of the chunks as records, version 1 files
+/// have no checksums in their table.
+fn old_region(version: u32, chunks: &[&Chunk]) -> Vec<u8> {
+    let slots = 32 * 32 * WORLD_HEIGHT as usize;
+    let entry = if version == 1 { 8 } else { 12 };
+    let mut table = b"M64R".to_vec();
+    table.extend_from_slice(&version.to_le_bytes());
+    table.extend_from_slice(&(slots as u32).to_le_bytes());
+    let mut data = Vec::new();
+    for index in 0..slots {
+        let record = chunks
+            .iter()
+            .find(|chunk| slot(chunk.position()) == index)
+            .map(|chunk| encode_blocks(chunk.blocks()));
+        let (offset, record) = match record {
+            Some(record) => (TABLE_START + slots * entry + data.len(), record),
+            None => (0, Vec::new()),
+        };
+        table.extend_from_slice(&(offset as u32).to_le_bytes());
+        table.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        if version > 1 {
+            let sum = if record.is_empty() {
+                0
+            } else {
+                checksum(&record)
+            };
+            table.extend_from_slice(&sum.to_le_bytes());
+        }
+        data.extend(record);
+    }
+    table.extend(data);
+    table
+}
+
+#[test]
+fn chunks_survive_writing_and_reading() {
+    let first = chunk(Vector3::new(1, 2, 3), Block::Stone);
+    let second = chunk(Vector3::new(31, 0, 31), Block::Sand);
+    let mut region = Region::default();
+    assert!(region.insert(&first));
+    assert!(region.insert(&second));
+    assert!(!region.insert(&Chunk::new(Vector3::new(0, WORLD_HEIGHT, 0))));
+
+    let bytes = region.write();
+    assert_eq!(&bytes[4..8], REGION_VERSION.to_le_bytes());
+    let mut read = Region::read(&bytes).unwrap();
+    assert!(read.take_corrupted().is_empty());
+    assert_eq!(read.chunk(first.position()).unwrap(), Some(first));
+    assert_eq!(read.chunk(second.position()).unwrap(), Some(second));
+    assert_eq!(read.chunk(Vector3::new(0, 0, 0)).unwrap(), None);
+    assert_eq!(read.write(), bytes);
+}
+
+#[test]
+fn damaged_records_are_left_out() {
+    let good = chunk(Vector3::new(1, 2, 3), Block::Stone);
+    let bad = chunk(Vector3::new(4, 1, 0), Block::Sand);
+    let mut region = Region::default();
+    region.insert(&good);
+    region.insert(&bad);
+    let mut bytes = region.write();
+    let range = record_range(&bytes, slot(bad.position()));
+    bytes[range.start + 6] ^= 0xFF;
+    let damaged = bytes[range].to_vec();
+
+    let mut read = Region::read(&bytes).unwrap();
+    let corrupted = read.take_corrupted();
+    assert_eq!(corrupted.len(), 1);
+    assert_eq!(corrupted[0].slot, slot(bad.position()));
+    assert_eq!(corrupted[0].bytes, damaged);
+    assert!(
+        read.take_corrupted().is_empty(),
+        "taken records are forgotten"
+    );
+    assert_eq!(read.chunk(bad.position()).unwrap(), None);
+    assert_eq!(read.chunk(good.position()).unwrap(), Some(good));
+
+    // Records pointing past the end of the file are damaged too.
+    let cut = record_range(&bytes, slot(bad.position())).end - 1;
+    let mut truncated = Region::read(&bytes[..cut]).unwrap();
+    assert_eq!(truncated.take_corrupted().len(), 2);
+    assert!(Region::read(b"M64R").is_err());
+    assert!(Region::read(b"nope\x03\0\0\0\0\0\0\0").is_err());
+}
+
+#[test]
+fn damaged_saves_are_quarantined() {
+    let good = chunk(Vector3::new(1, 2, 3), Block::Stone);
+    let bad = chunk(Vector3::new(4, 1, 0), Block::Sand);
+    let mut region = Region::default();
+    region.insert(&good);
+    region.insert(&bad);
+    let mut bytes = region.write();
+    let range = record_range(&bytes, slot(bad.position()));
+    bytes[range.start + 6] ^= 0xFF;
+    let damaged = bytes[range].to_vec();
+
+    let storage = Arc::new(MemoryStorage::default());
+    storage.write("r.0.0.region", &bytes).unwrap();
+    storage.write("r.1.0.region", b"not a region").unwrap();
+    let save = WorldSave::new(Box::new(storage.clone()));
+    let report = save.check_integrity().unwrap();
+    assert_eq!(report.quarantined_records, 1);
+    assert_eq!(report.quarantined_regions, 1);
+
+    let key = format!("quarantine/r.0.0.{}.record", slot(bad.position()));
+    assert_eq!(storage.read(&key).unwrap(), Some(damaged));
+    assert_eq!(
+        storage.read("quarantine/r.1.0.region").unwrap().as_deref(),
+        Some(&b"not a region"[..])
+    );
+    assert_eq!(storage.read("r.1.0.region").unwrap(), None);
+    assert_eq!(save.load_chunk(good.position()).unwrap(), Some(good));
+    assert_eq!(save.load_chunk(bad.position()).unwrap(), None);
+
+    // The region was written again without the damaged record.
+    let rewritten = storage.read("r.0.0.region").unwrap().unwrap();
+    assert!(Region::read(&rewritten)
+        .unwrap()
+        .take_corrupted()
+        .is_empty());
+    let again = WorldSave::new(Box::new(storage));
+    assert!(again.check_integrity().unwrap().is_clean());
+}
+
+#[test]
+fn old_regions_are_upgraded() {
+    let first = chunk(Vector3::new(1, 2, 3), Block::Stone);
+    let second = chunk(Vector3::new(0, 0, 0), Block::Sand);
+    // Items were not saved before version 3.
+    let mut bare = first.clone();
+    bare.take_items();
+    for version in [1, 2] {
+        let bytes = old_region(version, &[&first, &second]);
+        let mut read = Region::read(&bytes).unwrap();
+        assert!(read.take_corrupted().is_empty(), "version {version}");
+        assert_eq!(read.chunk(first.position()).unwrap().as_ref(), Some(&bare));
+
+        let upgraded = read.write();
+        assert_eq!(&upgraded[4..8], REGION_VERSION.to_le_bytes());
+        let read = Region::read(&upgraded).unwrap();
+        assert_eq!(read.chunk(first.position()).unwrap().as_ref(), Some(&bare));
+        assert_eq!(
+            read.chunk(second.position()).unwrap().unwrap().blocks(),
+            second.blocks()
+        );
+    }
+
+    // Version 2 tables have checksums, damaged records are caught there as well.
+    let mut bytes = old_region(2, &[&second]);
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    let mut read = Region::read(&bytes).unwrap();
+    assert_eq!(read.take_corrupted().len(), 1);
+    assert_eq!(read.chunk(second.position()).unwrap(), None);
+    assert!(Region::read(&old_region(REGION_VERSION + 1, &[])).is_err());
+}
@@ -0,0 +1,171 @@
+use fyrox::core::algebra::Vector3;
+use mine64::block::Block;
+use mine64::schematic::{Placement, Schematic, SCHEMATIC_VERSION};
+
+/// 3 wide, 1 high and 2 deep, with stone in the corner at the origin and sand next to it on x.
+fn corner() -> Schematic {
+    let mut schematic = Schematic::new(Vector3::new(3, 1, 2));
+    schematic.set(Vector3::new(0, 0, 0), Block::Stone);
+    schematic.set(Vector3::new(1, 0, 0), Block::Sand);
+    schematic
+}
+
+fn placed(schematic: &Schematic, placement: Placement) -> Vec<(Vector3<i32>, Block)> {
+    let mut blocks: Vec<_> = schematic.blocks(Vector3::zeros(), placement).collect();
+    blocks.sort_by_key(|(position, _)| (position.x, position.y, position.z));
+    blocks
+}
+
+#[test]
+fn quarter_turns_go_from_x_towards_z() {
+    let schematic = corner();
+    let turned = Placement {
+        quarter_turns: 1,
+        mirrored: false,
+    };
+    assert_eq!(schematic.placed_size(turned), Vector3::new(2, 1, 3));
+    assert_eq!(
+        placed(&schematic, turned),
+        vec![
+            (Vector3::new(1, 0, 0), Block::Stone),
+            (Vector3::new(1, 0, 1), Block::Sand),
+        ]
+    );
+
+    let half = Placement {
+        quarter_turns: 2,
+        mirrored: false,
+    };
+    assert_eq!(schematic.placed_size(half), schematic.size());
+    assert_eq!(
+        placed(&schematic, half),
+        vec![
+            (Vector3::new(1, 0, 1), Block::Sand),
+            (Vector3::new(2, 0, 1), Block::Stone),
+        ]
+    );
+
+    // Four quarter turns are the schematic as it was, like no turn at all.
+    let full = Placement {
+        quarter_turns: 4,
+        mirrored: false,
+    };
+    assert_eq!(
+        placed(&schematic, full),
+        placed(&schematic, Placement::default())
+    );
+}
+
+#[test]
+fn mirroring_flips_x_before_turning() {
+    let schematic = corner();
+    let mirrored = Placement {
+        quarter_turns: 0,
+        mirrored: true,
+    };
+    assert_eq!(
+        placed(&schematic, mirrored),
+        vec![
+            (Vector3::new(1, 0, 0), Block::Sand),
+            (Vector3::new(2, 0, 0), Block::Stone),
+        ]
+    );
+
+    let turned = Placement {
+        quarter_turns: 1,
+        mirrored: true,
+    };
+    assert_eq!(
+        placed(&schematic, turned),
+        vec![
+            (Vector3::new(1, 0, 1), Block::Sand),
+            (Vector3::new(1, 0, 2), Block::Stone),
+        ]
+    );
+
+    // Every placement keeps the blocks in the placed box.
+    for index in 0..8 {
+        let placement = Placement::from_variation(index as f32 / 8.0);
+        let size = schematic.placed_size(placement);
+        for (position, _) in schematic.blocks(Vector3::zeros(), placement) {
+            assert!((0..3).all(|axis| (0..size[axis]).contains(&position[axis])));
+        }
+    }
+}
+
+#[test]
+fn encoded_schematics_decode_to_the_same_blocks() {
+    let mut schematic = Schematic::new(Vector3::new(5, 4, 3));
+    for (index, block) in Block::ALL.iter().enumerate().skip(1) {
+        let index = index as i32;
+        schematic.set(Vector3::new(index % 5, index / 15, index / 5 % 3), *block);
+    }
+    let decoded = Schematic::decode(&schematic.encode()).unwrap();
+    assert_eq!(decoded, schematic);
+
+    let empty = Schematic::new(Vector3::zeros());
+    assert_eq!(Schematic::decode(&empty.encode()).unwrap(), empty);
+}
+
+#[test]
+fn sizes_with_too_many_blocks_are_rejected() {
+    let mut bytes = b"M64S".to_vec();
+    bytes.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+    for _ in 0..3 {
+        bytes.extend_from_slice(&u16::MAX.to_le_bytes());
+    }
+    assert!(Schematic::decode(&bytes).is_err());
+
+    let mut encoded = corner().encode();
+    encoded[8] = 4;
+    assert!(Schematic::decode(&encoded).is_err());
+    assert!(Schematic::decode(b"M64").is_err());
+}
+
+/// Chunk of a `.vox` file without children.
+fn vox_chunk(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut bytes = id.to_vec();
+    bytes.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+/// MagicaVoxel file with a single model of the size and voxels as x, y, z and palette index.
+fn vox(size: [u32; 3], voxels: &[[u8; 4]]) -> Vec<u8> {
+    let size: Vec<u8> = size.iter().flat_map(|value| value.to_le_bytes()).collect();
+    let mut xyzi = (voxels.len() as u32).to_le_bytes().to_vec();
+    xyzi.extend(voxels.iter().flatten());
+    let mut children = vox_chunk(b"SIZE", &size);
+    children.extend(vox_chunk(b"XYZI", &xyzi));
+    children.extend(vox_chunk(b"RGBA", &[0; 1024]));
+
+    let mut bytes = b"VOX ".to_vec();
+    bytes.extend_from_slice(&150u32.to_le_bytes());
+    bytes.extend_from_slice(b"MAIN");
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&(children.len() as u32).to_le_bytes());
+    bytes.extend(children);
+    bytes
+}
+
+#[test]
+fn vox_files_import_with_z_up_and_palette_indices_as_blocks() {
+    let bytes = vox(
+        [2, 3, 4],
+        &[[1, 2, 3, Block::Stone.id()], [0, 0, 0, Block::Planks.id()]],
+    );
+    let schematic = Schematic::decode(&bytes).unwrap();
+    assert_eq!(schematic.size(), Vector3::new(2, 4, 3));
+    assert_eq!(schematic.get(Vector3::new(1, 3, 2)), Block::Stone);
+    assert_eq!(schematic.get(Vector3::new(0, 0, 0)), Block::Planks);
+    assert_eq!(
+        schematic
+            .blocks(Vector3::zeros(), Placement::default())
+            .count(),
+        2
+    );
+
+    assert!(Schematic::decode(&vox([2, 2, 2], &[[0, 0, 0, 255]])).is_err());
+    assert!(Schematic::decode(&vox([300, 1, 1], &[])).is_err());
+}
@@ -0,0 +1,127 @@
+use fyrox::core::algebra::{UnitQuaternion, Vector3};
+use mine64::block::Block;
+use mine64::schematic::Schematic;
+use mine64::screenshot::{encode_png, render, Image};
+
+const SKY: [u8; 3] = [150, 200, 240];
+
+/// Wall in front of an eye at the origin that looks along z, stone on its left and sand on its
+/// right.
+fn wall() -> Schematic {
+    let mut schematic = Schematic::new(Vector3::new(16, 16, 16));
+    for y in 0..16 {
+        for z in 0..16 {
+            schematic.set(Vector3::new(10, y, z), Block::Stone);
+            schematic.set(Vector3::new(6, y, z), Block::Sand);
+        }
+    }
+    schematic
+}
+
+#[test]
+fn screenshots_show_the_blocks_the_camera_looks_at() {
+    let eye = Vector3::new(8.5, 8.5, 0.5);
+    let image = render(
+        &wall(),
+        eye,
+        UnitQuaternion::identity(),
+        90.0,
+        (32, 16),
+        100.0,
+    );
+    assert_eq!(image.pixels.len(), 32 * 16);
+    // The camera has x to its left, so the stone at a larger x is on the left of the picture.
+    let stone = Block::Stone.color();
+    let sand = Block::Sand.color();
+    let left = image.pixel(0, 8);
+    let right = image.pixel(31, 8);
+    assert!(left[0] < stone[0] && left[0] > 0, "{left:?} is lit stone");
+    assert_eq!(left[0], left[1]);
+    assert!(right[0] > right[2], "{right:?} is lit sand");
+    assert!(right[0] <= sand[0]);
+    // Between the walls the ray leaves the schematic and hits nothing.
+    assert_eq!(image.pixel(16, 8), SKY);
+
+    // Turned around there is nothing to see.
+    let behind = UnitQuaternion::from_euler_angles(0.0, std::f32::consts::PI, 0.0);
+    let eye = Vector3::new(8.5, 8.5, -0.5);
+    let image = render(&wall(), eye, behind, 90.0, (8, 4), 100.0);
+    assert!(image.pixels.iter().all(|pixel| *pixel == SKY));
+}
+
+#[test]
+fn distant_blocks_fade_into_the_sky() {
+    let eye = Vector3::new(8.5, 8.5, 0.5);
+    let near = render(
+        &wall(),
+        eye,
+        UnitQuaternion::identity(),
+        90.0,
+        (32, 16),
+        100.0,
+    );
+    let far = render(
+        &wall(),
+        eye,
+        UnitQuaternion::identity(),
+        90.0,
+        (32, 16),
+        3.0,
+    );
+    assert_ne!(near.pixel(0, 8), far.pixel(0, 8));
+    assert_eq!(far.pixel(16, 8), SKY);
+}
+
+/// Chunks of a PNG as their kind and data.
+fn png_chunks(mut bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut chunks = Vec::new();
+    while !bytes.is_empty() {
+        let length = u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize;
+        let kind = bytes[4..8].try_into().unwrap();
+        chunks.push((kind, bytes[8..8 + length].to_vec()));
+        bytes = &bytes[12 + length..];
+    }
+    chunks
+}
+
+#[test]
+fn screenshots_are_stored_pngs() {
+    let width = 300;
+    let image = Image {
+        width,
+        height: 80,
+        pixels: (0..width * 80)
+            .map(|index| [index as u8, (index / 256) as u8, 7])
+            .collect(),
+    };
+    let png = encode_png(&image);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let chunks = png_chunks(&png[8..]);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+    let header = &chunks[0].1;
+    assert_eq!(&header[..8], [0, 0, 1, 44, 0, 0, 0, 80]);
+    assert_eq!(&header[8..], [8, 2, 0, 0, 0]);
+
+    // Rows of the image behind a filter byte each, split into stored deflate blocks.
+    let zlib = &chunks[1].1;
+    let mut data = &zlib[2..zlib.len() - 4];
+    let mut rows = Vec::new();
+    loop {
+        let last = data[0] == 1;
+        let length = u16::from_le_bytes([data[1], data[2]]) as usize;
+        assert_eq!(u16::from_le_bytes([data[3], data[4]]), !(length as u16));
+        rows.extend_from_slice(&data[5..5 + length]);
+        data = &data[5 + length..];
+        if last {
+            break;
+        }
+    }
+    assert!(data.is_empty());
+    let expected: Vec<u8> = image
+        .pixels
+        .chunks(width as usize)
+        .flat_map(|row| std::iter::once(0).chain(row.iter().flatten().copied()))
+        .collect();
+    assert_eq!(rows, expected);
+}
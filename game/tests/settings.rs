@@ -0,0 +1,199 @@
+use std::time::{Duration, Instant};
+
+use fyrox::keyboard::KeyCode;
+use mine64::frame_limiter::FrameLimiter;
+use mine64::settings::{
+    key_character, strip_key_character, Action, GameSettings, Setting, BINDABLE_KEYS,
+};
+use mine64::storage::{MemoryStorage, Storage};
+
+#[test]
+fn steps_stay_in_range() {
+    let mut settings = GameSettings::default();
+    for _ in 0..100 {
+        settings.step(Setting::ViewDistance, 1);
+        settings.step(Setting::Fov, -1);
+    }
+    assert_eq!(settings.view_distance, 64);
+    assert_eq!(settings.fov, 50.0);
+    assert_eq!(settings.label(Setting::Fov), "Field of view: 50");
+
+    for _ in 0..7 {
+        settings.step(Setting::MouseSensitivity, -1);
+    }
+    assert_eq!(
+        settings.label(Setting::MouseSensitivity),
+        "Mouse sensitivity: 0.3"
+    );
+    settings.step(Setting::MouseSensitivity, -5);
+    assert!((settings.mouse_sensitivity - 0.1).abs() < 1e-6);
+
+    settings.step(Setting::Shadows, -1);
+    assert!(!settings.shadows);
+    assert_eq!(settings.label(Setting::Shadows), "Shadows: off");
+}
+
+#[test]
+fn binding_a_used_key_swaps_the_keys() {
+    let mut settings = GameSettings::default();
+    let bindings = &mut settings.key_bindings;
+    bindings.bind(Action::Forward, KeyCode::KeyS);
+    assert_eq!(bindings.key(Action::Forward), KeyCode::KeyS);
+    assert_eq!(bindings.key(Action::Backward), KeyCode::KeyW);
+    assert_eq!(bindings.action(KeyCode::KeyW), Some(Action::Backward));
+
+    bindings.bind(Action::Console, KeyCode::F1);
+    assert_eq!(bindings.action(KeyCode::Backquote), None);
+    assert_eq!(
+        settings.label(Setting::Binding(Action::Console)),
+        "Console: F1"
+    );
+}
+
+#[test]
+fn saved_settings_load_back() {
+    let storage = MemoryStorage::default();
+    assert_eq!(
+        GameSettings::load(&storage).unwrap(),
+        GameSettings::default()
+    );
+
+    let mut settings = GameSettings::default();
+    settings.step(Setting::Fov, 2);
+    settings.step(Setting::Fullscreen, 1);
+    settings.key_bindings.bind(Action::Up, KeyCode::Space);
+    settings.save(&storage).unwrap();
+    assert_eq!(GameSettings::load(&storage).unwrap(), settings);
+}
+
+#[test]
+fn missing_settings_take_defaults_and_values_are_clamped() {
+    let storage = MemoryStorage::default();
+    storage
+        .write(
+            "settings.ron",
+            b"(view_distance: 1000, key_bindings: (forward: \"KeyI\"))",
+        )
+        .unwrap();
+    let settings = GameSettings::load(&storage).unwrap();
+    assert_eq!(settings.view_distance, 64);
+    assert_eq!(settings.key_bindings.forward, KeyCode::KeyI);
+    assert_eq!(settings.key_bindings.backward, KeyCode::KeyS);
+    assert_eq!(settings.fov, GameSettings::default().fov);
+
+    storage
+        .write("settings.ron", b"(key_bindings: (forward: \"Pause\"))")
+        .unwrap();
+    assert!(GameSettings::load(&storage).is_err());
+}
+
+#[test]
+fn hotbar_and_debug_keys_are_not_bindable() {
+    assert!(!BINDABLE_KEYS.contains(&KeyCode::Digit1));
+    assert!(!BINDABLE_KEYS.contains(&KeyCode::F5));
+    assert!(BINDABLE_KEYS.contains(&KeyCode::Digit0));
+    let storage = MemoryStorage::default();
+    storage
+        .write("settings.ron", b"(key_bindings: (console: \"Digit3\"))")
+        .unwrap();
+    assert!(GameSettings::load(&storage).is_err());
+}
+
+#[test]
+fn keys_type_their_characters() {
+    assert_eq!(key_character(KeyCode::Backquote), Some('`'));
+    assert_eq!(key_character(KeyCode::KeyQ), Some('q'));
+    assert_eq!(key_character(KeyCode::Digit0), Some('0'));
+    assert_eq!(key_character(KeyCode::Slash), Some('/'));
+    assert_eq!(key_character(KeyCode::F1), None);
+}
+
+#[test]
+fn console_bound_to_a_letter_types_it() {
+    let mut settings = GameSettings::default();
+    let bindings = &mut settings.key_bindings;
+    assert!(bindings.bind(Action::Console, KeyCode::KeyT));
+    // The key types a character, so it doesn't close the open console.
+    assert!(key_character(bindings.console).is_some());
+
+    // Opening the console typed the first t, like the console only the first text is stripped.
+    let mut opening = true;
+    let mut line = String::new();
+    for character in "ttp 1 -2 3".chars() {
+        let mut text = line.clone();
+        text.push(character);
+        let stripped = strip_key_character(&text, bindings.console);
+        line = stripped
+            .filter(|_| std::mem::take(&mut opening))
+            .unwrap_or(text);
+    }
+    assert_eq!(line, "tp 1 -2 3");
+    assert_eq!(
+        strip_key_character("tTp", KeyCode::KeyT).unwrap(),
+        "Tp",
+        "only the character of the keypress that opened the console is left out"
+    );
+    assert_eq!(strip_key_character("tp", KeyCode::F1), None);
+}
+
+#[test]
+fn console_keeps_the_keys_it_uses_itself() {
+    let mut settings = GameSettings::default();
+    let bindings = &mut settings.key_bindings;
+    assert!(!bindings.bind(Action::Console, KeyCode::Enter));
+    assert!(!bindings.bind(Action::Console, KeyCode::ArrowUp));
+    assert_eq!(bindings.console, KeyCode::Backquote);
+
+    // Nor through the swap of the key another action had.
+    assert!(bindings.bind(Action::Up, KeyCode::Tab));
+    assert!(!bindings.bind(Action::Up, KeyCode::Backquote));
+    assert_eq!(bindings.up, KeyCode::Tab);
+    assert_eq!(bindings.console, KeyCode::Backquote);
+
+    let storage = MemoryStorage::default();
+    storage
+        .write("settings.ron", b"(key_bindings: (console: \"Enter\"))")
+        .unwrap();
+    let settings = GameSettings::load(&storage).unwrap();
+    assert_eq!(settings.key_bindings.console, KeyCode::Backquote);
+    assert_eq!(settings.key_bindings.action(KeyCode::Enter), None);
+}
+
+#[test]
+fn frame_cap_steps_up_to_no_cap_and_is_clamped() {
+    let mut settings = GameSettings::default();
+    assert_eq!(settings.label(Setting::MaxFps), "Frame cap: off");
+    settings.step(Setting::MaxFps, -1);
+    assert_eq!(settings.max_fps, 240);
+    settings.step(Setting::MaxFps, -100);
+    assert_eq!(settings.label(Setting::MaxFps), "Frame cap: 30 fps");
+    settings.step(Setting::MaxFps, 1);
+    assert_eq!(settings.max_fps, 60);
+    settings.step(Setting::MaxFps, 100);
+    assert_eq!(settings.max_fps, 0);
+
+    let storage = MemoryStorage::default();
+    storage.write("settings.ron", b"(max_fps: 5)").unwrap();
+    assert_eq!(GameSettings::load(&storage).unwrap().max_fps, 30);
+    storage.write("settings.ron", b"(max_fps: 1000)").unwrap();
+    assert_eq!(GameSettings::load(&storage).unwrap().max_fps, 240);
+    storage.write("settings.ron", b"(max_fps: 0)").unwrap();
+    assert_eq!(GameSettings::load(&storage).unwrap().max_fps, 0);
+}
+
+#[test]
+fn frames_wait_for_the_frame_cap() {
+    let mut limiter = FrameLimiter::default();
+    let start = Instant::now();
+    let frame = Duration::from_millis(20);
+    assert_eq!(limiter.delay(start, 50), Duration::ZERO);
+    // A frame right after the first waits out the rest of its time.
+    let early = start + Duration::from_millis(5);
+    assert_eq!(limiter.delay(early, 50), Duration::from_millis(15));
+    // A late frame starts at once, and the next one a whole frame after it.
+    let late = start + Duration::from_millis(100);
+    assert_eq!(limiter.delay(late, 50), Duration::ZERO);
+    assert_eq!(limiter.delay(late, 50), frame);
+    assert_eq!(limiter.delay(late, 0), Duration::ZERO);
+    assert_eq!(limiter.delay(late, 0), Duration::ZERO);
+}
@@ -0,0 +1,396 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use fyrox::core::algebra::Vector3;
+use mine64::block::Block;
+use mine64::chunk::{chunk_position, BlockSource};
+use mine64::generation_settings::GenerationSettings;
+use mine64::item::{ItemStack, ITEM_SIZE};
+use mine64::player::{GameMode, PlayerInput, MAX_AIR, MAX_HEALTH};
+use mine64::simulation::Simulation;
+use mine64::storage::{MemoryStorage, Storage};
+
+const SEED: u32 = 7;
+
+const VIEW_DISTANCE: i32 = 2;
+
+fn simulation(storage: impl Storage + 'static) -> Simulation {
+    Simulation::new(
+        Box::new(storage),
+        SEED,
+        GenerationSettings::default(),
+        VIEW_DISTANCE,
+    )
+}
+
+/// Steps without moving until every chunk around the player is loaded.
+fn load(simulation: &mut Simulation) {
+    let start = Instant::now();
+    simulation.step(0.0, &PlayerInput::default());
+    while !simulation.is_loaded() {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "chunks did not load in time"
+        );
+        std::thread::sleep(Duration::from_millis(1));
+        simulation.step(0.0, &PlayerInput::default());
+    }
+}
+
+/// Steps for `seconds` in steps of a twentieth of a second.
+fn run(simulation: &mut Simulation, seconds: f32) -> Vec<ItemStack> {
+    let mut collected = Vec::new();
+    for _ in 0..(seconds * 20.0) as usize {
+        collected.extend(simulation.step(0.05, &PlayerInput::default()).collected);
+    }
+    collected
+}
+
+/// Clears the column above the ground and puts the player there, looking straight down. The
+/// player flies in creative, so it stays there.
+fn hover(simulation: &mut Simulation, x: i32, z: i32, height: f32) -> i32 {
+    let ground = simulation.generator().height(x, z);
+    for y in ground + 1..=ground + height as i32 {
+        simulation.set_block(Vector3::new(x, y, z), Block::Air);
+    }
+    let player = simulation.player_mut();
+    player.set_game_mode(GameMode::Creative);
+    player.set_position(Vector3::new(
+        x as f32 + 0.5,
+        ground as f32 + height,
+        z as f32 + 0.5,
+    ));
+    player.turn(0.0, 90.0f32.to_radians());
+    ground
+}
+
+#[test]
+fn generates_chunks_around_player() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+
+    let center = simulation.center();
+    let loaded = simulation.world().chunks().count();
+    assert!(loaded > 0);
+    assert!(simulation.world().chunks().all(|chunk| {
+        let offset = chunk.position() - center;
+        offset.x * offset.x + offset.z * offset.z <= VIEW_DISTANCE * VIEW_DISTANCE
+    }));
+
+    let spawn = simulation.metadata().spawn.map(|v| v.floor() as i32);
+    let ground = simulation.generator().height(spawn.x, spawn.z);
+    assert_ne!(
+        simulation
+            .world()
+            .block(Vector3::new(spawn.x, ground, spawn.z)),
+        Block::Air
+    );
+}
+
+#[test]
+fn raycast_sees_block_edits() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    let ground = hover(&mut simulation, 0, 0, 8.5);
+
+    let hit = simulation.target(16.0).expect("ground is below the player");
+    assert_eq!(hit.block, Vector3::new(0, ground, 0));
+
+    let pillar = Vector3::new(0, ground + 4, 0);
+    let changed = simulation.set_block(pillar, Block::Stone);
+    assert_eq!(changed, Some(chunk_position(pillar)));
+    let hit = simulation
+        .target(16.0)
+        .expect("the new block is below the player");
+    assert_eq!(hit.block, pillar);
+    assert!((hit.position.y - (pillar.y + 1) as f32).abs() < 1e-4);
+
+    simulation.set_block(pillar, Block::Air);
+    simulation.set_block(Vector3::new(0, ground, 0), Block::Air);
+    let hit = simulation
+        .target(16.0)
+        .expect("there is ground under the hole");
+    assert!(hit.block.y < ground);
+
+    // Far away chunks are not loaded, edits there are refused rather than making up a chunk.
+    let far = Vector3::new(10_000, ground, 0);
+    assert_eq!(simulation.set_block(far, Block::Stone), None);
+    assert!(simulation.world().chunk(chunk_position(far)).is_none());
+}
+
+#[test]
+fn player_flies_and_chunks_follow() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    let start = simulation.player().position();
+    simulation.player_mut().set_game_mode(GameMode::Creative);
+    // Nothing is in the way.
+    let (min, max) = simulation.player().bounding_box();
+    for y in min.y.floor() as i32..=max.y.floor() as i32 {
+        for z in 0..8 {
+            simulation.set_block(Vector3::new(0, y, z), Block::Air);
+        }
+    }
+
+    let forward = PlayerInput {
+        forward: true,
+        ..Default::default()
+    };
+    for _ in 0..10 {
+        simulation.step(0.1, &forward);
+    }
+    let moved = simulation.player().position() - start;
+    assert!(
+        (moved - Vector3::new(0.0, 0.0, 6.0)).norm() < 1e-3,
+        "{moved:?}"
+    );
+    assert!((simulation.metadata().time - 1.0).abs() < 1e-4);
+
+    let far = simulation.player().position() + Vector3::new(0.0, 0.0, 100.0);
+    simulation.player_mut().set_position(far);
+    let changes = simulation.step(0.0, &PlayerInput::default());
+    assert!(!changes.unloaded.is_empty());
+    assert!(changes
+        .unloaded
+        .iter()
+        .all(|position| simulation.world().chunk(*position).is_none()));
+    load(&mut simulation);
+    let center = simulation.center();
+    assert!(simulation.world().chunk(center).is_some());
+}
+
+#[test]
+fn edits_survive_saving_and_reopening() {
+    let storage = Arc::new(MemoryStorage::default());
+    let mut first = simulation(storage.clone());
+    load(&mut first);
+    let ground = hover(&mut first, 3, 5, 4.0);
+    let edited = Vector3::new(3, ground + 1, 5);
+    first.set_block(edited, Block::Planks);
+    first.step(0.0, &PlayerInput::default());
+    let position = first.player().position();
+    first.save();
+    drop(first);
+
+    let mut second = simulation(storage);
+    assert_eq!(second.player().position(), position);
+    load(&mut second);
+    assert_eq!(second.world().block(edited), Block::Planks);
+}
+
+#[test]
+fn broken_blocks_drop_items_that_fall_and_are_collected() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    let ground = hover(&mut simulation, 0, 0, 6.0);
+    let broken = Vector3::new(0, ground, 0);
+    let block = simulation.world().block(broken);
+
+    // Only survival drops the broken block, the player hovers in creative again right after.
+    simulation.player_mut().set_game_mode(GameMode::Survival);
+    assert_eq!(simulation.break_block(broken), Some(chunk_position(broken)));
+    simulation.player_mut().set_game_mode(GameMode::Creative);
+    assert_eq!(simulation.world().block(broken), Block::Air);
+    assert_eq!(simulation.break_block(broken), None);
+    // Breaking in creative drops nothing, the item below is the only one.
+    let beside = Vector3::new(1, ground, 0);
+    simulation.set_block(beside, Block::Stone);
+    assert!(simulation.break_block(beside).is_some());
+    assert!(run(&mut simulation, 3.0).is_empty());
+    let [item] = simulation.items() else {
+        panic!("{:?}", simulation.items());
+    };
+    assert_eq!(item.stack, ItemStack::new(block, 1));
+    assert_eq!(item.velocity, Vector3::zeros());
+    // Lies on the block under the hole.
+    assert!((item.position.y - (ground as f32 + ITEM_SIZE / 2.0)).abs() < 1e-3);
+    assert_eq!(item.position.map(|v| v.floor() as i32), broken);
+
+    let planks = ItemStack::new(Block::Planks, 2);
+    let above = item.position + Vector3::new(0.0, 0.5, 0.0);
+    simulation.drop_item(planks, above, Vector3::zeros());
+    simulation.drop_item(planks, above, Vector3::zeros());
+    run(&mut simulation, 1.0);
+    assert_eq!(simulation.items().len(), 2, "the planks merged");
+    assert!(simulation
+        .items()
+        .iter()
+        .any(|item| item.stack == ItemStack::new(Block::Planks, 4)));
+
+    let position = simulation.items()[0].position;
+    simulation
+        .player_mut()
+        .set_position(position + Vector3::new(0.0, 1.0, 0.0));
+    let mut collected = run(&mut simulation, 0.1);
+    collected.sort_by_key(|stack| stack.block.id());
+    let mut expected = vec![ItemStack::new(block, 1), ItemStack::new(Block::Planks, 4)];
+    expected.sort_by_key(|stack| stack.block.id());
+    assert_eq!(collected, expected);
+    assert!(simulation.items().is_empty());
+}
+
+#[test]
+fn items_at_the_feet_are_collected() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    // Stands on the ground with an item lying a block to the side, out of reach of the eyes.
+    let ground = hover(&mut simulation, 3, 5, 2.625);
+    for y in ground + 1..=ground + 3 {
+        simulation.set_block(Vector3::new(4, y, 5), Block::Air);
+    }
+    simulation.set_block(Vector3::new(4, ground, 5), Block::Stone);
+    let planks = ItemStack::new(Block::Planks, 1);
+    simulation.drop_item(
+        planks,
+        Vector3::new(4.5, ground as f32 + 1.5, 5.5),
+        Vector3::zeros(),
+    );
+    assert_eq!(run(&mut simulation, 1.0), [planks]);
+    assert!(simulation.items().is_empty());
+}
+
+#[test]
+fn dropped_items_survive_unloading_and_saving() {
+    let storage = Arc::new(MemoryStorage::default());
+    let mut first = simulation(storage.clone());
+    load(&mut first);
+    let ground = hover(&mut first, 3, 5, 4.0);
+    let planks = ItemStack::new(Block::Planks, 3);
+    first.drop_item(
+        planks,
+        Vector3::new(3.5, ground as f32 + 1.5, 5.5),
+        Vector3::zeros(),
+    );
+    run(&mut first, 2.0);
+    let item = first.items()[0].clone();
+
+    let start = first.player().position();
+    first
+        .player_mut()
+        .set_position(start + Vector3::new(0.0, 0.0, 100.0));
+    first.step(0.0, &PlayerInput::default());
+    assert!(first.items().is_empty(), "the item left with its chunk");
+    first.player_mut().set_position(start);
+    load(&mut first);
+    assert_eq!(first.items(), std::slice::from_ref(&item));
+    first.save();
+    drop(first);
+
+    let mut second = simulation(storage);
+    load(&mut second);
+    assert_eq!(second.items(), [item]);
+}
+
+#[test]
+fn collected_items_are_placed_from_the_hotbar_and_saved() {
+    let storage = Arc::new(MemoryStorage::default());
+    let mut first = simulation(storage.clone());
+    load(&mut first);
+    let ground = hover(&mut first, 3, 5, 4.0);
+    let planks = ItemStack::new(Block::Planks, 2);
+    let above = Vector3::new(3.5, ground as f32 + 1.5, 5.5);
+    first.drop_item(planks, above, Vector3::zeros());
+    assert!(run(&mut first, 1.0).is_empty(), "the item is out of reach");
+    first
+        .player_mut()
+        .set_position(above + Vector3::new(0.0, 0.5, 0.0));
+    let collected = run(&mut first, 0.1);
+    assert_eq!(collected, [ItemStack::new(Block::Planks, 2)]);
+    assert_eq!(first.player().inventory().count(Block::Planks), 2);
+
+    let hit = first.target(16.0).expect("ground is below the player");
+    assert_eq!(hit.block, Vector3::new(3, ground, 5));
+    // Only survival uses up the placed blocks.
+    first.player_mut().set_game_mode(GameMode::Survival);
+    first.player_mut().inventory_mut().select(1);
+    assert_eq!(first.place_block(&hit), None, "nothing is selected");
+    first.player_mut().inventory_mut().select(0);
+    let placed = Vector3::new(3, ground + 1, 5);
+    assert_eq!(first.place_block(&hit), Some(placed));
+    assert_eq!(first.world().block(placed), Block::Planks);
+    assert_eq!(first.place_block(&hit), None, "the place is taken");
+    assert_eq!(
+        first.player().inventory().selected_stack(),
+        Some(ItemStack::new(Block::Planks, 1))
+    );
+    first.save();
+    drop(first);
+
+    let second = simulation(storage);
+    assert_eq!(second.player().inventory().count(Block::Planks), 1);
+}
+
+#[test]
+fn player_runs_out_of_air_under_water_and_drowns() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    // Standing on the ground, creative can't drown.
+    let ground = hover(&mut simulation, 3, 5, 2.625);
+    simulation.player_mut().set_game_mode(GameMode::Survival);
+    let eyes = Vector3::new(3, ground + 2, 5);
+    simulation.set_block(eyes, Block::Water);
+
+    run(&mut simulation, 5.0);
+    assert!(simulation.player().air() < MAX_AIR);
+    assert_eq!(simulation.player().health(), MAX_HEALTH, "air is left");
+    run(&mut simulation, 7.0);
+    assert_eq!(simulation.player().air(), 0.0);
+    let health = simulation.player().health();
+    assert!(health < MAX_HEALTH);
+
+    simulation.set_block(eyes, Block::Air);
+    run(&mut simulation, 1.0);
+    assert!(simulation.player().air() > 0.0);
+    assert!(simulation.player().health() > health);
+
+    simulation.set_block(eyes, Block::Water);
+    run(&mut simulation, MAX_AIR + MAX_HEALTH);
+    let player = simulation.player();
+    let spawn = simulation.metadata().spawn;
+    assert_eq!(player.position().xz(), spawn.xz(), "respawned");
+    assert!(player.position().y <= spawn.y, "fell from the spawn");
+    assert_eq!(player.health(), MAX_HEALTH);
+}
+
+#[test]
+fn player_falls_walks_into_walls_and_jumps() {
+    let mut simulation = simulation(MemoryStorage::default());
+    load(&mut simulation);
+    let ground = hover(&mut simulation, 0, 0, 6.0);
+    simulation.player_mut().set_game_mode(GameMode::Survival);
+    // A wall two blocks high right in front, along z.
+    for y in ground + 1..=ground + 2 {
+        simulation.set_block(Vector3::new(0, y, 1), Block::Stone);
+    }
+
+    run(&mut simulation, 2.0);
+    let player = simulation.player();
+    assert!(player.is_on_ground());
+    let (min, _) = player.bounding_box();
+    assert!((min.y - (ground + 1) as f32).abs() < 1e-4, "{min:?}");
+
+    let forward = PlayerInput {
+        forward: true,
+        ..Default::default()
+    };
+    for _ in 0..20 {
+        simulation.step(0.05, &forward);
+    }
+    let (_, max) = simulation.player().bounding_box();
+    assert!((max.z - 1.0).abs() < 1e-4, "stopped at the wall: {max:?}");
+
+    let jump = PlayerInput {
+        up: true,
+        ..Default::default()
+    };
+    simulation.step(0.05, &jump);
+    assert!(!simulation.player().is_on_ground());
+    assert!(simulation.player().velocity().y > 0.0);
+    let mut highest = simulation.player().position().y;
+    for _ in 0..40 {
+        simulation.step(0.05, &PlayerInput::default());
+        highest = highest.max(simulation.player().position().y);
+    }
+    assert!(highest > min.y + 1.6 + 1.0, "jumped higher than a block");
+    assert!(simulation.player().is_on_ground(), "landed again");
+}
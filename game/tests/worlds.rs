@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use mine64::generation_settings::GenerationSettings;
+use mine64::player::PlayerInput;
+use mine64::simulation::Simulation;
+use mine64::storage::{MemoryStorage, Storage};
+use mine64::worlds::{list_worlds, new_world_name, parse_seed, WorldInfo};
+
+/// Metadata of a new world created from the seed.
+fn metadata(seed: u32) -> Vec<u8> {
+    let storage = Arc::new(MemoryStorage::default());
+    let mut simulation = Simulation::new(
+        Box::new(storage.clone()),
+        seed,
+        GenerationSettings::default(),
+        1,
+    );
+    simulation.step(1.5, &PlayerInput::default());
+    simulation.save();
+    storage
+        .read("world.meta")
+        .unwrap()
+        .expect("metadata is saved")
+}
+
+#[test]
+fn lists_saved_worlds_by_name() {
+    let saves = MemoryStorage::default();
+    saves.write("second/world.meta", &metadata(2)).unwrap();
+    saves.write("first/world.meta", &metadata(1)).unwrap();
+    saves.write("first/r.0.0.region", &[1, 2, 3]).unwrap();
+    saves.write("broken/world.meta", b"not metadata").unwrap();
+    saves.write("empty/r.0.0.region", &[1, 2, 3]).unwrap();
+    saves.write("loose.meta", &metadata(3)).unwrap();
+
+    let worlds = list_worlds(&saves);
+    assert_eq!(
+        worlds,
+        [
+            WorldInfo {
+                name: "first".to_string(),
+                seed: 1,
+                time: 1.5,
+            },
+            WorldInfo {
+                name: "second".to_string(),
+                seed: 2,
+                time: 1.5,
+            },
+        ]
+    );
+}
+
+#[test]
+fn new_worlds_get_free_names() {
+    let world = |name: &str| WorldInfo {
+        name: name.to_string(),
+        seed: 0,
+        time: 0.0,
+    };
+    assert_eq!(new_world_name(&[]), "world");
+    assert_eq!(new_world_name(&[world("other")]), "world");
+    assert_eq!(new_world_name(&[world("world")]), "world-2");
+    assert_eq!(
+        new_world_name(&[world("world"), world("world-2"), world("world-4")]),
+        "world-3"
+    );
+}
+
+#[test]
+fn seeds_are_numbers_or_hashed_text() {
+    assert_eq!(parse_seed("64"), 64);
+    assert_eq!(parse_seed(" 4294967295 "), u32::MAX);
+    assert_eq!(parse_seed("mountains"), parse_seed("mountains"));
+    assert_ne!(parse_seed("mountains"), parse_seed("mountain"));
+}